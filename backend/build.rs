@@ -0,0 +1,75 @@
+//! Builds the frontend into the binary when the `embed-frontend` feature is enabled.
+//!
+//! Every file of the built frontend is listed in `$OUT_DIR/assets.rs`, in sorted order, with its
+//! contents included and a hash of them to use as an ETag. The frontend is read from
+//! `../frontend/build`, or from `STATE_MACHINA_ASSETS` if it is set.
+
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Finds every file below a directory, along with its path relative to the directory.
+fn walk(root: &Path, directory: &Path, files: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root)
+                .unwrap()
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=STATE_MACHINA_ASSETS");
+    if env::var_os("CARGO_FEATURE_EMBED_FRONTEND").is_none() {
+        return;
+    }
+
+    let root = match env::var_os("STATE_MACHINA_ASSETS") {
+        Some(root) => PathBuf::from(root),
+        None => Path::new(&env::var_os("CARGO_MANIFEST_DIR").unwrap()).join("../frontend/build"),
+    };
+    if !root.join("index.html").is_file() {
+        panic!(
+            "the frontend must be built before it can be embedded, but {} does not exist",
+            root.join("index.html").display()
+        );
+    }
+    let root = root.canonicalize().unwrap();
+    println!("cargo:rerun-if-changed={}", root.display());
+
+    let mut files = Vec::new();
+    walk(&root, &root, &mut files).unwrap();
+    files.sort();
+
+    let output = Path::new(&env::var_os("OUT_DIR").unwrap()).join("assets.rs");
+    let mut output = File::create(output).unwrap();
+    writeln!(output, "static ASSETS: &[Asset] = &[").unwrap();
+    for (relative, path) in files {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let mut contents = Vec::new();
+        File::open(&path).unwrap().read_to_end(&mut contents).unwrap();
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&contents);
+        writeln!(
+            output,
+            "    Asset {{ path: {:?}, contents: include_bytes!({:?}), \
+             etag: \"\\\"{:016x}\\\"\" }},",
+            relative,
+            path.display().to_string(),
+            hasher.finish()
+        ).unwrap();
+    }
+    writeln!(output, "];").unwrap();
+}
@@ -0,0 +1,112 @@
+//! The frontend, served either from files built into the binary by the `embed-frontend` feature,
+//! or from the filesystem.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use rocket::Request;
+use rocket::http::{ContentType, Status};
+use rocket::response::{self, NamedFile, Responder, Response};
+
+/// A file of the frontend, built into the binary.
+pub struct Asset {
+    /// The path of the file within the frontend, with `/` separators.
+    pub path: &'static str,
+
+    /// The contents of the file.
+    pub contents: &'static [u8],
+
+    /// A quoted hash of the contents.
+    pub etag: &'static str,
+}
+
+#[cfg(feature = "embed-frontend")]
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+#[cfg(not(feature = "embed-frontend"))]
+static ASSETS: &[Asset] = &[];
+
+/// Whether the frontend was built into the binary.
+pub fn embedded() -> bool {
+    cfg!(feature = "embed-frontend")
+}
+
+/// A file of the frontend, from wherever the frontend is served from.
+pub enum Frontend {
+    Embedded(&'static Asset),
+    File(NamedFile),
+}
+
+/// Opens a file of the frontend, from the binary if it was built in, or otherwise from a
+/// directory.
+pub fn open(root: &Path, path: &Path) -> Option<Frontend> {
+    if !embedded() {
+        return NamedFile::open(root.join(path)).ok().map(Frontend::File);
+    }
+
+    let mut name = Vec::new();
+    for component in path.components() {
+        match component.as_os_str().to_str() {
+            Some(component) => name.push(component),
+            None => return None,
+        }
+    }
+    let name = name.join("/");
+    ASSETS
+        .binary_search_by_key(&name.as_str(), |asset| asset.path)
+        .ok()
+        .map(|i| Frontend::Embedded(&ASSETS[i]))
+}
+
+/// Files under `static/` have a hash of their contents in their name, so they can be cached
+/// forever. Everything else has to be revalidated using its ETag.
+fn cache_control(path: &str) -> &'static str {
+    if path.starts_with("static/") {
+        "public, max-age=31536000, immutable"
+    } else {
+        "no-cache"
+    }
+}
+
+impl<'r> Responder<'r> for Frontend {
+    fn respond_to(self, request: &Request) -> response::Result<'r> {
+        let asset = match self {
+            Frontend::Embedded(asset) => asset,
+            Frontend::File(file) => return file.respond_to(request),
+        };
+
+        let mut response = Response::build();
+        response
+            .raw_header("ETag", asset.etag)
+            .raw_header("Cache-Control", cache_control(asset.path));
+
+        let unchanged = request
+            .headers()
+            .get("If-None-Match")
+            .flat_map(|tags| tags.split(','))
+            .any(|tag| tag.trim() == asset.etag || tag.trim() == "*");
+        if unchanged {
+            response.status(Status::NotModified);
+        } else {
+            if let Some(content_type) = Path::new(asset.path)
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .and_then(ContentType::from_extension)
+            {
+                response.header(content_type);
+            }
+            response.sized_body(Cursor::new(asset.contents));
+        }
+        response.ok()
+    }
+}
+
+#[test]
+fn frontend_cache_control() {
+    assert_eq!(cache_control("index.html"), "no-cache");
+    assert_eq!(cache_control("service-worker.js"), "no-cache");
+    assert_eq!(
+        cache_control("static/js/main.1f2e3d4c.js"),
+        "public, max-age=31536000, immutable"
+    );
+}
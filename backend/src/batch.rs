@@ -0,0 +1,335 @@
+//! Converting many automata at once, such as every submission for an assignment.
+//!
+//! Each automaton succeeds or fails on its own, so one invalid submission does not stop the rest
+//! of the batch, even one that is not shaped like an automaton at all. The automata are
+//! converted in parallel, each with its own limits, and the batch as a whole can have a time
+//! limit too.
+
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+use serde_json::{self, Value};
+
+use state_machina::{Dfa, Limit, Limits, Nfa, NfaError, Unsanitary};
+
+/// An automaton to convert, as part of a batch.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchItem {
+    /// Identifies the automaton in the results, such as a student number.
+    pub name: String,
+
+    /// The automaton to convert.
+    pub nfa: Nfa<Unsanitary>,
+}
+
+/// Why an automaton in a batch could not be converted.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ItemError {
+    /// What kind of error it is, such as `unknown_state`, for scripts to match on.
+    pub kind: &'static str,
+
+    /// A description of the error.
+    pub message: String,
+
+    /// The state the error is about, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+
+    /// The symbol the error is about, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+impl<'a> From<&'a NfaError> for ItemError {
+    fn from(err: &'a NfaError) -> Self {
+        let (kind, state, symbol) = match *err {
+            NfaError::UnknownState(ref state) => ("unknown_state", Some(state), None),
+            NfaError::UnknownSymbol(ref symbol) => ("unknown_symbol", None, Some(symbol)),
+            NfaError::InvalidSymbol(ref symbol) => ("invalid_symbol", None, Some(symbol)),
+            NfaError::OverlappingSymbols(ref symbol, _) => {
+                ("overlapping_symbols", None, Some(symbol))
+            }
+            NfaError::InvalidStateName(ref state) => ("invalid_state_name", Some(state), None),
+            NfaError::MissingTransition(ref state, ref symbol) => {
+                ("missing_transition", Some(state), Some(symbol))
+            }
+            NfaError::MissingOutput(ref state) => ("missing_output", Some(state), None),
+            NfaError::LimitExceeded(_) => ("limit_exceeded", None, None),
+        };
+        ItemError {
+            kind,
+            message: err.to_string(),
+            state: state.cloned(),
+            symbol: symbol.cloned(),
+        }
+    }
+}
+
+/// The outcome of converting one automaton in a batch.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BatchResult {
+    /// The automaton was valid, and this is its minimised DFA.
+    Ok { name: String, dfa: Dfa },
+    /// The automaton could not be converted.
+    Error { name: String, error: ItemError },
+}
+
+impl ItemError {
+    /// An item that could not be read as a [`BatchItem`](struct.BatchItem.html).
+    fn invalid_json(err: &serde_json::Error) -> Self {
+        ItemError {
+            kind: "invalid_json",
+            message: err.to_string(),
+            state: None,
+            symbol: None,
+        }
+    }
+}
+
+/// Converts every automaton into a minimised DFA, in parallel. The results are in the same order
+/// as the automata.
+///
+/// Each item is read on its own, and one that is not a valid [`BatchItem`](struct.BatchItem.html)
+/// fails with an `invalid_json` error. Its name is taken from its `name` field if it has one that
+/// is a string, and is empty otherwise.
+///
+/// Every item has to be finished within `time` of the batch starting, if it is given. Items that
+/// are not fail with a `limit_exceeded` error for the time limit of the batch.
+pub fn convert(
+    items: Vec<Value>,
+    limits: &Limits,
+    time: Option<Duration>,
+) -> Vec<BatchResult> {
+    let deadline = time.map(|time| (Instant::now() + time, time));
+    items
+        .into_par_iter()
+        .map(|value| {
+            let name = value
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_owned();
+            let item: BatchItem = match serde_json::from_value(value) {
+                Ok(item) => item,
+                Err(err) => {
+                    return BatchResult::Error {
+                        name,
+                        error: ItemError::invalid_json(&err),
+                    }
+                }
+            };
+
+            // the item has whatever is left of the batch's time, if that is less than its own
+            let mut limits = *limits;
+            let mut batch_time = None;
+            if let Some((deadline, time)) = deadline {
+                let now = Instant::now();
+                if now >= deadline {
+                    return BatchResult::Error {
+                        name: item.name,
+                        error: ItemError::from(&NfaError::LimitExceeded(Limit::Time(time))),
+                    };
+                }
+                let left = deadline - now;
+                if limits.time.map_or(true, |item_time| left < item_time) {
+                    limits.time = Some(left);
+                    batch_time = Some(time);
+                }
+            }
+
+            let dfa = item.nfa
+                .check()
+                .and_then(|nfa| limits.make_deterministic(nfa))
+                .and_then(|dfa| limits.minimise(dfa));
+            match dfa {
+                Ok(dfa) => BatchResult::Ok {
+                    name: item.name,
+                    dfa,
+                },
+                Err(err) => {
+                    let err = match (err, batch_time) {
+                        (NfaError::LimitExceeded(Limit::Time(_)), Some(time)) => {
+                            NfaError::LimitExceeded(Limit::Time(time))
+                        }
+                        (err, _) => err,
+                    };
+                    BatchResult::Error {
+                        name: item.name,
+                        error: ItemError::from(&err),
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn batch_convert() {
+    let items: Vec<Value> = serde_json::from_str(
+        r#"[
+            {
+                "name": "s1",
+                "nfa": {
+                    "start": "1",
+                    "alphabet": ["a"],
+                    "nodes": { "1": { "a": ["2"] }, "2": {} },
+                    "final_states": ["2"]
+                }
+            },
+            {
+                "name": "s2",
+                "nfa": {
+                    "start": "1",
+                    "alphabet": ["a"],
+                    "nodes": { "1": { "a": ["3"] } },
+                    "final_states": []
+                }
+            },
+            {
+                "name": "s3",
+                "nfa": {
+                    "start": "1",
+                    "alphabet": ["a", "b"],
+                    "nodes": { "1": { "a": ["1", "2"], "b": ["1"] }, "2": {} },
+                    "final_states": ["2"]
+                }
+            }
+        ]"#,
+    ).unwrap();
+    let results = convert(items, &Limits::default(), None);
+    assert_eq!(results.len(), 3);
+
+    match results[0] {
+        BatchResult::Ok { ref name, .. } => assert_eq!(name, "s1"),
+        ref err @ _ => panic!("{:?}", err),
+    }
+    match results[1] {
+        BatchResult::Error {
+            ref name,
+            ref error,
+        } => {
+            assert_eq!(name, "s2");
+            assert_eq!(
+                *error,
+                ItemError {
+                    kind: "unknown_state",
+                    message: "unknown state \"3\"".to_string(),
+                    state: Some("3".to_string()),
+                    symbol: None,
+                }
+            );
+        }
+        ref ok @ _ => panic!("{:?}", ok),
+    }
+    match results[2] {
+        BatchResult::Ok { ref name, .. } => assert_eq!(name, "s3"),
+        ref err @ _ => panic!("{:?}", err),
+    }
+
+    let limits = Limits {
+        max_nfa_states: Some(1),
+        ..Limits::default()
+    };
+    let items = vec![
+        serde_json::from_str(
+            r#"{
+                "name": "s1",
+                "nfa": {
+                    "start": "1",
+                    "alphabet": ["a"],
+                    "nodes": { "1": { "a": ["2"] }, "2": {} },
+                    "final_states": ["2"]
+                }
+            }"#,
+        ).unwrap(),
+    ];
+    let results = serde_json::to_value(&convert(items, &limits, None)).unwrap();
+    assert_eq!(results[0]["status"], "error");
+    assert_eq!(results[0]["error"]["kind"], "limit_exceeded");
+    assert!(results[0]["error"].get("state").is_none());
+
+    // once the batch is out of time, the items left fail without being converted
+    let items = vec![
+        serde_json::from_str(
+            r#"{
+                "name": "s1",
+                "nfa": {
+                    "start": "1",
+                    "alphabet": ["a"],
+                    "nodes": { "1": { "a": ["1"] } },
+                    "final_states": ["1"]
+                }
+            }"#,
+        ).unwrap(),
+    ];
+    let results = convert(items, &Limits::default(), Some(Duration::from_secs(0)));
+    let results = serde_json::to_value(&results).unwrap();
+    assert_eq!(results[0]["name"], "s1");
+    assert_eq!(results[0]["error"]["kind"], "limit_exceeded");
+    assert_eq!(
+        results[0]["error"]["message"],
+        "limit exceeded: more than 0.000 seconds"
+    );
+}
+
+#[test]
+fn batch_invalid_json() {
+    let items: Vec<Value> = serde_json::from_str(
+        r#"[
+            {
+                "name": "s1",
+                "nfa": {
+                    "start": "1",
+                    "alphabet": ["a"],
+                    "nodes": { "1": { "a": ["2"] }, "2": {} },
+                    "final_states": ["2"]
+                }
+            },
+            {
+                "name": "s2",
+                "nfa": {
+                    "alphabet": ["a"],
+                    "nodes": { "1": {} },
+                    "final_states": []
+                }
+            },
+            {
+                "name": "s3",
+                "nfa": {
+                    "start": "1",
+                    "alphabet": "a",
+                    "nodes": { "1": {} },
+                    "final_states": []
+                }
+            },
+            {
+                "nmae": "s4",
+                "nfa": {
+                    "start": "1",
+                    "alphabet": ["a"],
+                    "nodes": { "1": {} },
+                    "final_states": []
+                }
+            },
+            7
+        ]"#,
+    ).unwrap();
+    let results = serde_json::to_value(&convert(items, &Limits::default(), None)).unwrap();
+    assert_eq!(results.as_array().unwrap().len(), 5);
+
+    assert_eq!(results[0]["status"], "ok");
+    for (i, name) in vec!["s2", "s3", "", ""].into_iter().enumerate() {
+        let result = &results[i + 1];
+        assert_eq!(result["name"], name);
+        assert_eq!(result["status"], "error");
+        assert_eq!(result["error"]["kind"], "invalid_json");
+    }
+    assert!(
+        results[1]["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("start")
+    );
+}
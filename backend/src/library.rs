@@ -0,0 +1,258 @@
+//! A library of named automata, saved as JSON files in a directory.
+
+use std::error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde_json;
+
+use state_machina::{Dfa, Limits, Nfa, NfaError, Unsanitary};
+
+/// The longest name an automaton can be saved under.
+const MAX_NAME_LENGTH: usize = 64;
+
+/// An automaton as it was submitted, either an NFA or a DFA.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Automaton {
+    Nfa(Nfa<Unsanitary>),
+    Dfa(Dfa),
+}
+
+impl Automaton {
+    /// Validates the automaton and minimises it, treating a DFA as an NFA with a single state in
+    /// every transition.
+    pub fn minimise(&self, limits: &Limits) -> Result<Dfa, NfaError> {
+        let nfa = match *self {
+            Automaton::Nfa(ref nfa) => nfa.clone(),
+            Automaton::Dfa(ref dfa) => dfa.clone().into_nfa(),
+        };
+        limits.minimise(limits.make_deterministic(nfa.check()?)?)
+    }
+}
+
+/// A saved automaton.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    /// The name the automaton is saved under.
+    pub name: String,
+
+    /// The automaton as it was submitted.
+    pub input: Automaton,
+
+    /// The minimised DFA, as of the last time the automaton was saved.
+    pub minimised: Dfa,
+}
+
+/// Errors from reading or writing the library.
+#[derive(Debug)]
+pub enum LibraryError {
+    /// The name cannot be used for a saved automaton.
+    InvalidName(String),
+    /// There is already an automaton saved under the name.
+    AlreadyExists(String),
+    /// There is no automaton saved under the name.
+    NotFound(String),
+    /// The automaton is invalid, or too large to minimise.
+    Nfa(NfaError),
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LibraryError::InvalidName(ref name) => write!(
+                f,
+                "invalid name {:?}: names are 1 to {} lowercase letters, digits, '-' or '_'",
+                name,
+                MAX_NAME_LENGTH
+            ),
+            LibraryError::AlreadyExists(ref name) => {
+                write!(f, "an automaton named {:?} already exists", name)
+            }
+            LibraryError::NotFound(ref name) => write!(f, "no automaton named {:?}", name),
+            LibraryError::Nfa(ref err) => err.fmt(f),
+            LibraryError::Io(ref err) => err.fmt(f),
+            LibraryError::Json(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for LibraryError {
+    fn description(&self) -> &str {
+        "library error"
+    }
+}
+
+impl From<NfaError> for LibraryError {
+    fn from(err: NfaError) -> Self {
+        LibraryError::Nfa(err)
+    }
+}
+
+impl From<io::Error> for LibraryError {
+    fn from(err: io::Error) -> Self {
+        LibraryError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for LibraryError {
+    fn from(err: serde_json::Error) -> Self {
+        LibraryError::Json(err)
+    }
+}
+
+/// A directory of saved automata, with one `<name>.json` file for each.
+#[derive(Debug)]
+pub struct Library {
+    root: PathBuf,
+
+    /// Held while writing, so that checking whether a name is taken and saving under it happen
+    /// together.
+    lock: Mutex<()>,
+}
+
+impl Library {
+    /// Opens the library in a directory, creating the directory if it does not exist.
+    pub fn open<P: Into<PathBuf>>(root: P) -> io::Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        Ok(Library {
+            root,
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// The names of every saved automaton, in sorted order.
+    pub fn list(&self) -> Result<Vec<String>, LibraryError> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            if path.extension().map_or(false, |extension| extension == "json") {
+                if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                    if valid_name(name) {
+                        names.push(name.to_owned());
+                    }
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Fetches a saved automaton.
+    pub fn get(&self, name: &str) -> Result<Entry, LibraryError> {
+        let path = self.path(name)?;
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(LibraryError::NotFound(name.to_owned()))
+            }
+            Err(err) => return Err(err.into()),
+        };
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    /// Saves a new automaton, failing if the name is already taken.
+    pub fn create(
+        &self,
+        name: &str,
+        input: Automaton,
+        limits: &Limits,
+    ) -> Result<Entry, LibraryError> {
+        let path = self.path(name)?;
+        let minimised = input.minimise(limits)?;
+        let _lock = self.lock.lock().unwrap();
+        if path.exists() {
+            return Err(LibraryError::AlreadyExists(name.to_owned()));
+        }
+        self.write(&path, name, input, minimised)
+    }
+
+    /// Replaces a saved automaton, failing if there is nothing saved under the name.
+    pub fn update(
+        &self,
+        name: &str,
+        input: Automaton,
+        limits: &Limits,
+    ) -> Result<Entry, LibraryError> {
+        let path = self.path(name)?;
+        let minimised = input.minimise(limits)?;
+        let _lock = self.lock.lock().unwrap();
+        if !path.exists() {
+            return Err(LibraryError::NotFound(name.to_owned()));
+        }
+        self.write(&path, name, input, minimised)
+    }
+
+    /// Deletes a saved automaton.
+    pub fn delete(&self, name: &str) -> Result<(), LibraryError> {
+        let path = self.path(name)?;
+        let _lock = self.lock.lock().unwrap();
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                Err(LibraryError::NotFound(name.to_owned()))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// The file an automaton is saved in. Names are restricted so that they cannot escape the
+    /// library's directory.
+    fn path(&self, name: &str) -> Result<PathBuf, LibraryError> {
+        if valid_name(name) {
+            Ok(self.root.join(format!("{}.json", name)))
+        } else {
+            Err(LibraryError::InvalidName(name.to_owned()))
+        }
+    }
+
+    /// Writes an entry to a temporary file, and makes sure it is on disk, before moving it into
+    /// place, so that a failed write never leaves a half written file behind.
+    fn write(
+        &self,
+        path: &Path,
+        name: &str,
+        input: Automaton,
+        minimised: Dfa,
+    ) -> Result<Entry, LibraryError> {
+        let entry = Entry {
+            name: name.to_owned(),
+            input,
+            minimised,
+        };
+        let temporary = path.with_extension("json.tmp");
+        {
+            // flush and sync explicitly, as dropping the writer would ignore any errors
+            let mut writer = BufWriter::new(File::create(&temporary)?);
+            serde_json::to_writer(&mut writer, &entry)?;
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+        }
+        fs::rename(&temporary, path)?;
+        Ok(entry)
+    }
+}
+
+/// Whether a name can be used for a saved automaton. Uppercase letters are rejected, as names
+/// differing only in case would share a file on case-insensitive filesystems.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= MAX_NAME_LENGTH &&
+        name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+#[test]
+fn library_names() {
+    assert!(valid_name("even-a_s2"));
+    assert!(!valid_name(""));
+    assert!(!valid_name("../secrets"));
+    assert!(!valid_name("a.json"));
+    assert!(!valid_name("Even"));
+    assert!(!valid_name("\u{e9}t\u{e9}"));
+    assert!(!valid_name(&"a".repeat(MAX_NAME_LENGTH + 1)));
+}
@@ -1,49 +1,484 @@
 #![feature(plugin, custom_derive, custom_attribute)]
 #![plugin(rocket_codegen)]
+extern crate base64;
+extern crate flate2;
+extern crate rayon;
 extern crate rocket;
+extern crate rocket_contrib;
 extern crate serde;
-extern crate itertools;
-
 #[macro_use]
 extern crate serde_derive;
-extern crate rocket_contrib;
+extern crate state_machina;
 
-#[cfg(test)]
 extern crate serde_json;
 
+mod assets;
+mod batch;
+mod library;
+mod permalink;
+mod settings;
+
 use rocket_contrib::Json;
 
-use std::io;
 use std::path::{Path, PathBuf};
 
-use rocket::response::NamedFile;
+use rocket::State;
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::response::content;
+use rocket::response::status::{BadRequest, Created, Custom, NoContent};
+
+use state_machina::{Dfa, Generator, Grade, Grader, MinimisationExplanation, NerodeReport, Nfa,
+                    Pda, PdaRun, Sanitary, SubsetStep, TuringMachine, TuringRun, Unsanitary};
+use state_machina::text;
+
+use assets::Frontend;
+use batch::BatchResult;
+use library::{Automaton, Entry, Library, LibraryError};
+use settings::{Endpoint, Settings};
 
-mod automata;
-use automata::{Dfa, Nfa, Unsanitary};
+/// Where the API is mounted. Breaking changes to the API go under a new version.
+const API_BASE: &str = "/api/v1";
+
+/// A description of the API, kept in sync with the routes by the tests.
+const SCHEMA: &str = include_str!("schema.json");
 
 #[get("/")]
-fn index() -> io::Result<NamedFile> {
-    NamedFile::open("../frontend/build/index.html")
+fn index(settings: State<Settings>) -> Option<Frontend> {
+    assets::open(&settings.assets, Path::new("index.html"))
 }
 
-#[get("/<file..>")]
-fn files(file: PathBuf) -> Option<NamedFile> {
-    NamedFile::open(Path::new("../frontend/build/").join(file)).ok()
+#[get("/<file..>", rank = 10)]
+fn files(file: PathBuf, settings: State<Settings>) -> Option<Frontend> {
+    assets::open(&settings.assets, &file)
 }
 
 #[post("/submit", format = "application/json", data = "<data>")]
-fn submit_nfa(data: Json<Nfa<Unsanitary>>) -> Json<Dfa> {
-    Json(
-        data.into_inner()
-            .check()
-            .unwrap()
-            .make_deterministic()
-            .minimise(),
-    )
+fn submit_nfa(
+    data: Json<Nfa<Unsanitary>>,
+    settings: State<Settings>,
+) -> Result<Json<Dfa>, BadRequest<String>> {
+    let limits = settings.limits;
+    data.into_inner()
+        .check()
+        .and_then(|nfa| limits.make_deterministic(nfa))
+        .and_then(|dfa| limits.minimise(dfa))
+        .map(Json)
+        .map_err(|err| BadRequest(Some(err.to_string())))
+}
+
+#[post("/submit", format = "text/plain", data = "<data>", rank = 2)]
+fn submit_text(data: String, settings: State<Settings>) -> Result<Json<Dfa>, BadRequest<String>> {
+    let nfa = text::parse_nfa(&data).map_err(|err| BadRequest(Some(err.to_string())))?;
+    let limits = settings.limits;
+    nfa.check()
+        .and_then(|nfa| limits.make_deterministic(nfa))
+        .and_then(|dfa| limits.minimise(dfa))
+        .map(Json)
+        .map_err(|err| BadRequest(Some(err.to_string())))
+}
+
+#[post("/batch", format = "application/json", data = "<data>")]
+fn submit_batch(
+    data: Json<Vec<serde_json::Value>>,
+    settings: State<Settings>,
+) -> Result<Json<Vec<BatchResult>>, BadRequest<String>> {
+    let items = data.into_inner();
+    if let Some(max_batch_size) = settings.max_batch_size {
+        if items.len() > max_batch_size {
+            return Err(BadRequest(Some(format!(
+                "a batch may have at most {} automata, but this one has {}",
+                max_batch_size,
+                items.len()
+            ))));
+        }
+    }
+    Ok(Json(batch::convert(items, &settings.limits, settings.batch_time_limit)))
+}
+
+/// Refuses to generate automata larger than the server allows.
+fn limit_generator(generator: &Generator, settings: &Settings) -> Result<(), BadRequest<String>> {
+    if let Some(max_states) = settings.max_random_states {
+        if generator.states > max_states {
+            return Err(BadRequest(Some(format!(
+                "a random automaton may have at most {} states",
+                max_states
+            ))));
+        }
+    }
+    if let Some(max_alphabet_size) = settings.limits.max_alphabet_size {
+        if generator.alphabet_size > max_alphabet_size {
+            return Err(BadRequest(Some(format!(
+                "a random automaton may have at most {} symbols",
+                max_alphabet_size
+            ))));
+        }
+    }
+    Ok(())
+}
+
+#[post("/random/nfa", format = "application/json", data = "<data>")]
+fn random_nfa(
+    data: Json<Generator>,
+    settings: State<Settings>,
+) -> Result<Json<Nfa<Sanitary>>, BadRequest<String>> {
+    limit_generator(&data, &settings)?;
+    data.nfa()
+        .map(Json)
+        .map_err(|err| BadRequest(Some(err.to_string())))
+}
+
+#[post("/random/dfa", format = "application/json", data = "<data>")]
+fn random_dfa(
+    data: Json<Generator>,
+    settings: State<Settings>,
+) -> Result<Json<Dfa>, BadRequest<String>> {
+    limit_generator(&data, &settings)?;
+    data.dfa()
+        .map(Json)
+        .map_err(|err| BadRequest(Some(err.to_string())))
+}
+
+/// A request to grade an automaton against a reference.
+#[derive(Deserialize)]
+struct Grading {
+    reference: Automaton,
+    student: Automaton,
+    max_length: Option<usize>,
+    max_counterexamples: Option<usize>,
+}
+
+#[post("/grade", format = "application/json", data = "<data>")]
+fn grade_automaton(
+    data: Json<Grading>,
+    settings: State<Settings>,
+) -> Result<Json<Grade>, BadRequest<String>> {
+    let Grading {
+        reference,
+        student,
+        max_length,
+        max_counterexamples,
+    } = data.into_inner();
+    let limits = settings.limits;
+    let reference = reference
+        .minimise(&limits)
+        .map_err(|err| BadRequest(Some(format!("invalid reference: {}", err))))?;
+    let student = student
+        .minimise(&limits)
+        .map_err(|err| BadRequest(Some(format!("invalid student automaton: {}", err))))?;
+
+    let defaults = Grader::default();
+    let grader = Grader {
+        max_length: max_length
+            .unwrap_or(defaults.max_length)
+            .min(settings.max_grading_length),
+        max_counterexamples: max_counterexamples.unwrap_or(defaults.max_counterexamples),
+    };
+    limits
+        .grade(&grader, &reference, &student)
+        .map(Json)
+        .map_err(|err| BadRequest(Some(err.to_string())))
+}
+
+/// A DFA, along with how the subset construction produced it.
+#[derive(Serialize)]
+struct TracedDfa {
+    dfa: Dfa,
+    steps: Vec<SubsetStep>,
+}
+
+#[post("/trace", format = "application/json", data = "<data>")]
+fn trace_nfa(
+    data: Json<Nfa<Unsanitary>>,
+    settings: State<Settings>,
+) -> Result<Json<TracedDfa>, BadRequest<String>> {
+    let nfa = data.into_inner()
+        .check()
+        .map_err(|err| BadRequest(Some(err.to_string())))?;
+    let (dfa, steps) = settings
+        .limits
+        .make_deterministic_traced(nfa)
+        .map_err(|err| BadRequest(Some(err.to_string())))?;
+    Ok(Json(TracedDfa { dfa, steps }))
+}
+
+/// A minimised DFA, along with how minimisation produced it.
+#[derive(Serialize)]
+struct ExplainedDfa {
+    dfa: Dfa,
+    explanation: MinimisationExplanation,
+}
+
+#[post("/explain", format = "application/json", data = "<data>")]
+fn explain_nfa(
+    data: Json<Nfa<Unsanitary>>,
+    settings: State<Settings>,
+) -> Result<Json<ExplainedDfa>, BadRequest<String>> {
+    let nfa = data.into_inner()
+        .check()
+        .map_err(|err| BadRequest(Some(err.to_string())))?;
+    let limits = settings.limits;
+    let (dfa, explanation) = limits
+        .make_deterministic(nfa)
+        .and_then(|dfa| limits.minimise_explained(dfa))
+        .map_err(|err| BadRequest(Some(err.to_string())))?;
+    Ok(Json(ExplainedDfa { dfa, explanation }))
+}
+
+/// A minimised DFA, along with the Myhill–Nerode equivalence classes of its language.
+#[derive(Serialize)]
+struct NerodeDfa {
+    dfa: Dfa,
+    report: NerodeReport,
+}
+
+#[post("/nerode", format = "application/json", data = "<data>")]
+fn nerode_nfa(
+    data: Json<Nfa<Unsanitary>>,
+    settings: State<Settings>,
+) -> Result<Json<NerodeDfa>, BadRequest<String>> {
+    let nfa = data.into_inner()
+        .check()
+        .map_err(|err| BadRequest(Some(err.to_string())))?;
+    let limits = settings.limits;
+    let dfa = limits
+        .make_deterministic(nfa)
+        .and_then(|dfa| limits.minimise(dfa))
+        .map_err(|err| BadRequest(Some(err.to_string())))?;
+    let report = limits
+        .nerode_classes(&dfa)
+        .map_err(|err| BadRequest(Some(err.to_string())))?;
+    Ok(Json(NerodeDfa { dfa, report }))
+}
+
+/// A request to simulate a PDA over a word.
+#[derive(Deserialize)]
+struct PdaSimulation {
+    pda: Pda<Unsanitary>,
+    word: Vec<String>,
+    max_steps: Option<usize>,
+}
+
+#[post("/pda/simulate", format = "application/json", data = "<data>")]
+fn simulate_pda(
+    data: Json<PdaSimulation>,
+    settings: State<Settings>,
+) -> Result<Json<PdaRun>, BadRequest<String>> {
+    let PdaSimulation {
+        pda,
+        word,
+        max_steps,
+    } = data.into_inner();
+    let pda = pda.check().map_err(|err| BadRequest(Some(err.to_string())))?;
+    let max_steps = max_steps.map_or(settings.max_pda_steps, |steps| {
+        steps.min(settings.max_pda_steps)
+    });
+    settings
+        .limits
+        .simulate_pda(&pda, word.iter().map(|x| x.as_str()), max_steps)
+        .map(Json)
+        .map_err(|err| BadRequest(Some(err.to_string())))
+}
+
+/// A request to run a Turing machine over a word.
+#[derive(Deserialize)]
+struct TuringSimulation {
+    machine: TuringMachine<Unsanitary>,
+    word: Vec<String>,
+    max_steps: Option<usize>,
+}
+
+#[post("/turing/simulate", format = "application/json", data = "<data>")]
+fn simulate_turing(
+    data: Json<TuringSimulation>,
+    settings: State<Settings>,
+) -> Result<Json<TuringRun>, BadRequest<String>> {
+    let TuringSimulation {
+        machine,
+        word,
+        max_steps,
+    } = data.into_inner();
+    let machine = machine
+        .check()
+        .map_err(|err| BadRequest(Some(err.to_string())))?;
+    let max_steps = max_steps.map_or(settings.max_turing_steps, |steps| {
+        steps.min(settings.max_turing_steps)
+    });
+    settings
+        .limits
+        .run_turing(&machine, word.iter().map(|x| x.as_str()), max_steps)
+        .map(Json)
+        .map_err(|err| BadRequest(Some(err.to_string())))
+}
+
+/// A token that encodes an automaton, along with the path that decodes it.
+#[derive(Serialize)]
+struct Permalink {
+    token: String,
+    path: String,
+}
+
+#[post("/permalink", format = "application/json", data = "<data>")]
+fn create_permalink(data: Json<Nfa<Unsanitary>>) -> Result<Json<Permalink>, BadRequest<String>> {
+    let nfa = data.into_inner()
+        .check()
+        .map_err(|err| BadRequest(Some(err.to_string())))?;
+    let token = permalink::encode(&nfa);
+    let path = format!("{}/permalink/{}", API_BASE, token);
+    Ok(Json(Permalink { token, path }))
+}
+
+#[get("/permalink/<token>")]
+fn open_permalink(token: String) -> Result<Json<Nfa<Sanitary>>, BadRequest<String>> {
+    permalink::decode(&token)
+        .map_err(|err| BadRequest(Some(err.to_string())))?
+        .check()
+        .map(Json)
+        .map_err(|err| BadRequest(Some(err.to_string())))
+}
+
+/// A request to save an automaton under a name.
+#[derive(Deserialize)]
+struct NewEntry {
+    name: String,
+    automaton: Automaton,
+}
+
+/// Responds to a library error with the matching status.
+fn library_error(err: LibraryError) -> Custom<String> {
+    let status = match err {
+        LibraryError::InvalidName(_) | LibraryError::Nfa(_) => Status::BadRequest,
+        LibraryError::AlreadyExists(_) => Status::Conflict,
+        LibraryError::NotFound(_) => Status::NotFound,
+        LibraryError::Io(_) | LibraryError::Json(_) => Status::InternalServerError,
+    };
+    Custom(status, err.to_string())
+}
+
+#[get("/library")]
+fn list_entries(library: State<Library>) -> Result<Json<Vec<String>>, Custom<String>> {
+    library.list().map(Json).map_err(library_error)
+}
+
+#[post("/library", format = "application/json", data = "<data>")]
+fn create_entry(
+    data: Json<NewEntry>,
+    library: State<Library>,
+    settings: State<Settings>,
+) -> Result<Created<Json<Entry>>, Custom<String>> {
+    let NewEntry { name, automaton } = data.into_inner();
+    library
+        .create(&name, automaton, &settings.limits)
+        .map(|entry| Created(format!("{}/library/{}", API_BASE, name), Some(Json(entry))))
+        .map_err(library_error)
+}
+
+#[get("/library/<name>")]
+fn get_entry(name: String, library: State<Library>) -> Result<Json<Entry>, Custom<String>> {
+    library.get(&name).map(Json).map_err(library_error)
+}
+
+#[put("/library/<name>", format = "application/json", data = "<data>")]
+fn update_entry(
+    name: String,
+    data: Json<Automaton>,
+    library: State<Library>,
+    settings: State<Settings>,
+) -> Result<Json<Entry>, Custom<String>> {
+    library
+        .update(&name, data.into_inner(), &settings.limits)
+        .map(Json)
+        .map_err(library_error)
+}
+
+#[delete("/library/<name>")]
+fn delete_entry(name: String, library: State<Library>) -> Result<NoContent, Custom<String>> {
+    library
+        .delete(&name)
+        .map(|()| NoContent)
+        .map_err(library_error)
+}
+
+#[get("/schema")]
+fn schema() -> content::Json<&'static str> {
+    content::Json(SCHEMA)
+}
+
+/// The API routes of every endpoint that is enabled.
+fn api_routes(settings: &Settings) -> Vec<rocket::Route> {
+    let mut routes = routes![submit_nfa, submit_text, schema];
+    for endpoint in &Endpoint::ALL {
+        if settings.enabled(*endpoint) {
+            routes.extend(match *endpoint {
+                Endpoint::Trace => routes![trace_nfa],
+                Endpoint::Explain => routes![explain_nfa],
+                Endpoint::Pda => routes![simulate_pda],
+                Endpoint::Turing => routes![simulate_turing],
+                Endpoint::Permalink => routes![create_permalink, open_permalink],
+                Endpoint::Library => {
+                    routes![list_entries, create_entry, get_entry, update_entry, delete_entry]
+                }
+                Endpoint::Batch => routes![submit_batch],
+                Endpoint::Random => routes![random_nfa, random_dfa],
+                Endpoint::Grade => routes![grade_automaton],
+                Endpoint::Nerode => routes![nerode_nfa],
+            });
+        }
+    }
+    routes
+}
+
+/// Reads the settings, and sets up everything that depends on them. Refuses to launch if the
+/// settings are invalid, the frontend has not been built, or the library cannot be opened.
+fn configure(rocket: rocket::Rocket) -> Result<rocket::Rocket, rocket::Rocket> {
+    let settings = Settings::from_config(rocket.config());
+    let settings = match settings {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return Err(rocket);
+        }
+    };
+
+    let index = settings.assets.join("index.html");
+    if !assets::embedded() && !index.is_file() {
+        eprintln!(
+            "Error: the frontend is missing, as {} does not exist. Build the frontend, or set \
+             `assets` to the directory it was built in.",
+            index.display()
+        );
+        return Err(rocket);
+    }
+
+    let rocket = if settings.enabled(Endpoint::Library) {
+        match Library::open(&settings.library) {
+            Ok(library) => rocket.manage(library),
+            Err(err) => {
+                eprintln!(
+                    "Error: could not open the library at {}: {}",
+                    settings.library.display(),
+                    err
+                );
+                return Err(rocket);
+            }
+        }
+    } else {
+        rocket
+    };
+
+    let routes = api_routes(&settings);
+    Ok(rocket
+        .manage(settings)
+        .mount("/", routes![index, files])
+        .mount(API_BASE, routes))
+}
+
+fn server(rocket: rocket::Rocket) -> rocket::Rocket {
+    rocket.attach(AdHoc::on_attach(configure))
 }
 
 fn rocket() -> rocket::Rocket {
-    rocket::ignite().mount("/", routes![index, submit_nfa, files])
+    server(rocket::ignite())
 }
 
 fn main() {
@@ -52,13 +487,28 @@ fn main() {
 
 #[cfg(test)]
 mod test {
-    use super::rocket;
+    use super::{api_routes, rocket, server, SCHEMA};
+    use assets;
+    use settings::Settings;
+    use rocket::config::{Config, Environment, Value};
     use rocket::local::Client;
     use rocket::http::Status;
-    use rocket::http::ContentType;
+    use rocket::http::{ContentType, Header};
 
     use serde_json;
-    use automata::*;
+    use state_machina::*;
+
+    use std::env;
+    use std::fs;
+
+    /// A server with some settings changed from their defaults.
+    fn configured(settings: Vec<(&str, Value)>) -> rocket::Rocket {
+        let mut config = Config::build(Environment::Development);
+        for (name, value) in settings {
+            config = config.extra(name, value);
+        }
+        server(rocket::custom(config.finalize().unwrap(), false))
+    }
 
     /// Test home page ("/" or "/index.html")
     #[test]
@@ -80,6 +530,16 @@ mod test {
         let response = client.get("/index.html").dispatch();
         assert_eq!(response.status(), Status::Ok);
         assert_eq!(response.content_type(), Some(ContentType::HTML));
+
+        if assets::embedded() {
+            assert_eq!(response.headers().get_one("Cache-Control"), Some("no-cache"));
+            let etag = response.headers().get_one("ETag").unwrap().to_string();
+            let response = client
+                .get("/index.html")
+                .header(Header::new("If-None-Match", etag))
+                .dispatch();
+            assert_eq!(response.status(), Status::NotModified);
+        }
     }
 
     #[test]
@@ -118,7 +578,7 @@ mod test {
         }"#;
 
         let mut response = client
-            .post("/submit")
+            .post("/api/v1/submit")
             .body(input)
             .header(ContentType::JSON)
             .dispatch();
@@ -136,7 +596,7 @@ mod test {
         let rocket = rocket();
         let client = Client::new(rocket).expect("valid rocket instance");
         let response = client
-            .post("/submit")
+            .post("/api/v1/submit")
             .body("{}")
             .header(ContentType::JSON)
             .dispatch();
@@ -144,6 +604,254 @@ mod test {
         assert_eq!(response.status(), Status::BadRequest);
     }
 
+    /// Test that an NFA whose DFA would be too large is refused
+    #[test]
+    fn test_submit_nfa_limits() {
+        let rocket = rocket();
+        let client = Client::new(rocket).expect("valid rocket instance");
+
+        // the nth symbol from the end is an "a", which needs 2^n DFA states
+        let mut input = String::from("start: 0\nfinal: 16\n0 -a-> 0, 1\n0 -b-> 0\n");
+        for i in 1..16 {
+            input.push_str(&format!("{} -a-> {}\n{} -b-> {}\n", i, i + 1, i, i + 1));
+        }
+
+        let mut response = client
+            .post("/api/v1/submit")
+            .body(input)
+            .header(ContentType::Plain)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(
+            response.body_string().unwrap(),
+            "limit exceeded: more than 10000 DFA states"
+        );
+    }
+
+    /// Test that a batch reports on each NFA separately, and can be limited in size
+    #[test]
+    fn test_batch() {
+        let batch = r#"[
+            {
+                "name": "good",
+                "nfa": {
+                    "start": "1",
+                    "alphabet": ["a"],
+                    "nodes": { "1": { "a": ["2"] }, "2": {} },
+                    "final_states": ["2"]
+                }
+            },
+            {
+                "name": "bad",
+                "nfa": {
+                    "start": "1",
+                    "alphabet": ["a"],
+                    "nodes": { "1": { "b": ["1"] } },
+                    "final_states": []
+                }
+            }
+        ]"#;
+
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut response = client
+            .post("/api/v1/batch")
+            .body(batch)
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let results: serde_json::Value =
+            serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        assert_eq!(results[0]["name"], "good");
+        assert_eq!(results[0]["status"], "ok");
+        assert_eq!(results[0]["dfa"]["start"], "1");
+        assert_eq!(results[1]["name"], "bad");
+        assert_eq!(results[1]["status"], "error");
+        assert_eq!(results[1]["error"]["kind"], "unknown_symbol");
+        assert_eq!(results[1]["error"]["symbol"], "b");
+
+        // an item that is not shaped like an automaton only fails by itself
+        let mixed = r#"[
+            { "name": "missing", "nfa": { "alphabet": [], "nodes": {}, "final_states": [] } },
+            {
+                "name": "good",
+                "nfa": {
+                    "start": "1",
+                    "alphabet": ["a"],
+                    "nodes": { "1": { "a": ["2"] }, "2": {} },
+                    "final_states": ["2"]
+                }
+            },
+            { "name": "typo", "nfa": {}, "extra": true }
+        ]"#;
+        let mut response = client
+            .post("/api/v1/batch")
+            .body(mixed)
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let results: serde_json::Value =
+            serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        assert_eq!(results[0]["name"], "missing");
+        assert_eq!(results[0]["error"]["kind"], "invalid_json");
+        assert_eq!(results[1]["status"], "ok");
+        assert_eq!(results[2]["name"], "typo");
+        assert_eq!(results[2]["error"]["kind"], "invalid_json");
+
+        let rocket = configured(vec![("max_batch_size", Value::Integer(1))]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut response = client
+            .post("/api/v1/batch")
+            .body(batch)
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(
+            response.body_string().unwrap(),
+            "a batch may have at most 1 automata, but this one has 2"
+        );
+    }
+
+    /// Test that random automata are repeatable, and limited in size
+    #[test]
+    fn test_random() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let generate = |path: &str, body: &str| {
+            let mut response = client
+                .post(path)
+                .body(body)
+                .header(ContentType::JSON)
+                .dispatch();
+            (response.status(), response.body_string().unwrap())
+        };
+
+        let (status, nfa) = generate("/api/v1/random/nfa", r#"{ "states": 10, "seed": 3 }"#);
+        assert_eq!(status, Status::Ok);
+        let nfa: Nfa<Unsanitary> = serde_json::from_str(&nfa).unwrap();
+        let nfa = nfa.check().unwrap();
+        assert_eq!(nfa.states().count(), 10);
+        assert_eq!(nfa.final_states().len(), 3);
+
+        let (status, dfa) = generate("/api/v1/random/dfa", r#"{ "final_ratio": 1.0 }"#);
+        assert_eq!(status, Status::Ok);
+        let dfa: Dfa = serde_json::from_str(&dfa).unwrap();
+        assert!(dfa.accepts(vec!["a", "b"]).unwrap());
+        let (_, again) = generate("/api/v1/random/dfa", r#"{ "final_ratio": 1.0 }"#);
+        let again: Dfa = serde_json::from_str(&again).unwrap();
+        assert!(
+            dfa.states()
+                .all(|state| dfa.transitions(state) == again.transitions(state))
+        );
+
+        assert_eq!(
+            generate("/api/v1/random/nfa", r#"{ "states": 101 }"#),
+            (
+                Status::BadRequest,
+                "a random automaton may have at most 100 states".to_string()
+            )
+        );
+        assert_eq!(
+            generate("/api/v1/random/dfa", r#"{ "final_ratio": 2 }"#),
+            (
+                Status::BadRequest,
+                "final_ratio must be between 0 and 1, not 2".to_string()
+            )
+        );
+    }
+
+    /// Test grading an NFA against a DFA
+    #[test]
+    fn test_grade() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+
+        // the reference accepts an even number of "a"s, and the student's NFA accepts an even
+        // number of "a"s, except for none at all
+        let grading = r#"{
+            "reference": {
+                "start": "even",
+                "alphabet": ["a"],
+                "nodes": { "even": { "a": "odd" }, "odd": { "a": "even" } },
+                "final_states": ["even"]
+            },
+            "student": {
+                "start": "1",
+                "alphabet": ["a"],
+                "nodes": { "1": { "a": ["2"] }, "2": { "a": ["3"] }, "3": { "a": ["2"] } },
+                "final_states": ["3"]
+            },
+            "max_length": 3
+        }"#;
+        let mut response = client
+            .post("/api/v1/grade")
+            .body(grading)
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let grade: serde_json::Value =
+            serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        assert_eq!(grade["equivalent"].as_bool(), Some(false));
+        assert_eq!(grade["should_accept"].as_array().unwrap().len(), 1);
+        assert!(grade["should_accept"][0].as_array().unwrap().is_empty());
+        assert!(grade["should_reject"].as_array().unwrap().is_empty());
+        assert_eq!(grade["similarity"].as_f64(), Some(0.75));
+
+        let mut response = client
+            .post("/api/v1/grade")
+            .body(
+                r#"{
+                    "reference": { "start": "1", "alphabet": [], "nodes": {}, "final_states": [] },
+                    "student": { "start": "1", "alphabet": [], "nodes": { "1": {} },
+                                 "final_states": [] }
+                }"#,
+            )
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(
+            response.body_string().unwrap(),
+            "invalid reference: unknown state \"1\""
+        );
+    }
+
+    /// Test POSTing of NFA in the text format
+    #[test]
+    fn test_submit_text() {
+        let rocket = rocket();
+        let client = Client::new(rocket).expect("valid rocket instance");
+
+        let input = "start: 1
+            final: 3
+            1 -a-> 1, 2
+            1 -b-> 1
+            2 -a-> 3
+            2 -b-> 3
+            3 -a-> 1
+            3 -b-> 2";
+
+        let mut response = client
+            .post("/api/v1/submit")
+            .body(input)
+            .header(ContentType::Plain)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body_string: &str = &(response.body_string()).unwrap();
+        let _: Dfa = serde_json::from_str(body_string).unwrap();
+
+        let mut response = client
+            .post("/api/v1/submit")
+            .body("start: 1\n1 -a-> ")
+            .header(ContentType::Plain)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(
+            response.body_string().unwrap(),
+            "line 2, column 8: expected a state"
+        );
+    }
+
     #[test]
     fn test_double_submit_nfa_dfa() {
         let rocket = rocket();
@@ -171,7 +879,7 @@ mod test {
         }"#;
 
         let mut response = client
-            .post("/submit")
+            .post("/api/v1/submit")
             .body(input)
             .header(ContentType::JSON)
             .dispatch();
@@ -183,7 +891,7 @@ mod test {
 
         //Re-submit the DFA JSON we received
         let response = client
-            .post("/submit")
+            .post("/api/v1/submit")
             .body(dfa_json)
             .header(ContentType::JSON)
             .dispatch();
@@ -191,4 +899,632 @@ mod test {
         assert_eq!(response.status(), Status::Ok);
     }
 
+    /// Test tracing the subset construction
+    #[test]
+    fn test_trace_nfa() {
+        let rocket = rocket();
+        let client = Client::new(rocket).expect("valid rocket instance");
+
+        let input = r#"{
+            "start": "1",
+            "alphabet": ["a", "b"],
+            "nodes": {
+                "1": {
+                    "a": ["1", "2"],
+                    "b": ["1"]
+                },
+                "2": {
+                    "a": ["3"],
+                    "b": ["3"]
+                },
+                "3": {}
+            },
+            "final_states": ["3"]
+        }"#;
+
+        let mut response = client
+            .post("/api/v1/trace")
+            .body(input)
+            .header(ContentType::JSON)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body_string: &str = &(response.body_string()).unwrap();
+        let traced: serde_json::Value = serde_json::from_str(body_string).unwrap();
+        let _: Dfa = serde_json::from_value(traced["dfa"].clone()).unwrap();
+        let steps = traced["steps"].as_array().unwrap();
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps[0]["state"], serde_json::Value::String("1".into()));
+        assert_eq!(
+            steps[0]["transitions"][0]["status"],
+            serde_json::Value::String("new".into())
+        );
+    }
+
+    /// Test explaining minimisation
+    #[test]
+    fn test_explain_nfa() {
+        let rocket = rocket();
+        let client = Client::new(rocket).expect("valid rocket instance");
+
+        let input = r#"{
+            "start": "1",
+            "alphabet": ["a", "b"],
+            "nodes": {
+                "1": {
+                    "a": ["2"],
+                    "b": ["3"]
+                },
+                "2": {},
+                "3": {
+                    "a": ["4"],
+                    "b": ["1"]
+                },
+                "4": {}
+            },
+            "final_states": ["2", "4"]
+        }"#;
+
+        let mut response = client
+            .post("/api/v1/explain")
+            .body(input)
+            .header(ContentType::JSON)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body_string: &str = &(response.body_string()).unwrap();
+        let explained: serde_json::Value = serde_json::from_str(body_string).unwrap();
+        let _: Dfa = serde_json::from_value(explained["dfa"].clone()).unwrap();
+        let classes = explained["explanation"]["classes"].as_object().unwrap();
+        assert_eq!(classes.len(), 3);
+        let distinguished = explained["explanation"]["distinguished"].as_array().unwrap();
+        assert_eq!(distinguished.len(), 8);
+    }
+
+    /// Test reporting Myhill–Nerode equivalence classes
+    #[test]
+    fn test_nerode_nfa() {
+        let rocket = rocket();
+        let client = Client::new(rocket).expect("valid rocket instance");
+
+        // words ending in "ab"
+        let input = r#"{
+            "start": "1",
+            "alphabet": ["a", "b"],
+            "nodes": {
+                "1": {
+                    "a": ["1", "2"],
+                    "b": ["1"]
+                },
+                "2": {
+                    "b": ["3"]
+                },
+                "3": {}
+            },
+            "final_states": ["3"]
+        }"#;
+
+        let mut response = client
+            .post("/api/v1/nerode")
+            .body(input)
+            .header(ContentType::JSON)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body_string: &str = &(response.body_string()).unwrap();
+        let reported: serde_json::Value = serde_json::from_str(body_string).unwrap();
+        let _: Dfa = serde_json::from_value(reported["dfa"].clone()).unwrap();
+        let classes = reported["report"]["classes"].as_array().unwrap();
+        assert_eq!(classes.len(), 3);
+        assert_eq!(
+            classes[1]["access_word"][0],
+            serde_json::Value::String("a".into())
+        );
+        let distinguished = reported["report"]["distinguished"].as_array().unwrap();
+        assert_eq!(distinguished.len(), 3);
+    }
+
+    /// Test simulating a PDA
+    #[test]
+    fn test_simulate_pda() {
+        let rocket = rocket();
+        let client = Client::new(rocket).expect("valid rocket instance");
+
+        let input = r#"{
+            "pda": {
+                "start": "1",
+                "start_stack": "Z",
+                "accept_by": "empty_stack",
+                "alphabet": ["(", ")"],
+                "stack_alphabet": ["Z", "("],
+                "nodes": {
+                    "1": {
+                        "(": {
+                            "Z": [{ "state": "1", "push": ["(", "Z"] }],
+                            "(": [{ "state": "1", "push": ["(", "("] }]
+                        },
+                        ")": {
+                            "(": [{ "state": "1", "push": [] }]
+                        },
+                        "": {
+                            "Z": [{ "state": "1", "push": [] }]
+                        }
+                    }
+                }
+            },
+            "word": ["(", "(", ")", ")"]
+        }"#;
+
+        let mut response = client
+            .post("/api/v1/pda/simulate")
+            .body(input)
+            .header(ContentType::JSON)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body_string: &str = &(response.body_string()).unwrap();
+        let run: serde_json::Value = serde_json::from_str(body_string).unwrap();
+        assert_eq!(run["accepted"], serde_json::Value::Bool(true));
+        assert_eq!(run["traces"][0].as_array().unwrap().len(), 6);
+
+        let response = client
+            .post("/api/v1/pda/simulate")
+            .body(input.replace(r#""word": ["(""#, r#""word": ["[""#))
+            .header(ContentType::JSON)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::BadRequest);
+
+        // an epsilon loop that grows the stack forever runs into the stack limit, but the
+        // accepting run is still found
+        let mut response = client
+            .post("/api/v1/pda/simulate")
+            .body(input.replace(
+                r#""Z": [{ "state": "1", "push": [] }]"#,
+                r#""Z": [{ "state": "1", "push": [] }],
+                            "(": [{ "state": "1", "push": ["(", "("] }]"#,
+            ))
+            .header(ContentType::JSON)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body_string: &str = &(response.body_string()).unwrap();
+        let run: serde_json::Value = serde_json::from_str(body_string).unwrap();
+        assert_eq!(run["accepted"], serde_json::Value::Bool(true));
+        assert_eq!(run["exhausted"], serde_json::Value::Bool(true));
+    }
+
+    /// Test running a Turing machine
+    #[test]
+    fn test_simulate_turing() {
+        let rocket = rocket();
+        let client = Client::new(rocket).expect("valid rocket instance");
+
+        let input = r#"{
+            "machine": {
+                "start": "1",
+                "final_states": ["2"],
+                "alphabet": ["a"],
+                "tape_alphabet": ["a", "_"],
+                "blank": "_",
+                "nodes": {
+                    "1": {
+                        "a": { "state": "1", "write": "a", "move": "R" },
+                        "_": { "state": "2", "write": "a", "move": "S" }
+                    },
+                    "2": {}
+                }
+            },
+            "word": ["a", "a"]
+        }"#;
+
+        let mut response = client
+            .post("/api/v1/turing/simulate")
+            .body(input)
+            .header(ContentType::JSON)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let body_string: &str = &(response.body_string()).unwrap();
+        let run: serde_json::Value = serde_json::from_str(body_string).unwrap();
+        assert_eq!(run["outcome"], serde_json::Value::String("accept".into()));
+        assert_eq!(run["steps"].as_array().unwrap().len(), 4);
+        let tape: serde_json::Value = serde_json::from_str(r#"["a", "a", "a"]"#).unwrap();
+        assert_eq!(run["steps"][3]["tape"], tape);
+
+        // the input counts towards the length of the tape
+        let long_word = vec![r#""a""#; 1001].join(", ");
+        let response = client
+            .post("/api/v1/turing/simulate")
+            .body(input.replace(r#""a", "a"]"#, &format!("{}]", long_word)))
+            .header(ContentType::JSON)
+            .dispatch();
+
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    /// Test saving, fetching, updating and deleting automata
+    #[test]
+    fn test_library() {
+        let root = env::temp_dir().join("state-machina-test-library");
+        let _ = fs::remove_dir_all(&root);
+        let rocket = configured(vec![("library", root.to_str().unwrap().into())]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+
+        let nfa = r#"{
+            "start": "1",
+            "alphabet": ["a"],
+            "nodes": {
+                "1": { "a": ["2"] },
+                "2": { "a": ["1"] }
+            },
+            "final_states": ["2"]
+        }"#;
+        let input = format!(r#"{{ "name": "odd", "automaton": {} }}"#, nfa);
+
+        let mut response = client
+            .post("/api/v1/library")
+            .body(input.as_str())
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), Status::Created);
+        assert_eq!(response.headers().get_one("Location"), Some("/api/v1/library/odd"));
+        let entry: serde_json::Value =
+            serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        let _: Dfa = serde_json::from_value(entry["minimised"].clone()).unwrap();
+
+        let response = client
+            .post("/api/v1/library")
+            .body(input.as_str())
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), Status::Conflict);
+
+        let mut response = client.get("/api/v1/library").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.body_string().unwrap(), r#"["odd"]"#);
+
+        // a DFA can replace the NFA
+        let dfa = r#"{
+            "start": "1",
+            "alphabet": ["a"],
+            "nodes": {
+                "1": { "a": "1" }
+            },
+            "final_states": ["1"]
+        }"#;
+        let response = client
+            .put("/api/v1/library/odd")
+            .body(dfa)
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let mut response = client.get("/api/v1/library/odd").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let entry: serde_json::Value =
+            serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        assert_eq!(entry["input"]["nodes"]["1"]["a"], serde_json::Value::String("1".into()));
+
+        let response = client.delete("/api/v1/library/odd").dispatch();
+        assert_eq!(response.status(), Status::NoContent);
+        let response = client.get("/api/v1/library/odd").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        let response = client
+            .put("/api/v1/library/odd")
+            .body(dfa)
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response = client.get("/api/v1/library/..%2Fsecrets").dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Test encoding an automaton into a permalink and back
+    #[test]
+    fn test_permalink() {
+        let rocket = rocket();
+        let client = Client::new(rocket).expect("valid rocket instance");
+
+        let input = r#"{
+            "start": "1",
+            "alphabet": ["a", "b"],
+            "nodes": {
+                "1": { "a": ["1", "2"], "b": ["1"] },
+                "2": {}
+            },
+            "final_states": ["2"]
+        }"#;
+        let reordered = r#"{
+            "final_states": ["2"],
+            "nodes": {
+                "2": {},
+                "1": { "b": ["1"], "a": ["2", "1"] }
+            },
+            "alphabet": ["b", "a"],
+            "start": "1"
+        }"#;
+
+        let mut response = client
+            .post("/api/v1/permalink")
+            .body(input)
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let permalink: serde_json::Value =
+            serde_json::from_str(&response.body_string().unwrap()).unwrap();
+
+        let mut response = client
+            .post("/api/v1/permalink")
+            .body(reordered)
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let same: serde_json::Value =
+            serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        assert_eq!(permalink, same);
+
+        let mut response = client
+            .get(permalink["path"].as_str().unwrap())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let decoded: serde_json::Value =
+            serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(input).unwrap();
+        assert_eq!(decoded["start"], expected["start"]);
+        assert_eq!(decoded["nodes"]["1"]["b"], expected["nodes"]["1"]["b"]);
+
+        let response = client.get("/api/v1/permalink/not-a-token").dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    /// Checks a value against a schema from the API description. Supports the parts of JSON
+    /// Schema that the description uses, apart from patterns and bounds.
+    fn conforms(
+        root: &serde_json::Value,
+        schema: &serde_json::Value,
+        value: &serde_json::Value,
+        at: &str,
+    ) -> Result<(), String> {
+        use serde_json::Value;
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let target = root.pointer(&reference[1..])
+                .ok_or_else(|| format!("{}: unknown reference {}", at, reference))?;
+            return conforms(root, target, value, at);
+        }
+        if let Some(options) = schema.get("oneOf").and_then(Value::as_array) {
+            return match options
+                .iter()
+                .find(|option| conforms(root, option, value, at).is_ok())
+            {
+                Some(_) => Ok(()),
+                None => Err(format!("{}: matches none of the options", at)),
+            };
+        }
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                return Err(format!("{}: {} is not one of {:?}", at, value, allowed));
+            }
+        }
+
+        let matches = match schema.get("type").and_then(Value::as_str) {
+            Some("object") => value.is_object(),
+            Some("array") => value.is_array(),
+            Some("string") => value.is_string(),
+            Some("boolean") => value.is_boolean(),
+            Some("integer") => value.is_u64() || value.is_i64(),
+            _ => true,
+        };
+        if !matches {
+            return Err(format!("{}: {} is not of type {}", at, value, schema["type"]));
+        }
+
+        if let Some(object) = value.as_object() {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(key) {
+                        return Err(format!("{}: missing property {:?}", at, key));
+                    }
+                }
+            }
+            for (key, field) in object {
+                let field_schema = match schema.get("properties").and_then(|x| x.get(key)) {
+                    Some(field_schema) => field_schema,
+                    None => match schema.get("additionalProperties") {
+                        Some(&Value::Bool(false)) => {
+                            return Err(format!("{}: unexpected property {:?}", at, key))
+                        }
+                        Some(&Value::Bool(true)) | None => continue,
+                        Some(additional) => additional,
+                    },
+                };
+                conforms(root, field_schema, field, &format!("{}.{}", at, key))?;
+            }
+        }
+        if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+            for (i, item) in array.iter().enumerate() {
+                conforms(root, items, item, &format!("{}[{}]", at, i))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Test that every API route is described by the schema, and nothing else is
+    #[test]
+    fn test_schema_routes() {
+        let rocket = rocket();
+        let client = Client::new(rocket).expect("valid rocket instance");
+
+        let mut response = client.get("/api/v1/schema").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+        let schema: serde_json::Value =
+            serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        assert_eq!(schema, serde_json::from_str::<serde_json::Value>(SCHEMA).unwrap());
+
+        let mut routes: Vec<(String, String)> = api_routes(&Settings::default())
+            .iter()
+            .map(|route| {
+                // OpenAPI writes dynamic segments as {name} rather than <name>
+                let path = route.uri.path().replace('<', "{").replace('>', "}");
+                (path, route.method.to_string().to_lowercase())
+            })
+            .collect();
+        routes.sort();
+        routes.dedup();
+
+        let mut described = Vec::new();
+        for (path, operations) in schema["paths"].as_object().unwrap() {
+            for method in operations.as_object().unwrap().keys() {
+                if method != "parameters" {
+                    described.push((path.to_owned(), method.to_owned()));
+                }
+            }
+        }
+        described.sort();
+
+        assert_eq!(routes, described);
+    }
+
+    /// Test that requests and responses match the schema
+    #[test]
+    fn test_schema_payloads() {
+        let root = env::temp_dir().join("state-machina-test-schema");
+        let _ = fs::remove_dir_all(&root);
+        let rocket = configured(vec![("library", root.to_str().unwrap().into())]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let schema: serde_json::Value = serde_json::from_str(SCHEMA).unwrap();
+
+        let check = |path: &str, body: &str, request: &str, response: &str| {
+            let body: serde_json::Value = serde_json::from_str(body).unwrap();
+            let request = &schema["components"]["schemas"][request];
+            conforms(&schema, request, &body, path).unwrap();
+
+            let mut output = client
+                .post(path)
+                .body(body.to_string())
+                .header(ContentType::JSON)
+                .dispatch();
+            assert_eq!(output.status().code / 100, 2);
+            let output: serde_json::Value =
+                serde_json::from_str(&output.body_string().unwrap()).unwrap();
+            let response = &schema["components"]["schemas"][response];
+            conforms(&schema, response, &output, path).unwrap();
+            output
+        };
+
+        let nfa = r#"{
+            "start": "1",
+            "alphabet": ["a", "b"],
+            "nodes": {
+                "1": { "a": ["1", "2"], "b": ["1"] },
+                "2": { "a": ["3"], "b": ["3"] },
+                "3": {}
+            },
+            "final_states": ["3"]
+        }"#;
+        check("/api/v1/submit", nfa, "Nfa", "Dfa");
+        check("/api/v1/trace", nfa, "Nfa", "TracedDfa");
+        check("/api/v1/explain", nfa, "Nfa", "ExplainedDfa");
+        check("/api/v1/nerode", nfa, "Nfa", "NerodeDfa");
+        let entry = format!(r#"{{ "name": "example", "automaton": {} }}"#, nfa);
+        check("/api/v1/library", &entry, "NewEntry", "Entry");
+        let batch = format!(
+            r#"[{{ "name": "a", "nfa": {} }}, {{ "name": "b", "nfa": {{ "start": "9",
+                "alphabet": [], "nodes": {{}}, "final_states": [] }} }}]"#,
+            nfa
+        );
+        check("/api/v1/batch", &batch, "Batch", "BatchResults");
+        let generator = r#"{ "states": 6, "alphabet_size": 3, "seed": 9 }"#;
+        check("/api/v1/random/nfa", generator, "Generator", "Nfa");
+        check("/api/v1/random/dfa", generator, "Generator", "Dfa");
+        let grading = format!(r#"{{ "reference": {}, "student": {} }}"#, nfa, nfa);
+        check("/api/v1/grade", &grading, "Grading", "Grade");
+
+        let permalink = check("/api/v1/permalink", nfa, "Nfa", "Permalink");
+        let mut response = client
+            .get(permalink["path"].as_str().unwrap())
+            .dispatch();
+        let decoded: serde_json::Value =
+            serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        conforms(&schema, &schema["components"]["schemas"]["Nfa"], &decoded, "permalink")
+            .unwrap();
+
+        let pda = r#"{
+            "pda": {
+                "start": "1",
+                "start_stack": "Z",
+                "accept_by": "empty_stack",
+                "alphabet": ["a"],
+                "stack_alphabet": ["Z"],
+                "nodes": {
+                    "1": { "a": { "Z": [{ "state": "1", "push": [] }] } }
+                }
+            },
+            "word": ["a"]
+        }"#;
+        check("/api/v1/pda/simulate", pda, "PdaSimulation", "PdaRun");
+
+        let turing = r#"{
+            "machine": {
+                "start": "1",
+                "final_states": ["2"],
+                "alphabet": ["a"],
+                "tape_alphabet": ["a", "_"],
+                "blank": "_",
+                "nodes": {
+                    "1": { "a": { "state": "2", "write": "_", "move": "R" } },
+                    "2": {}
+                }
+            },
+            "word": ["a"],
+            "max_steps": 10
+        }"#;
+        check("/api/v1/turing/simulate", turing, "TuringSimulation", "TuringRun");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Test that settings change the server
+    #[test]
+    fn test_settings() {
+        let rocket = configured(vec![
+            ("endpoints", vec!["trace"].into()),
+            ("max_dfa_states", Value::Integer(2)),
+        ]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+
+        let input = "start: 1\nfinal: 2\n1 -a-> 2\n";
+        let mut response = client
+            .post("/api/v1/submit")
+            .body(input)
+            .header(ContentType::Plain)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(
+            response.body_string().unwrap(),
+            "limit exceeded: more than 2 DFA states"
+        );
+
+        let response = client.get("/api/v1/library").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+
+        // a missing frontend stops the server from launching, unless it is built in
+        let rocket = configured(vec![("assets", "does/not/exist".into())]);
+        assert_eq!(Client::new(rocket).is_err(), !assets::embedded());
+
+        let rocket = configured(vec![("endpoints", vec!["everything"].into())]);
+        assert!(Client::new(rocket).is_err());
+    }
 }
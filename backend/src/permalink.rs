@@ -0,0 +1,141 @@
+//! Permalinks, which encode an automaton into a URL-safe token.
+//!
+//! A token is the automaton in the text format, which lists everything in sorted order, deflated
+//! and then base64 encoded. The same automaton always gets the same token, however its maps
+//! happen to be ordered.
+
+use std::error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use base64::{self, DecodeError};
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+use state_machina::{Nfa, Sanitary, Unsanitary};
+use state_machina::text::{self, ParseError};
+
+/// The most bytes a token may inflate to, so that a small token cannot inflate into a huge
+/// automaton.
+const MAX_DECODED_LENGTH: u64 = 1 << 20;
+
+/// Errors from decoding a token.
+#[derive(Debug)]
+pub enum PermalinkError {
+    /// The token is not valid base64.
+    Base64(DecodeError),
+    /// The token does not inflate.
+    Io(io::Error),
+    /// The token inflates to more than the maximum length.
+    TooLarge,
+    /// The token inflates to something that is not UTF-8.
+    Utf8,
+    /// The token inflates to something that is not an automaton.
+    Parse(ParseError),
+}
+
+impl fmt::Display for PermalinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PermalinkError::Base64(ref err) => write!(f, "invalid token: {}", err),
+            PermalinkError::Io(ref err) => write!(f, "invalid token: {}", err),
+            PermalinkError::TooLarge => write!(
+                f,
+                "invalid token: longer than {} bytes when decoded",
+                MAX_DECODED_LENGTH
+            ),
+            PermalinkError::Utf8 => write!(f, "invalid token: not UTF-8 when decoded"),
+            PermalinkError::Parse(ref err) => write!(f, "invalid token: {}", err),
+        }
+    }
+}
+
+impl error::Error for PermalinkError {
+    fn description(&self) -> &str {
+        "invalid token"
+    }
+}
+
+impl From<DecodeError> for PermalinkError {
+    fn from(err: DecodeError) -> Self {
+        PermalinkError::Base64(err)
+    }
+}
+
+impl From<io::Error> for PermalinkError {
+    fn from(err: io::Error) -> Self {
+        PermalinkError::Io(err)
+    }
+}
+
+impl From<ParseError> for PermalinkError {
+    fn from(err: ParseError) -> Self {
+        PermalinkError::Parse(err)
+    }
+}
+
+/// Encodes an NFA into a token. Only checked NFAs are encoded, so that every token decodes into
+/// a valid NFA.
+pub fn encode(nfa: &Nfa<Sanitary>) -> String {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::Best);
+    encoder
+        .write_all(nfa.to_string().as_bytes())
+        .expect("writing to a vector cannot fail");
+    let compressed = encoder.finish().expect("writing to a vector cannot fail");
+    base64::encode_config(&compressed, base64::URL_SAFE_NO_PAD)
+}
+
+/// Decodes a token back into an NFA.
+pub fn decode(token: &str) -> Result<Nfa<Unsanitary>, PermalinkError> {
+    let compressed = base64::decode_config(token, base64::URL_SAFE_NO_PAD)?;
+    let mut decoded = Vec::new();
+    DeflateDecoder::new(compressed.as_slice())
+        .take(MAX_DECODED_LENGTH + 1)
+        .read_to_end(&mut decoded)?;
+    if decoded.len() as u64 > MAX_DECODED_LENGTH {
+        return Err(PermalinkError::TooLarge);
+    }
+    let decoded = String::from_utf8(decoded).map_err(|_| PermalinkError::Utf8)?;
+    Ok(text::parse_nfa(&decoded)?)
+}
+
+#[cfg(test)]
+use state_machina::NfaBuilder;
+
+#[test]
+fn permalink_round_trip() {
+    let nfa = || {
+        NfaBuilder::new()
+            .start("q0")
+            .transition("q0", "a", "q0")
+            .transition("q0", "a", "q1")
+            .transition("q1", "b", "final state")
+            .final_state("final state")
+            .check()
+            .unwrap()
+    };
+
+    // rebuilding the NFA orders its maps differently, but gives the same token
+    let token = encode(&nfa());
+    for _ in 0..10 {
+        assert_eq!(encode(&nfa()), token);
+    }
+    assert!(
+        token
+            .chars()
+            .all(|c| c.is_digit(36) || c == '-' || c == '_')
+    );
+
+    let decoded = decode(&token).unwrap().check().unwrap();
+    assert_eq!(decoded.to_string(), nfa().to_string());
+
+    match decode("not a token!").unwrap_err() {
+        PermalinkError::Base64(_) => {}
+        err @ _ => panic!(err),
+    }
+    match decode("AAAA").unwrap_err() {
+        PermalinkError::Io(_) => {}
+        err @ _ => panic!(err),
+    }
+}
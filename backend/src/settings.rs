@@ -0,0 +1,389 @@
+//! Server settings, read from the extras in `Rocket.toml` or from `ROCKET_<NAME>` environment
+//! variables.
+//!
+//! | Setting                | Default               | Meaning                                      |
+//! |------------------------|-----------------------|----------------------------------------------|
+//! | `assets`               | `"../frontend/build"` | Directory the frontend is served from.       |
+//! | `library`              | `"library"`           | Directory saved automata are kept in.        |
+//! | `max_nfa_states`       | `1000`                | Most states a submitted NFA may have.        |
+//! | `max_alphabet_size`    | `256`                 | Most symbols a submitted alphabet may have.  |
+//! | `max_dfa_states`       | `10000`               | Most states a DFA may have.                  |
+//! | `max_explained_states` | `1000`                | Most states of a DFA compared pairwise.      |
+//! | `time_limit_ms`        | `5000`                | Longest a conversion or simulation runs.     |
+//! | `max_pda_steps`        | `10000`               | Most configurations a PDA simulation visits. |
+//! | `max_stack_size`       | `1000`                | Most symbols on a simulated PDA's stack.     |
+//! | `max_turing_steps`     | `1000`                | Most steps a Turing machine run takes.       |
+//! | `max_tape_length`      | `1000`                | Most cells on a Turing machine's tape.       |
+//! | `max_batch_size`       | `1000`                | Most automata a single batch may have.       |
+//! | `batch_time_limit_ms`  | `30000`               | Longest a whole batch runs.                  |
+//! | `max_random_states`    | `100`                 | Most states a random automaton may have.     |
+//! | `max_grading_length`   | `20`                  | Longest words compared when grading.         |
+//! | `endpoints`            | every endpoint        | Optional endpoints to enable, by name.       |
+//!
+//! Setting a limit to `0` removes it, except for `max_pda_steps`, `max_turing_steps` and
+//! `max_grading_length`. Simulations can run forever and grading has to stop at some length, so
+//! these are always bounded, and `0` allows no steps, or only the empty word. `assets` is
+//! ignored when the frontend is built into the binary with the `embed-frontend` feature.
+
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rocket::config::{Config, Value};
+
+use state_machina::Limits;
+
+/// The default for how long a conversion or minimisation may run, in milliseconds.
+const DEFAULT_TIME_LIMIT_MS: usize = 5_000;
+
+/// The default for how long a whole batch may run, in milliseconds.
+const DEFAULT_BATCH_TIME_LIMIT_MS: usize = 30_000;
+
+/// An optional group of endpoints, which can be turned off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// Tracing the subset construction.
+    Trace,
+    /// Explaining minimisation.
+    Explain,
+    /// Simulating pushdown automata.
+    Pda,
+    /// Running Turing machines.
+    Turing,
+    /// Encoding and decoding permalinks.
+    Permalink,
+    /// The library of saved automata.
+    Library,
+    /// Converting many automata at once.
+    Batch,
+    /// Generating random automata.
+    Random,
+    /// Grading automata against a reference.
+    Grade,
+    /// Reporting the Myhill–Nerode equivalence classes of a language.
+    Nerode,
+}
+
+impl Endpoint {
+    /// Every optional endpoint.
+    pub const ALL: [Endpoint; 10] = [
+        Endpoint::Trace,
+        Endpoint::Explain,
+        Endpoint::Pda,
+        Endpoint::Turing,
+        Endpoint::Permalink,
+        Endpoint::Library,
+        Endpoint::Batch,
+        Endpoint::Random,
+        Endpoint::Grade,
+        Endpoint::Nerode,
+    ];
+
+    /// The name the endpoint is enabled by.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Endpoint::Trace => "trace",
+            Endpoint::Explain => "explain",
+            Endpoint::Pda => "pda",
+            Endpoint::Turing => "turing",
+            Endpoint::Permalink => "permalink",
+            Endpoint::Library => "library",
+            Endpoint::Batch => "batch",
+            Endpoint::Random => "random",
+            Endpoint::Grade => "grade",
+            Endpoint::Nerode => "nerode",
+        }
+    }
+}
+
+/// A setting that could not be read.
+#[derive(Debug)]
+pub struct SettingsError {
+    /// The name of the setting.
+    pub setting: &'static str,
+
+    /// What was wrong with it.
+    pub message: String,
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid `{}` setting: {}", self.setting, self.message)
+    }
+}
+
+impl error::Error for SettingsError {
+    fn description(&self) -> &str {
+        "invalid setting"
+    }
+}
+
+/// Everything about the server that can be configured.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// The directory the frontend is served from, unless it is built into the binary.
+    pub assets: PathBuf,
+
+    /// The directory saved automata are kept in.
+    pub library: PathBuf,
+
+    /// The limits on converting, minimising and simulating submitted automata, so that a single
+    /// request cannot tie up the server with an exponential subset construction, an ever
+    /// growing stack or a long tape copied on every step.
+    pub limits: Limits,
+
+    /// The most configurations a single PDA simulation may explore.
+    pub max_pda_steps: usize,
+
+    /// The most steps a single Turing machine simulation may take. Lower than for PDAs by
+    /// default, as every step returns a copy of the tape, which is bounded by
+    /// `limits.max_tape_length`.
+    pub max_turing_steps: usize,
+
+    /// The most automata a single batch may have, if there is a limit.
+    pub max_batch_size: Option<usize>,
+
+    /// How long a whole batch may take, if there is a limit, so that a batch cannot tie up the
+    /// threads converting it for the time limit of every automaton in it.
+    pub batch_time_limit: Option<Duration>,
+
+    /// The most states a random automaton may have, if there is a limit. Lower than for
+    /// submitted NFAs by default, as a dense random NFA has a transition for nearly every pair of
+    /// states.
+    pub max_random_states: Option<usize>,
+
+    /// The longest words compared for the similarity score when grading. The number of words
+    /// grows exponentially with their length, but they are counted rather than enumerated, so
+    /// this only bounds the work linearly.
+    pub max_grading_length: usize,
+
+    /// The optional endpoints that are enabled.
+    pub endpoints: HashSet<Endpoint>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            assets: PathBuf::from("../frontend/build"),
+            library: PathBuf::from("library"),
+            limits: Limits {
+                max_nfa_states: Some(1_000),
+                max_alphabet_size: Some(256),
+                max_dfa_states: Some(10_000),
+                max_explained_states: Some(1_000),
+                max_stack_size: Some(1_000),
+                max_tape_length: Some(1_000),
+                time: Some(Duration::from_millis(DEFAULT_TIME_LIMIT_MS as u64)),
+            },
+            max_pda_steps: 10_000,
+            max_turing_steps: 1_000,
+            max_batch_size: Some(1_000),
+            batch_time_limit: Some(Duration::from_millis(DEFAULT_BATCH_TIME_LIMIT_MS as u64)),
+            max_random_states: Some(100),
+            max_grading_length: 20,
+            endpoints: Endpoint::ALL.iter().cloned().collect(),
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings from the Rocket configuration, using the defaults for anything that
+    /// is not set.
+    pub fn from_config(config: &Config) -> Result<Self, SettingsError> {
+        let defaults = Settings::default();
+
+        let assets = optional(config, "assets", "a path", Value::as_str)?
+            .map_or(defaults.assets, PathBuf::from);
+        let library = optional(config, "library", "a path", Value::as_str)?
+            .map_or(defaults.library, PathBuf::from);
+
+        let limits = Limits {
+            max_nfa_states: limit(config, "max_nfa_states", defaults.limits.max_nfa_states)?,
+            max_alphabet_size: limit(
+                config,
+                "max_alphabet_size",
+                defaults.limits.max_alphabet_size,
+            )?,
+            max_dfa_states: limit(config, "max_dfa_states", defaults.limits.max_dfa_states)?,
+            max_explained_states: limit(
+                config,
+                "max_explained_states",
+                defaults.limits.max_explained_states,
+            )?,
+            max_stack_size: limit(config, "max_stack_size", defaults.limits.max_stack_size)?,
+            max_tape_length: limit(config, "max_tape_length", defaults.limits.max_tape_length)?,
+            time: match limit(config, "time_limit_ms", Some(DEFAULT_TIME_LIMIT_MS))? {
+                Some(milliseconds) => Some(Duration::from_millis(milliseconds as u64)),
+                None => None,
+            },
+        };
+
+        let max_pda_steps = count(config, "max_pda_steps")?.unwrap_or(defaults.max_pda_steps);
+        let max_turing_steps =
+            count(config, "max_turing_steps")?.unwrap_or(defaults.max_turing_steps);
+        let max_batch_size = limit(config, "max_batch_size", defaults.max_batch_size)?;
+        let batch_time_limit = match limit(
+            config,
+            "batch_time_limit_ms",
+            Some(DEFAULT_BATCH_TIME_LIMIT_MS),
+        )? {
+            Some(milliseconds) => Some(Duration::from_millis(milliseconds as u64)),
+            None => None,
+        };
+        let max_random_states =
+            limit(config, "max_random_states", defaults.max_random_states)?;
+        let max_grading_length =
+            count(config, "max_grading_length")?.unwrap_or(defaults.max_grading_length);
+
+        let endpoints = match optional(config, "endpoints", "a list", Value::as_array)? {
+            Some(names) => {
+                let mut endpoints = HashSet::new();
+                for name in names {
+                    let name = name.as_str().ok_or_else(|| SettingsError {
+                        setting: "endpoints",
+                        message: format!("{} is not a name", name),
+                    })?;
+                    let endpoint = Endpoint::ALL
+                        .iter()
+                        .find(|x| x.name() == name)
+                        .ok_or_else(|| SettingsError {
+                            setting: "endpoints",
+                            message: format!(
+                                "unknown endpoint {:?}, expected one of {}",
+                                name,
+                                Endpoint::ALL
+                                    .iter()
+                                    .map(|x| x.name())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ),
+                        })?;
+                    endpoints.insert(*endpoint);
+                }
+                endpoints
+            }
+            None => defaults.endpoints,
+        };
+
+        Ok(Settings {
+            assets,
+            library,
+            limits,
+            max_pda_steps,
+            max_turing_steps,
+            max_batch_size,
+            batch_time_limit,
+            max_random_states,
+            max_grading_length,
+            endpoints,
+        })
+    }
+
+    /// Whether an optional endpoint is enabled.
+    pub fn enabled(&self, endpoint: Endpoint) -> bool {
+        self.endpoints.contains(&endpoint)
+    }
+}
+
+/// Reads a setting, if it is set, failing if it is not what was expected.
+fn optional<'a, T, F>(
+    config: &'a Config,
+    setting: &'static str,
+    expected: &str,
+    convert: F,
+) -> Result<Option<T>, SettingsError>
+where
+    F: FnOnce(&'a Value) -> Option<T>,
+{
+    match config.extras.get(setting) {
+        Some(value) => convert(value).map(Some).ok_or_else(|| SettingsError {
+            setting,
+            message: format!("expected {}, found {}", expected, value),
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Reads a setting that must be a non-negative integer, if it is set.
+fn count(config: &Config, setting: &'static str) -> Result<Option<usize>, SettingsError> {
+    match optional(config, setting, "a number", Value::as_integer)? {
+        Some(value) if value < 0 => Err(SettingsError {
+            setting,
+            message: format!("{} is negative", value),
+        }),
+        value => Ok(value.map(|x| x as usize)),
+    }
+}
+
+/// Reads a limit, where `0` means that there is no limit.
+fn limit(
+    config: &Config,
+    setting: &'static str,
+    default: Option<usize>,
+) -> Result<Option<usize>, SettingsError> {
+    Ok(match count(config, setting)? {
+        Some(0) => None,
+        Some(value) => Some(value),
+        None => default,
+    })
+}
+
+#[cfg(test)]
+use rocket::config::Environment;
+
+#[test]
+fn read_settings() {
+    let config = Config::build(Environment::Development).finalize().unwrap();
+    let settings = Settings::from_config(&config).unwrap();
+    assert_eq!(settings.assets, Settings::default().assets);
+    assert_eq!(settings.limits, Settings::default().limits);
+    assert!(settings.enabled(Endpoint::Library));
+
+    let config = Config::build(Environment::Development)
+        .extra("assets", "/srv/state-machina")
+        .extra("max_dfa_states", 0)
+        .extra("max_explained_states", 50)
+        .extra("time_limit_ms", 250)
+        .extra("max_turing_steps", 20)
+        .extra("max_stack_size", 64)
+        .extra("max_tape_length", 0)
+        .extra("max_batch_size", 0)
+        .extra("batch_time_limit_ms", 1000)
+        .extra("endpoints", vec!["trace", "pda"])
+        .finalize()
+        .unwrap();
+    let settings = Settings::from_config(&config).unwrap();
+    assert_eq!(settings.assets, PathBuf::from("/srv/state-machina"));
+    assert_eq!(settings.limits.max_dfa_states, None);
+    assert_eq!(settings.limits.max_explained_states, Some(50));
+    assert_eq!(settings.limits.time, Some(Duration::from_millis(250)));
+    assert_eq!(settings.limits.max_stack_size, Some(64));
+    assert_eq!(settings.limits.max_tape_length, None);
+    assert_eq!(settings.max_turing_steps, 20);
+    assert_eq!(settings.max_pda_steps, 10_000);
+    assert_eq!(settings.max_batch_size, None);
+    assert_eq!(settings.batch_time_limit, Some(Duration::from_secs(1)));
+    assert!(settings.enabled(Endpoint::Trace));
+    assert!(!settings.enabled(Endpoint::Library));
+
+    let config = Config::build(Environment::Development)
+        .extra("endpoints", vec!["trace", "sorting"])
+        .finalize()
+        .unwrap();
+    assert_eq!(
+        Settings::from_config(&config).unwrap_err().to_string(),
+        "invalid `endpoints` setting: unknown endpoint \"sorting\", expected one of trace, \
+         explain, pda, turing, permalink, library, batch, random, grade, nerode"
+    );
+
+    let config = Config::build(Environment::Development)
+        .extra("max_pda_steps", -1)
+        .finalize()
+        .unwrap();
+    assert_eq!(
+        Settings::from_config(&config).unwrap_err().to_string(),
+        "invalid `max_pda_steps` setting: -1 is negative"
+    );
+}
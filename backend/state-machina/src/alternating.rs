@@ -0,0 +1,650 @@
+//! Alternating finite automata, whose transitions can require several runs to accept at once.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use automata::{Nfa, NfaError, Sanitary, Unsanitary};
+use limits::{Budget, Limit, Limits};
+use symbols;
+
+/// A positive boolean formula over states, describing which states the rest of a word must be
+/// accepted from. In JSON, formulas are written as strings like `"1 & (2 | 3)"`, where `&` binds
+/// more tightly than `|`, and `true` and `false` are constants. State names in formulas cannot
+/// contain spaces, brackets, `&` or `|`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Formula {
+    /// Always satisfied, so the rest of the word is always accepted.
+    True,
+    /// Never satisfied, so the rest of the word is never accepted.
+    False,
+    /// The rest of the word must be accepted from a state.
+    State(String),
+    /// Every formula must be satisfied.
+    And(Vec<Formula>),
+    /// At least one formula must be satisfied.
+    Or(Vec<Formula>),
+}
+
+impl Formula {
+    /// Parses a formula, returning the byte offset where it is invalid and why if it is not.
+    pub fn parse(formula: &str) -> Result<Formula, (usize, &'static str)> {
+        let mut parser = FormulaParser {
+            formula,
+            chars: formula.char_indices().peekable(),
+        };
+        let parsed = parser.or()?;
+        match parser.token() {
+            None => Ok(parsed),
+            Some((position, _)) => Err((position, "unmatched `)`")),
+        }
+    }
+
+    /// All of the states the formula mentions.
+    fn states<'a>(&'a self, states: &mut Vec<&'a String>) {
+        match *self {
+            Formula::True | Formula::False => {}
+            Formula::State(ref state) => states.push(state),
+            Formula::And(ref parts) | Formula::Or(ref parts) => for part in parts {
+                part.states(states);
+            },
+        }
+    }
+
+    /// The smallest sets of states that satisfy the formula, none of which contain another.
+    /// There can be exponentially many, so this stops once the time is up.
+    fn models(&self, budget: &Budget) -> Result<Vec<BTreeSet<&str>>, NfaError> {
+        Ok(match *self {
+            Formula::True => vec![BTreeSet::new()],
+            Formula::False => vec![],
+            Formula::State(ref state) => vec![Some(state.as_str()).into_iter().collect()],
+            Formula::And(ref parts) => {
+                let mut models = vec![BTreeSet::new()];
+                for part in parts {
+                    let part = part.models(budget)?;
+                    models = minimal(
+                        models
+                            .iter()
+                            .flat_map(|left| part.iter().map(move |right| left | right))
+                            .collect(),
+                        budget,
+                    )?;
+                }
+                models
+            }
+            Formula::Or(ref parts) => {
+                let mut models = Vec::new();
+                for part in parts {
+                    models.extend(part.models(budget)?);
+                }
+                minimal(models, budget)?
+            }
+        })
+    }
+
+    /// Whether a state can be referred to in a formula.
+    fn valid_state(state: &str) -> bool {
+        !state.is_empty() && state != "true" && state != "false" &&
+            !state.chars().any(|c| c.is_whitespace() || "()&|".contains(c))
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Formula::True => write!(f, "true"),
+            Formula::False => write!(f, "false"),
+            Formula::State(ref state) => write!(f, "{}", state),
+            Formula::And(ref parts) => {
+                for (i, part) in parts.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " & ")?;
+                    }
+                    match *part {
+                        Formula::Or(_) => write!(f, "({})", part)?,
+                        _ => write!(f, "{}", part)?,
+                    }
+                }
+                Ok(())
+            }
+            Formula::Or(ref parts) => {
+                for (i, part) in parts.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{}", part)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Formula {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let formula = String::deserialize(deserializer)?;
+        Formula::parse(&formula).map_err(|(position, reason)| {
+            de::Error::custom(format!(
+                "invalid formula {:?} at {}: {}",
+                formula,
+                position,
+                reason
+            ))
+        })
+    }
+}
+
+impl Serialize for Formula {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+struct FormulaParser<'a> {
+    formula: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> FormulaParser<'a> {
+    /// The next character that is not whitespace, without consuming it.
+    fn token(&mut self) -> Option<(usize, char)> {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+        self.chars.peek().cloned()
+    }
+
+    fn or(&mut self) -> Result<Formula, (usize, &'static str)> {
+        let mut parts = vec![self.and()?];
+        while let Some((_, '|')) = self.token() {
+            self.chars.next();
+            parts.push(self.and()?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.pop().unwrap()
+        } else {
+            Formula::Or(parts)
+        })
+    }
+
+    fn and(&mut self) -> Result<Formula, (usize, &'static str)> {
+        let mut parts = vec![self.atom()?];
+        while let Some((_, '&')) = self.token() {
+            self.chars.next();
+            parts.push(self.atom()?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.pop().unwrap()
+        } else {
+            Formula::And(parts)
+        })
+    }
+
+    fn atom(&mut self) -> Result<Formula, (usize, &'static str)> {
+        match self.token() {
+            None => Err((self.formula.len(), "expected a state")),
+            Some((position, '(')) => {
+                self.chars.next();
+                let formula = self.or()?;
+                match self.token() {
+                    Some((_, ')')) => {
+                        self.chars.next();
+                        Ok(formula)
+                    }
+                    _ => Err((position, "unclosed `(`")),
+                }
+            }
+            Some((position, ')')) | Some((position, '&')) | Some((position, '|')) => {
+                Err((position, "expected a state"))
+            }
+            Some((start, _)) => {
+                let mut end = self.formula.len();
+                while let Some(&(position, c)) = self.chars.peek() {
+                    if c.is_whitespace() || "()&|".contains(c) {
+                        end = position;
+                        break;
+                    }
+                    self.chars.next();
+                }
+                Ok(match &self.formula[start..end] {
+                    "true" => Formula::True,
+                    "false" => Formula::False,
+                    state => Formula::State(state.to_owned()),
+                })
+            }
+        }
+    }
+}
+
+/// Removes the sets that contain another, along with any duplicates, stopping once the time is
+/// up.
+fn minimal<'a>(
+    sets: Vec<BTreeSet<&'a str>>,
+    budget: &Budget,
+) -> Result<Vec<BTreeSet<&'a str>>, NfaError> {
+    let mut kept: Vec<BTreeSet<&str>> = Vec::new();
+    for set in sets {
+        budget.check()?;
+        if kept.iter().any(|other| other.is_subset(&set)) {
+            continue;
+        }
+        kept.retain(|other| !set.is_subset(other));
+        kept.push(set);
+    }
+    Ok(kept)
+}
+
+/// The name of a set of states, like `{1, 2}`.
+fn set_name(states: &BTreeSet<&str>) -> String {
+    let states: Vec<&str> = states.iter().cloned().collect();
+    format!("{{{}}}", states.join(", "))
+}
+
+/// Alternating finite automata. Each transition is a [`Formula`](enum.Formula.html) over
+/// states, and a word is accepted from a state when the rest of it is accepted from a set of
+/// states that satisfies the formula on its first symbol. The empty word is accepted from the
+/// final states. States without a transition on a symbol reject words starting with it, as
+/// though the transition were `false`.
+///
+/// Transitions are labelled with symbols, classes of symbols or `*`, like those of an
+/// [`Nfa`](struct.Nfa.html), and where several labels contain a symbol, any of their formulas
+/// may be satisfied.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Afa<T> {
+    /// Marker to ensure that the automata has been validated before it is used for any
+    /// computations.
+    #[serde(skip_serializing)]
+    pub(crate) _sanitized: T,
+
+    /// Start state.
+    pub(crate) start: String,
+
+    /// The states that accept the empty word.
+    pub(crate) final_states: HashSet<String>,
+
+    /// The alphabet of symbols the automata accepts.
+    pub(crate) alphabet: HashSet<String>,
+
+    /// The nodes within the automata. Each node has mappings from alphabet symbols to formulas
+    /// over other states.
+    pub(crate) nodes: HashMap<String, HashMap<String, Formula>>,
+}
+
+impl<T> Afa<T> {
+    /// The start state.
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// The states that accept the empty word.
+    pub fn final_states(&self) -> &HashSet<String> {
+        &self.final_states
+    }
+
+    /// The alphabet of symbols the automata accepts.
+    pub fn alphabet(&self) -> &HashSet<String> {
+        &self.alphabet
+    }
+
+    /// The transitions out of a state, as a mapping from symbols to formulas. Returns `None` if
+    /// there is no such state.
+    pub fn transitions(&self, state: &str) -> Option<&HashMap<String, Formula>> {
+        self.nodes.get(state)
+    }
+}
+
+impl Afa<Unsanitary> {
+    /// Creates an AFA from its parts. Nothing is validated until the AFA is checked.
+    pub fn new(
+        start: String,
+        final_states: HashSet<String>,
+        alphabet: HashSet<String>,
+        nodes: HashMap<String, HashMap<String, Formula>>,
+    ) -> Self {
+        Afa {
+            _sanitized: Unsanitary,
+            start,
+            final_states,
+            alphabet,
+            nodes,
+        }
+    }
+
+    /// Ensures that the AFA is valid: that every state can be referred to in a formula, that
+    /// every state referred to, including within formulas, exists, and that every transition is
+    /// on symbols within the alphabet.
+    pub fn check(self) -> Result<Afa<Sanitary>, NfaError> {
+        let Afa {
+            start,
+            final_states,
+            alphabet,
+            nodes,
+            ..
+        } = self;
+
+        // formulas cannot refer to states with names that are part of their syntax
+        if let Some(invalid_state) = nodes.keys().find(|state| !Formula::valid_state(state)) {
+            return Err(NfaError::InvalidStateName(invalid_state.to_owned()));
+        }
+        let mut states = vec![&start];
+        states.extend(&final_states);
+        for formula in nodes.values().flat_map(|transitions| transitions.values()) {
+            formula.states(&mut states);
+        }
+        if let Some(unknown_state) = states.into_iter().find(|&state| !nodes.contains_key(state)) {
+            return Err(NfaError::UnknownState(unknown_state.to_owned()));
+        }
+
+        let symbols = symbols::check_alphabet(&alphabet)?;
+        for transitions in nodes.values() {
+            for symbol in transitions.keys() {
+                if !alphabet.contains(symbol) {
+                    symbols::check_label(symbol, &symbols)?;
+                }
+            }
+        }
+
+        Ok(Afa {
+            _sanitized: Sanitary,
+            start,
+            final_states,
+            alphabet,
+            nodes,
+        })
+    }
+}
+
+impl Afa<Sanitary> {
+    /// Runs the AFA over a word, and reports whether the word is accepted. This keeps track of
+    /// every set of states the rest of the word must be accepted from, leaving out those that
+    /// contain another, as they can only accept fewer words.
+    pub fn accepts<'a, I>(&self, word: I) -> Result<bool, NfaError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let budget = Budget::unlimited();
+        let mut current = vec![Some(self.start.as_str()).into_iter().collect()];
+        for letter in word {
+            if !symbols::in_alphabet(&self.alphabet, letter) {
+                return Err(NfaError::UnknownSymbol(letter.to_owned()));
+            }
+            let mut next = Vec::new();
+            for states in &current {
+                next.extend(self.step(states, letter, &budget)?);
+            }
+            current = minimal(next, &budget)?;
+        }
+
+        Ok(current.iter().any(|states| {
+            states.iter().all(|&state| self.final_states.contains(state))
+        }))
+    }
+
+    /// Converts the AFA into an equivalent NFA. Each NFA state is a set of AFA states that the
+    /// rest of a word must be accepted from, named like `{1, 2}`, and is final when all of them
+    /// are. The empty set, `{}`, accepts everything. Only the sets reachable from the start,
+    /// `{start}`, are built, and sets are left out wherever another set on the same transition
+    /// is contained in them.
+    ///
+    /// The NFA can have exponentially many states, so this stops once it goes over
+    /// `max_nfa_states`. It has the same alphabet as the AFA, with its transitions split into
+    /// disjoint classes where labels overlap.
+    pub fn to_nfa(&self, limits: &Limits) -> Result<Nfa<Sanitary>, NfaError> {
+        let budget = Budget::start(limits);
+        let atoms: Vec<(String, String)> = symbols::minterms(
+            self.alphabet
+                .iter()
+                .chain(self.nodes.values().flat_map(|transitions| transitions.keys())),
+        ).into_iter()
+            .map(|(label, set)| (label, set.representative().unwrap()))
+            .collect();
+        Limits::ensure(limits.max_alphabet_size, atoms.len(), Limit::AlphabetSize)?;
+
+        let start: BTreeSet<&str> = Some(self.start.as_str()).into_iter().collect();
+        let mut seen = HashSet::new();
+        seen.insert(start.clone());
+        let mut work = VecDeque::new();
+        work.push_back(start.clone());
+        let mut final_states = HashSet::new();
+        let mut nodes = HashMap::new();
+
+        while let Some(states) = work.pop_front() {
+            budget.check()?;
+            let name = set_name(&states);
+            if states.iter().all(|&state| self.final_states.contains(state)) {
+                final_states.insert(name.to_owned());
+            }
+
+            let mut transitions = HashMap::new();
+            for &(ref label, ref symbol) in &atoms {
+                let targets = self.step(&states, symbol, &budget)?;
+                if targets.is_empty() {
+                    continue;
+                }
+                // every target is a state of the NFA
+                Limits::ensure(limits.max_nfa_states, targets.len(), Limit::NfaStates)?;
+                let names = targets.iter().map(set_name).collect();
+                for target in targets {
+                    if seen.insert(target.clone()) {
+                        Limits::ensure(limits.max_nfa_states, seen.len(), Limit::NfaStates)?;
+                        work.push_back(target);
+                    }
+                }
+                transitions.insert(label.to_owned(), names);
+            }
+            nodes.insert(name, transitions);
+        }
+
+        Ok(Nfa {
+            _sanitized: Sanitary,
+            start: set_name(&start),
+            final_states,
+            alphabet: self.alphabet.clone(),
+            nodes,
+        })
+    }
+
+    /// The sets of states that a set of states can move to on a symbol, none of which contain
+    /// another. Every state's formula has to be satisfied at once.
+    fn step<'a>(
+        &'a self,
+        states: &BTreeSet<&str>,
+        symbol: &str,
+        budget: &Budget,
+    ) -> Result<Vec<BTreeSet<&'a str>>, NfaError> {
+        let mut sets = vec![BTreeSet::new()];
+        for &state in states {
+            let mut models = Vec::new();
+            for formula in symbols::follow(&self.nodes[state], symbol) {
+                models.extend(formula.models(budget)?);
+            }
+            let models = minimal(models, budget)?;
+            sets = minimal(
+                sets.iter()
+                    .flat_map(|left| models.iter().map(move |right| left | right))
+                    .collect(),
+                budget,
+            )?;
+        }
+        Ok(sets)
+    }
+}
+
+#[cfg(test)]
+use serde_json;
+
+#[cfg(test)]
+use automata::Dfa;
+#[cfg(test)]
+use std::time::{Duration, Instant};
+
+#[test]
+fn parse_formulas() {
+    assert_eq!(
+        Formula::parse("1 & (2 | true) | false").unwrap(),
+        Formula::Or(vec![
+            Formula::And(vec![
+                Formula::State("1".to_string()),
+                Formula::Or(vec![Formula::State("2".to_string()), Formula::True]),
+            ]),
+            Formula::False,
+        ])
+    );
+    assert_eq!(
+        Formula::parse("a&(b|c)").unwrap().to_string(),
+        "a & (b | c)"
+    );
+    assert_eq!(Formula::parse("(a | b").unwrap_err(), (0, "unclosed `(`"));
+    assert_eq!(Formula::parse("a)").unwrap_err(), (1, "unmatched `)`"));
+    assert_eq!(Formula::parse("a & | b").unwrap_err(), (4, "expected a state"));
+    assert_eq!(Formula::parse(" ").unwrap_err(), (1, "expected a state"));
+}
+
+#[test]
+fn afa_to_nfa() {
+    // words containing both an "a" and a "b"
+    let afa: Afa<Unsanitary> = serde_json::from_str(
+        r#"{
+            "start": "both",
+            "final_states": ["done"],
+            "alphabet": ["a", "b", "c"],
+            "nodes": {
+                "both": { "a": "has_b", "b": "has_a", "c": "has_a & has_b" },
+                "has_a": { "a": "done", "*": "has_a" },
+                "has_b": { "b": "done", "*": "has_b" },
+                "done": { "*": "done" }
+            }
+        }"#,
+    ).unwrap();
+    let afa = afa.check().unwrap();
+    let accepts = |word: &str| {
+        afa.accepts(word.split("").filter(|x| !x.is_empty()))
+            .unwrap()
+    };
+    assert!(accepts("ab"));
+    assert!(accepts("cbca"));
+    assert!(!accepts("aaca"));
+    assert!(!accepts(""));
+    assert!(afa.accepts(vec!["d"]).is_err());
+
+    let nfa = afa.to_nfa(&Limits::default()).unwrap();
+    assert_eq!(nfa.start(), "{both}");
+    assert_eq!(
+        nfa.transition("{both}", "c").unwrap(),
+        &Some("{has_a, has_b}".to_string()).into_iter().collect()
+    );
+    assert!(nfa.final_states().contains("{done}"));
+    let dfa: Dfa = nfa.clone().make_deterministic().minimise();
+    for word in vec!["ab", "cbca", "aaca", "", "bbbbc", "ccbab"] {
+        let letters = || word.split("").filter(|x| !x.is_empty());
+        assert_eq!(nfa.accepts(letters()).unwrap(), accepts(word));
+        assert_eq!(dfa.accepts(letters()).unwrap(), accepts(word));
+    }
+
+    let limits = Limits {
+        max_nfa_states: Some(2),
+        ..Limits::default()
+    };
+    match afa.to_nfa(&limits) {
+        Err(NfaError::LimitExceeded(Limit::NfaStates(2))) => {}
+        err @ _ => panic!("{:?}", err),
+    }
+}
+
+#[test]
+fn invalid_afa() {
+    let afa: Afa<Unsanitary> = serde_json::from_str(
+        r#"{
+            "start": "1",
+            "final_states": ["1"],
+            "alphabet": ["a"],
+            "nodes": {
+                "1": { "a": "1 & (2 | true)" }
+            }
+        }"#,
+    ).unwrap();
+    match afa.check() {
+        Err(NfaError::UnknownState(ref state)) if state == "2" => {}
+        err @ _ => panic!("{:?}", err),
+    }
+
+    assert!(
+        serde_json::from_str::<Afa<Unsanitary>>(
+            r#"{
+                "start": "1",
+                "final_states": [],
+                "alphabet": ["a"],
+                "nodes": { "1": { "a": "1 &" } }
+            }"#,
+        ).is_err()
+    );
+}
+
+#[test]
+fn invalid_afa_state_names() {
+    for &name in &["true", "a b", "a|b", "(a)", ""] {
+        let nodes = vec![(name.to_owned(), HashMap::new())].into_iter().collect();
+        let afa = Afa::new(name.to_owned(), HashSet::new(), HashSet::new(), nodes);
+        match afa.check() {
+            Err(NfaError::InvalidStateName(ref state)) if state == name => {}
+            err @ _ => panic!("{:?}", err),
+        }
+    }
+}
+
+#[test]
+fn limit_afa_formulas() {
+    // an AFA whose only transition is `(a0 | b0) & (a1 | b1) & …`, with 2^n smallest models
+    let afa = |n: usize| {
+        let formula = (0..n)
+            .map(|i| format!("(a{} | b{})", i, i))
+            .collect::<Vec<_>>()
+            .join(" & ");
+        let mut nodes: HashMap<String, HashMap<String, Formula>> = (0..n)
+            .flat_map(|i| vec![format!("a{}", i), format!("b{}", i)])
+            .map(|state| (state, HashMap::new()))
+            .collect();
+        let transitions = vec![("x".to_owned(), Formula::parse(&formula).unwrap())];
+        nodes.insert("start".into(), transitions.into_iter().collect());
+        Afa::new(
+            "start".into(),
+            HashSet::new(),
+            vec!["x".to_owned()].into_iter().collect(),
+            nodes,
+        ).check()
+            .unwrap()
+    };
+
+    let limits = Limits {
+        time: Some(Duration::from_millis(100)),
+        ..Limits::default()
+    };
+    let started = Instant::now();
+    match afa(20).to_nfa(&limits) {
+        Err(NfaError::LimitExceeded(Limit::Time(_))) => {}
+        err @ _ => panic!("{:?}", err),
+    }
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    let limits = Limits {
+        max_nfa_states: Some(4),
+        ..Limits::default()
+    };
+    match afa(3).to_nfa(&limits) {
+        Err(NfaError::LimitExceeded(Limit::NfaStates(4))) => {}
+        err @ _ => panic!("{:?}", err),
+    }
+}
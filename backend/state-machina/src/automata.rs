@@ -0,0 +1,1674 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::hash_map::Keys;
+use std::fmt;
+use std::hash::Hash;
+use std::iter;
+
+use serde::de::{Deserialize, Deserializer};
+
+use itertools::Itertools;
+
+use limits::{Budget, Limit, Limits};
+use symbols;
+
+#[cfg(test)]
+use serde_json;
+
+/// Marker for unsanitized input.
+#[derive(Debug, Clone)]
+pub struct Unsanitary;
+
+impl<'de> Deserialize<'de> for Unsanitary {
+    fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Unsanitary)
+    }
+}
+
+/// Marker for sanitized input.
+#[derive(Debug, Clone)]
+pub struct Sanitary;
+
+/// Used to represent errors
+#[derive(Debug)]
+pub enum NfaError {
+    /// A state was referred to that is not one of the automata's states.
+    UnknownState(String),
+    /// A symbol was used that is not in the automata's alphabet.
+    UnknownSymbol(String),
+    /// A symbol has a name that cannot be used.
+    InvalidSymbol(String),
+    /// A state has a name that cannot be used.
+    InvalidStateName(String),
+    /// A state has no transition on a symbol, in an automata that needs one for every symbol.
+    MissingTransition(String, String),
+    /// A state has no output, in an automata that needs one for every state.
+    MissingOutput(String),
+    /// Work on an automata was stopped because it went over a limit.
+    LimitExceeded(Limit),
+    /// Two symbols of an alphabet, or two transitions out of a DFA state, share a symbol.
+    OverlappingSymbols(String, String),
+}
+
+impl fmt::Display for NfaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NfaError::UnknownState(ref state) => write!(f, "unknown state {:?}", state),
+            NfaError::UnknownSymbol(ref symbol) => write!(f, "unknown symbol {:?}", symbol),
+            NfaError::InvalidSymbol(ref symbol) => write!(f, "invalid symbol {:?}", symbol),
+            NfaError::InvalidStateName(ref state) => write!(f, "invalid state name {:?}", state),
+            NfaError::MissingTransition(ref state, ref symbol) => write!(
+                f,
+                "state {:?} has no transition on symbol {:?}",
+                state,
+                symbol
+            ),
+            NfaError::MissingOutput(ref state) => write!(f, "state {:?} has no output", state),
+            NfaError::LimitExceeded(ref limit) => write!(f, "limit exceeded: {}", limit),
+            NfaError::OverlappingSymbols(ref left, ref right) => {
+                write!(f, "symbols {:?} and {:?} overlap", left, right)
+            }
+        }
+    }
+}
+
+/// Nondeterministic finite automata.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Nfa<T> {
+    /// Marker to ensure that the state machine has been validated before it is used for any
+    /// computations.
+    #[serde(skip_serializing)]
+    pub(crate) _sanitized: T,
+
+    /// Start state.
+    pub(crate) start: String,
+
+    /// The final (accepting) states of the automata.
+    pub(crate) final_states: HashSet<String>,
+
+    /// The alphabet of symbols the automata accepts.
+    pub(crate) alphabet: HashSet<String>,
+
+    /// The nodes within the automata. Each node has mappings from alphabet symbols to sets of
+    /// other states.
+    pub(crate) nodes: HashMap<String, HashMap<String, HashSet<String>>>,
+}
+
+impl<T> Nfa<T> {
+    /// The start state.
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// The final (accepting) states of the automata.
+    pub fn final_states(&self) -> &HashSet<String> {
+        &self.final_states
+    }
+
+    /// The alphabet of symbols the automata accepts.
+    pub fn alphabet(&self) -> &HashSet<String> {
+        &self.alphabet
+    }
+
+    /// The names of all of the states within the automata.
+    pub fn states<'a>(&'a self) -> Keys<'a, String, HashMap<String, HashSet<String>>> {
+        self.nodes.keys()
+    }
+
+    /// The transitions out of a state, as a mapping from symbols to sets of states. Returns
+    /// `None` if there is no such state.
+    pub fn transitions(&self, state: &str) -> Option<&HashMap<String, HashSet<String>>> {
+        self.nodes.get(state)
+    }
+
+    /// The states reachable from a state on a single symbol, if there are any.
+    pub fn transition(&self, state: &str, symbol: &str) -> Option<&HashSet<String>> {
+        self.transitions(state)
+            .and_then(|transitions| transitions.get(symbol))
+    }
+}
+
+impl Nfa<Unsanitary> {
+    /// Creates an NFA from its parts. Nothing is validated until the NFA is checked.
+    pub fn new(
+        start: String,
+        final_states: HashSet<String>,
+        alphabet: HashSet<String>,
+        nodes: HashMap<String, HashMap<String, HashSet<String>>>,
+    ) -> Self {
+        Nfa {
+            _sanitized: Unsanitary,
+            start,
+            final_states,
+            alphabet,
+            nodes,
+        }
+    }
+
+    /// Ensures that the NFA is valid, and that relevant invariants within the structure hold.
+    pub fn check(self) -> Result<Nfa<Sanitary>, NfaError> {
+        let Nfa {
+            start,
+            final_states,
+            alphabet,
+            nodes,
+            ..
+        } = self;
+
+        // ensure that the start state is a valid state
+        if !nodes.contains_key(&start) {
+            return Err(NfaError::UnknownState(start));
+        }
+
+        // ensure that all final states are listed as actual states
+        if let Some(unknown_state) = final_states
+            .iter()
+            .find(|&state| !nodes.contains_key(state))
+        {
+            return Err(NfaError::UnknownState(unknown_state.to_owned()));
+        }
+
+        // ensure that all state transitions are on valid symbols, which may be classes of
+        // symbols within the alphabet
+        let symbols = symbols::check_alphabet(&alphabet)?;
+        for transitions in nodes.values() {
+            for symbol in transitions.keys() {
+                if !alphabet.contains(symbol) {
+                    symbols::check_label(symbol, &symbols)?;
+                }
+            }
+        }
+
+        // ensure that all state transitions are to valid states
+        if let Some(unknown_state) = nodes
+            .iter()
+            .filter_map(|(_, maps)| {
+                maps.iter()
+                    .filter_map(|(_, states)| {
+                        states.iter().find(|&state| !nodes.contains_key(state))
+                    })
+                    .next()
+            })
+            .next()
+        {
+            return Err(NfaError::UnknownState(unknown_state.to_owned()));
+        }
+
+        if let Some(invalid_state) = nodes.iter().map(|(state, _)| state).find(|&state| {
+            /*state.contains("+") || state.contains("|") || */ state.is_empty()
+        }) {
+            return Err(NfaError::InvalidStateName(invalid_state.to_owned()));
+        }
+
+        Ok(Nfa {
+            _sanitized: Sanitary,
+            start,
+            final_states,
+            alphabet,
+            nodes,
+        })
+    }
+}
+
+#[test]
+fn valid_nfa() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["1", "2"],
+                "b": ["1"]
+            },
+            "2": {
+                "a": ["3"],
+                "b": ["3"]
+            },
+            "3": {
+                "a": ["1"],
+                "b": ["2"]
+            }
+        },
+        "final_states": ["3"]
+    }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    unsanitary.check().unwrap();
+}
+
+#[test]
+fn invalid_nfa_final_state() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["1", "2"],
+                "b": ["1"]
+            },
+            "2": {
+                "a": ["3"],
+                "b": ["3"]
+            },
+            "3": {
+                "a": ["1"],
+                "b": ["2"]
+            }
+        },
+        "final_states": ["3", "4"]
+    }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::UnknownState(err) => assert_eq!(err, "4"),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn invalid_nfa_state_transition() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["1", "2"],
+                "b": ["1"]
+            },
+            "2": {
+                "a": ["3"],
+                "b": ["3"]
+            },
+            "3": {
+                "a": ["1"],
+                "b": ["2", "4"]
+            }
+        },
+        "final_states": ["3"]
+    }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::UnknownState(err) => assert_eq!(err, "4"),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn invalid_nfa_alphabet() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["1", "2"],
+                "b": ["1"],
+                "c": ["1"]
+            },
+            "2": {
+                "a": ["3"],
+                "b": ["3"]
+            },
+            "3": {
+                "a": ["1"],
+                "b": ["2"]
+            }
+        },
+        "final_states": ["3"]
+    }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::UnknownSymbol(err) => assert_eq!(err, "c"),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn invalid_nfa_start_state() {
+    let input = r#"{
+        "start": "4",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["1", "2"],
+                "b": ["1"]
+            },
+            "2": {
+                "a": ["3"],
+                "b": ["3"]
+            },
+            "3": {
+                "a": ["1"],
+                "b": ["2"]
+            }
+        },
+        "final_states": ["3"]
+    }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::UnknownState(err) => assert_eq!(err, "4"),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn invalid_nfa_state_name() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["1", "2"],
+                "b": ["1"]
+            },
+            "2": {
+                "a": ["3"],
+                "b": ["3"]
+            },
+            "3": {
+                "a": ["1"],
+                "b": ["2"]
+            },
+            "1 + 2": { }
+        },
+        "final_states": ["3"]
+    }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::InvalidStateName(err) => assert_eq!(err, "1 + 2"),
+        err @ _ => panic!(err),
+    }
+}
+
+fn hash_states<'a, I>(states: I) -> String
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    states
+        .into_iter()
+        .map(|x| x.as_str())
+        .intersperse(" + ")
+        .collect()
+}
+
+fn dead_state_name<'a, I>(states: I) -> String
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let states: HashSet<_> = states.into_iter().map(|x| x.as_str()).collect();
+    if !states.contains("dead state") {
+        "dead state".into()
+    } else {
+        let mut i = 0;
+        loop {
+            let name = format!("dead state{}", i);
+            if !states.contains(name.as_str()) {
+                break name;
+            }
+            i += 1;
+        }
+    }
+}
+
+/// Whether a subset reached during the subset construction still needed to be processed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubsetStatus {
+    /// The subset had not been seen before, and was queued up to become a DFA state.
+    New,
+    /// The subset is empty and had not been seen before, so the dead state was introduced.
+    NewDeadState,
+    /// The subset was already a DFA state, or already queued.
+    Seen,
+}
+
+/// The subset reached from a subset on a single symbol, during the subset construction.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubsetTransition {
+    /// The symbol read.
+    pub symbol: String,
+
+    /// The (sorted) NFA states reached on the symbol.
+    pub subset: Vec<String>,
+
+    /// The name of the DFA state for the reached subset.
+    pub state: String,
+
+    /// Whether the reached subset was new.
+    pub status: SubsetStatus,
+}
+
+/// A single step of the subset construction, where one subset is taken off the work queue and
+/// turned into a DFA state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubsetStep {
+    /// The NFA states in the subset taken off the work queue.
+    pub subset: Vec<String>,
+
+    /// The name of the DFA state made from the subset.
+    pub state: String,
+
+    /// Whether the DFA state is final, as it contains a final NFA state.
+    pub is_final: bool,
+
+    /// The subset reached on every symbol of the alphabet, in sorted order of symbols.
+    pub transitions: Vec<SubsetTransition>,
+}
+
+impl Nfa<Sanitary> {
+    /// Runs the NFA over a word, and reports whether the word is accepted.
+    pub fn accepts<'a, I>(&self, word: I) -> Result<bool, NfaError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut current: HashSet<&String> = iter::once(&self.start).collect();
+        for letter in word {
+            if !symbols::in_alphabet(&self.alphabet, letter) {
+                return Err(NfaError::UnknownSymbol(letter.to_owned()));
+            }
+            current = current
+                .into_iter()
+                .flat_map(|state| symbols::follow(&self.nodes[state], letter))
+                .flatten()
+                .collect();
+        }
+
+        Ok(current.iter().any(|&state| self.final_states.contains(state)))
+    }
+
+    /// Converts the NFA into an equivalent DFA using the subset construction. Each DFA state is
+    /// named after the set of NFA states it represents, joined with ` + `.
+    ///
+    /// Any [symbol classes](symbols/index.html) are first split into disjoint classes, which
+    /// become the alphabet of the DFA.
+    pub fn make_deterministic(self) -> Dfa {
+        self.determinise(&Limits::default(), None)
+            .expect("no limits to exceed")
+    }
+
+    /// Converts the NFA into an equivalent DFA exactly like
+    /// [`make_deterministic`](#method.make_deterministic), also returning every step of the
+    /// subset construction in the order it happened.
+    pub fn make_deterministic_traced(self) -> (Dfa, Vec<SubsetStep>) {
+        let mut trace = Vec::new();
+        let dfa = self.determinise(&Limits::default(), Some(&mut trace))
+            .expect("no limits to exceed");
+        (dfa, trace)
+    }
+
+    /// Runs the subset construction, stopping as soon as the DFA would go over the limits.
+    pub(crate) fn determinise(
+        self,
+        limits: &Limits,
+        mut trace: Option<&mut Vec<SubsetStep>>,
+    ) -> Result<Dfa, NfaError> {
+        Limits::ensure(limits.max_nfa_states, self.nodes.len(), Limit::NfaStates)?;
+        let budget = Budget::start(limits);
+
+        let Nfa {
+            alphabet,
+            start: nfa_start,
+            nodes: nfa_nodes,
+            final_states: nfa_final_states,
+            ..
+        } = self;
+        let (alphabet, nfa_nodes) = symbols::split_classes(alphabet, nfa_nodes);
+        Limits::ensure(limits.max_alphabet_size, alphabet.len(), Limit::AlphabetSize)?;
+        let mut work = VecDeque::new();
+        let mut final_states = HashSet::new();
+        let mut nodes = HashMap::new();
+        let letters = alphabet.iter().sorted();
+
+        let start = hash_states(iter::once(&nfa_start));
+        work.push_back(vec![nfa_start]);
+
+        while let Some(node) = work.pop_front() {
+            budget.check()?;
+            let dfa_state = if node.is_empty() {
+                dead_state_name(nfa_nodes.iter().map(|(x, _)| x))
+            } else {
+                hash_states(&node)
+            };
+            assert!(!nodes.contains_key(&dfa_state));
+            let mut step_transitions = Vec::new();
+            let transition_table = letters
+                .iter()
+                .map(|&letter| {
+                    let transition = node.iter()
+                        .filter_map(|state| nfa_nodes.get(state))
+                        .filter_map(|transitions| transitions.get(letter.as_str()))
+                        .flatten()
+                        .map(|x| x.to_owned())
+                        .unique()
+                        .sorted();
+                    let transition_state = if transition.is_empty() {
+                        dead_state_name(nfa_nodes.iter().map(|(x, _)| x))
+                    } else {
+                        hash_states(&transition)
+                    };
+                    let is_new = transition != node && !nodes.contains_key(&transition_state) &&
+                        !work.contains(&transition);
+                    if trace.is_some() {
+                        step_transitions.push(SubsetTransition {
+                            symbol: letter.to_owned(),
+                            state: transition_state.to_owned(),
+                            status: match (is_new, transition.is_empty()) {
+                                (true, true) => SubsetStatus::NewDeadState,
+                                (true, false) => SubsetStatus::New,
+                                (false, _) => SubsetStatus::Seen,
+                            },
+                            subset: transition.clone(),
+                        });
+                    }
+                    if is_new {
+                        work.push_back(transition)
+                    }
+                    (letter.to_owned(), transition_state)
+                })
+                .collect();
+
+            // every state found so far, including this one, ends up in the DFA
+            Limits::ensure(
+                limits.max_dfa_states,
+                nodes.len() + 1 + work.len(),
+                Limit::DfaStates,
+            )?;
+
+            let is_final = node.iter().any(|state| nfa_final_states.contains(state));
+            if is_final {
+                final_states.insert(dfa_state.to_owned());
+            }
+
+            if let Some(ref mut trace) = trace {
+                trace.push(SubsetStep {
+                    subset: node,
+                    state: dfa_state.to_owned(),
+                    is_final,
+                    transitions: step_transitions,
+                });
+            }
+
+            nodes.insert(dfa_state, transition_table);
+        }
+
+        Ok(Dfa {
+            final_states,
+            start,
+            alphabet,
+            nodes,
+        })
+    }
+}
+
+#[test]
+fn basic_deterministic_conversion() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["1", "2"],
+                "b": ["1"]
+            },
+            "2": {
+                "a": ["3"],
+                "b": ["3"]
+            },
+            "3": {}
+        },
+        "final_states": ["3"]
+        }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    let nfa = unsanitary.check().unwrap();
+    let Dfa {
+        final_states,
+        start,
+        alphabet,
+        nodes,
+    } = nfa.make_deterministic();
+    assert_eq!(
+        final_states,
+        vec!["1 + 2 + 3".into(), "1 + 3".into()]
+            .into_iter()
+            .collect()
+    );
+    assert_eq!(start, "1".to_owned());
+    assert_eq!(alphabet, vec!["a".into(), "b".into()].into_iter().collect());
+    assert_eq!(
+        nodes,
+        vec![
+            (
+                "1".into(),
+                vec![("a".into(), "1 + 2".into()), ("b".into(), "1".into())]
+                    .into_iter()
+                    .collect(),
+            ),
+            (
+                "1 + 2".into(),
+                vec![
+                    ("a".into(), "1 + 2 + 3".into()),
+                    ("b".into(), "1 + 3".into()),
+                ].into_iter()
+                    .collect(),
+            ),
+            (
+                "1 + 2 + 3".into(),
+                vec![
+                    ("a".into(), "1 + 2 + 3".into()),
+                    ("b".into(), "1 + 3".into()),
+                ].into_iter()
+                    .collect(),
+            ),
+            (
+                "1 + 3".into(),
+                vec![("a".into(), "1 + 2".into()), ("b".into(), "1".into())]
+                    .into_iter()
+                    .collect(),
+            ),
+        ].into_iter()
+            .collect()
+    );
+}
+
+#[test]
+fn construct_nfa() {
+    let nfa = Nfa::new(
+        "1".into(),
+        vec!["2".into()].into_iter().collect(),
+        vec!["a".into()].into_iter().collect(),
+        vec![
+            (
+                "1".into(),
+                vec![("a".into(), vec!["1".into(), "2".into()].into_iter().collect())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ("2".into(), HashMap::new()),
+        ].into_iter()
+            .collect(),
+    ).check()
+        .unwrap();
+    assert_eq!(nfa.start(), "1");
+    assert_eq!(nfa.states().count(), 2);
+    assert_eq!(nfa.transition("1", "a").unwrap().len(), 2);
+    assert!(nfa.transition("2", "a").is_none());
+
+    let dfa = nfa.make_deterministic();
+    assert_eq!(dfa.transition("1", "a"), Some("1 + 2"));
+    assert!(dfa.final_states().contains("1 + 2"));
+}
+
+#[test]
+fn traced_deterministic_conversion() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["2"],
+                "b": ["1"]
+            },
+            "2": {}
+        },
+        "final_states": ["2"]
+        }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    let (dfa, trace) = unsanitary.check().unwrap().make_deterministic_traced();
+    assert_eq!(trace.len(), dfa.nodes.len());
+    assert_eq!(
+        trace[0],
+        SubsetStep {
+            subset: vec!["1".into()],
+            state: "1".into(),
+            is_final: false,
+            transitions: vec![
+                SubsetTransition {
+                    symbol: "a".into(),
+                    subset: vec!["2".into()],
+                    state: "2".into(),
+                    status: SubsetStatus::New,
+                },
+                SubsetTransition {
+                    symbol: "b".into(),
+                    subset: vec!["1".into()],
+                    state: "1".into(),
+                    status: SubsetStatus::Seen,
+                },
+            ],
+        }
+    );
+    assert_eq!(trace[1].state, "2");
+    assert!(trace[1].is_final);
+    assert_eq!(trace[1].transitions[0].status, SubsetStatus::NewDeadState);
+    assert_eq!(trace[1].transitions[1].status, SubsetStatus::Seen);
+    assert_eq!(trace[2].state, "dead state");
+    assert!(trace[2].subset.is_empty());
+}
+
+#[test]
+fn simulate_nfa_and_dfa() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["1", "2"],
+                "b": ["1"]
+            },
+            "2": {
+                "a": ["3"],
+                "b": ["3"]
+            },
+            "3": {}
+        },
+        "final_states": ["3"]
+        }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    let nfa = unsanitary.check().unwrap();
+    let words: Vec<(Vec<&str>, bool)> = vec![
+        (vec![], false),
+        (vec!["a", "b"], true),
+        (vec!["b", "a", "a"], true),
+        (vec!["a", "b", "b"], false),
+    ];
+    for &(ref word, expected) in &words {
+        assert_eq!(nfa.accepts(word.iter().cloned()).unwrap(), expected);
+    }
+    match nfa.accepts(vec!["c"]).unwrap_err() {
+        NfaError::UnknownSymbol(err) => assert_eq!(err, "c"),
+        err @ _ => panic!(err),
+    }
+
+    let dfa = nfa.make_deterministic().minimise();
+    for &(ref word, expected) in &words {
+        assert_eq!(dfa.accepts(word.iter().cloned()).unwrap(), expected);
+    }
+}
+
+/// A (sorted) pair of states.
+type Pair<'a> = (&'a String, &'a String);
+
+/// How a pair of states was found to be distinct: either immediately, or by a symbol that takes
+/// them to a pair that had already been found to be distinct.
+type Distinction<'a> = Option<(&'a String, Pair<'a>)>;
+
+/// Determines which states in a deterministic transition graph are equivalent, given a way of
+/// telling which pairs of states are distinct without following any transitions. Returns the set
+/// of (sorted) pairs of equivalent states, along with how every other pair was found to be
+/// distinct.
+///
+/// Distinct pairs are found breadth first, so following the distinctions of a pair back to a
+/// pair that was immediately distinct gives the shortest word that tells the pair apart.
+pub(crate) fn distinguish_states<'a, F>(
+    alphabet: &'a HashSet<String>,
+    nodes: &'a HashMap<String, HashMap<String, String>>,
+    distinct: F,
+    budget: &Budget,
+) -> Result<(HashSet<Pair<'a>>, HashMap<Pair<'a>, Distinction<'a>>), NfaError>
+where
+    F: Fn(&String, &String) -> bool,
+{
+    let mut output = HashSet::new();
+    let mut work = VecDeque::new();
+    let mut distinctions: HashMap<_, Distinction> = HashMap::new();
+    for left in nodes.keys() {
+        budget.check()?;
+        for right in nodes.keys().filter(|&right| left < right) {
+            if distinct(left, right) {
+                distinctions.insert((left, right), None);
+                work.push_back((left, right));
+            } else {
+                output.insert((left, right));
+            }
+        }
+    }
+
+    // construct reversed graph
+    let mut backtrack: HashMap<_, HashMap<&String, _>> = nodes
+        .iter()
+        .map(|(node, _)| (node, HashMap::new()))
+        .collect();
+    for (state, transforms) in nodes {
+        budget.check()?;
+        for (letter, new_state) in transforms {
+            match backtrack.get_mut(&new_state) {
+                Some(sources) => sources.entry(&letter).or_insert(HashSet::new()).insert(state),
+                None => return Err(NfaError::UnknownState(new_state.to_owned())),
+            };
+        }
+    }
+
+    while let Some(node) = work.pop_front() {
+        budget.check()?;
+        for letter in alphabet {
+            let (left, right) = node;
+            if let (Some(left), Some(right)) =
+                (backtrack[left].get(letter), backtrack[right].get(letter))
+            {
+                for left in left {
+                    budget.check()?;
+                    for right in right {
+                        let pair = if left < right {
+                            (*left, *right)
+                        } else {
+                            (*right, *left)
+                        };
+                        if output.remove(&pair) {
+                            distinctions.insert(pair, Some((letter, node)));
+                            work.push_back(pair);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok((output, distinctions))
+}
+
+/// Groups the states of a deterministic transition graph into classes of equivalent states by
+/// partition refinement, starting from the classes of states with the same key. Each round splits
+/// the classes whose states go to different classes on some symbol, until a round splits none.
+///
+/// Unlike [`distinguish_states`](fn.distinguish_states.html), this never looks at pairs of
+/// states, so it needs space linear in the size of the graph. A missing transition is told apart
+/// from every transition. Returns the class name of every state, named like
+/// [`merge_classes`](fn.merge_classes.html).
+pub(crate) fn refine_classes<K, F>(
+    nodes: &HashMap<String, HashMap<String, String>>,
+    key: F,
+    budget: &Budget,
+) -> Result<HashMap<String, String>, NfaError>
+where
+    K: Hash + Eq,
+    F: Fn(&String) -> K,
+{
+    let mut classes: HashMap<&String, usize> = HashMap::new();
+    let mut count = {
+        let mut keys = HashMap::new();
+        for state in nodes.keys() {
+            budget.check()?;
+            let next = keys.len();
+            classes.insert(state, *keys.entry(key(state)).or_insert(next));
+        }
+        keys.len()
+    };
+
+    loop {
+        let mut signatures = HashMap::new();
+        let mut refined = HashMap::new();
+        for (state, transitions) in nodes {
+            budget.check()?;
+            let mut targets = Vec::with_capacity(transitions.len());
+            for (letter, new_state) in transitions {
+                match classes.get(new_state) {
+                    Some(&class) => targets.push((letter, class)),
+                    None => return Err(NfaError::UnknownState(new_state.to_owned())),
+                }
+            }
+            targets.sort();
+            let next = signatures.len();
+            refined.insert(
+                state,
+                *signatures
+                    .entry((classes[state], targets))
+                    .or_insert(next),
+            );
+        }
+        classes = refined;
+        if signatures.len() == count {
+            break;
+        }
+        count = signatures.len();
+    }
+
+    let mut members = vec![Vec::new(); count];
+    for (state, &class) in &classes {
+        members[class].push(state.as_str());
+    }
+    let names: Vec<_> = members
+        .into_iter()
+        .map(|mut class| {
+            class.sort();
+            class.join(" | ")
+        })
+        .collect();
+    Ok(classes
+        .into_iter()
+        .map(|(state, class)| (state.to_owned(), names[class].to_owned()))
+        .collect())
+}
+
+/// Follows the distinctions of a pair of states back to a pair that was immediately distinct,
+/// giving a shortest word that tells the pair apart.
+fn distinguishing_word(distinctions: &HashMap<Pair, Distinction>, pair: Pair) -> Vec<String> {
+    let mut word = Vec::new();
+    let mut pair = pair;
+    while let Some((letter, next)) = distinctions[&pair] {
+        word.push(letter.to_owned());
+        pair = next;
+    }
+    word
+}
+
+/// Determines which states in a deterministic transition graph are equivalent, given a way of
+/// telling which pairs of states are distinct without following any transitions. Returns the set
+/// of (sorted) pairs of equivalent states.
+pub(crate) fn equivalent_states<'a, F>(
+    alphabet: &'a HashSet<String>,
+    nodes: &'a HashMap<String, HashMap<String, String>>,
+    distinct: F,
+) -> HashSet<Pair<'a>>
+where
+    F: Fn(&String, &String) -> bool,
+{
+    distinguish_states(alphabet, nodes, distinct, &Budget::unlimited())
+        .expect("no limits to exceed")
+        .0
+}
+
+/// Groups states into classes from the (sorted) pairs of equivalent states, and names each
+/// class after the states within it, joined with ` | `. Returns the class name of every state.
+pub(crate) fn merge_classes<'a, I>(
+    states: I,
+    equivalent: HashSet<(&String, &String)>,
+) -> HashMap<String, String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let mut classes: HashMap<&str, Vec<&str>> = states
+        .into_iter()
+        .map(|state| (state.as_str(), vec![state.as_str()]))
+        .collect();
+    for (left, right) in equivalent {
+        classes.get_mut(left.as_str()).unwrap().push(right);
+        classes.get_mut(right.as_str()).unwrap().push(left);
+    }
+
+    classes
+        .into_iter()
+        .map(|(state, mut class)| {
+            class.sort();
+            (state.to_owned(), class.join(" | "))
+        })
+        .collect()
+}
+
+/// Why two states of a DFA are not equivalent.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DistinguishedPair {
+    /// The first state, in sorted order.
+    pub left: String,
+
+    /// The second state, in sorted order.
+    pub right: String,
+
+    /// A shortest word that is accepted from exactly one of the two states.
+    pub word: Vec<String>,
+}
+
+/// How a DFA was minimised, in the style of the table-filling algorithm.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MinimisationExplanation {
+    /// Every state of the minimised DFA, with the (sorted) states of the original DFA that were
+    /// merged into it.
+    pub classes: HashMap<String, Vec<String>>,
+
+    /// Every pair of states of the original DFA that could not be merged, in sorted order.
+    pub distinguished: Vec<DistinguishedPair>,
+}
+
+/// A state of a minimised DFA seen as a Myhill–Nerode equivalence class: the words leading to
+/// the state, which every suffix treats alike.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NerodeClass {
+    /// The state.
+    pub state: String,
+
+    /// A shortest word leading to the state, the first alphabetically of those, which represents
+    /// the class.
+    pub access_word: Vec<String>,
+}
+
+/// The Myhill–Nerode equivalence classes of the language of a minimised DFA, and what tells
+/// them apart.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NerodeReport {
+    /// Every state reachable from the start, in order of their access words, shortest first.
+    pub classes: Vec<NerodeClass>,
+
+    /// Every pair of classes, in sorted order, with a shortest suffix that is accepted after
+    /// exactly one of their access words.
+    pub distinguished: Vec<DistinguishedPair>,
+}
+
+/// Deterministic finite automata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dfa {
+    /// The final (accepting) states of the automata.
+    pub(crate) final_states: HashSet<String>,
+
+    /// Start state.
+    pub(crate) start: String,
+
+    /// The alphabet of symbols the automata accepts.
+    pub(crate) alphabet: HashSet<String>,
+
+    /// The nodes within the automata. Each node has mappings from alphabet symbols to transition
+    /// states.
+    pub(crate) nodes: HashMap<String, HashMap<String, String>>,
+}
+
+impl Dfa {
+    /// Creates a DFA from its parts.
+    pub fn new(
+        start: String,
+        final_states: HashSet<String>,
+        alphabet: HashSet<String>,
+        nodes: HashMap<String, HashMap<String, String>>,
+    ) -> Self {
+        Dfa {
+            final_states,
+            start,
+            alphabet,
+            nodes,
+        }
+    }
+
+    /// The start state.
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// The final (accepting) states of the automata.
+    pub fn final_states(&self) -> &HashSet<String> {
+        &self.final_states
+    }
+
+    /// The alphabet of symbols the automata accepts.
+    pub fn alphabet(&self) -> &HashSet<String> {
+        &self.alphabet
+    }
+
+    /// The names of all of the states within the automata.
+    pub fn states<'a>(&'a self) -> Keys<'a, String, HashMap<String, String>> {
+        self.nodes.keys()
+    }
+
+    /// The transitions out of a state, as a mapping from symbols to states. Returns `None` if
+    /// there is no such state.
+    pub fn transitions(&self, state: &str) -> Option<&HashMap<String, String>> {
+        self.nodes.get(state)
+    }
+
+    /// The state reached from a state on a single symbol, if there is one.
+    pub fn transition(&self, state: &str, symbol: &str) -> Option<&str> {
+        self.transitions(state)
+            .and_then(|transitions| transitions.get(symbol))
+            .map(|state| state.as_str())
+    }
+
+    /// Runs the DFA over a word, and reports whether the word is accepted. Missing transitions
+    /// reject the word.
+    pub fn accepts<'a, I>(&self, word: I) -> Result<bool, NfaError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut current = &self.start;
+        for letter in word {
+            if !symbols::in_alphabet(&self.alphabet, letter) {
+                return Err(NfaError::UnknownSymbol(letter.to_owned()));
+            }
+            let next = self.nodes
+                .get(current)
+                .and_then(|transitions| symbols::follow(transitions, letter).pop());
+            match next {
+                Some(state) => current = state,
+                None => return Ok(false),
+            }
+        }
+
+        Ok(self.final_states.contains(current))
+    }
+
+    /// Converts the DFA into an NFA with the same states, where every transition goes to a
+    /// single state. The NFA is unchecked, since nothing about the DFA has been validated.
+    pub fn into_nfa(self) -> Nfa<Unsanitary> {
+        let nodes = self.nodes
+            .into_iter()
+            .map(|(state, transitions)| {
+                (
+                    state,
+                    transitions
+                        .into_iter()
+                        .map(|(letter, new_state)| (letter, iter::once(new_state).collect()))
+                        .collect(),
+                )
+            })
+            .collect();
+        Nfa::new(self.start, self.final_states, self.alphabet, nodes)
+    }
+
+    /// Determines which states in the DFA are equivalent. Returns the set of (sorted) pairs of
+    /// equivalent states.
+    #[cfg(test)]
+    fn lint_states<'a>(&'a self) -> HashSet<(&'a String, &'a String)> {
+        equivalent_states(&self.alphabet, &self.nodes, |left, right| {
+            self.final_states.contains(left) != self.final_states.contains(right)
+        })
+    }
+
+    /// Minimises the DFA, merging equivalent states. Merged states are named after the states
+    /// they replace, joined with ` | `.
+    ///
+    /// Panics if the DFA refers to a state it does not have, which
+    /// [`Limits::minimise`](struct.Limits.html#method.minimise) reports as an error instead.
+    pub fn minimise(self) -> Self {
+        self.reduce(&Limits::default(), false)
+            .expect("the DFA to only refer to its own states")
+            .0
+    }
+
+    /// Minimises the DFA exactly like [`minimise`](#method.minimise), also explaining which
+    /// states were merged, and why the others could not be.
+    pub fn minimise_explained(self) -> (Self, MinimisationExplanation) {
+        let (dfa, explanation) = self.reduce(&Limits::default(), true)
+            .expect("the DFA to only refer to its own states");
+        (dfa, explanation.unwrap())
+    }
+
+    /// Reports the Myhill–Nerode equivalence classes of the DFA's language, with a shortest
+    /// word leading to every state and a shortest suffix telling every pair of states apart.
+    ///
+    /// The DFA should be [minimised](#method.minimise) first, as otherwise some classes will be
+    /// split over several states. Pairs of such states are left out of the distinguished pairs,
+    /// as nothing tells them apart. States that cannot be reached from the start are left out
+    /// altogether, as no word leads to them.
+    pub fn nerode_classes(&self) -> Result<NerodeReport, NfaError> {
+        self.nerode(&Budget::unlimited())
+    }
+
+    /// Reports the Myhill–Nerode equivalence classes of the DFA, stopping once the time is up.
+    pub(crate) fn nerode(&self, budget: &Budget) -> Result<NerodeReport, NfaError> {
+        // visiting the symbols in order finds the first of the shortest words to every state
+        let letters = self.alphabet.iter().sorted();
+        let mut access_words: HashMap<&String, Vec<String>> = HashMap::new();
+        access_words.insert(&self.start, Vec::new());
+        let mut order = vec![&self.start];
+        let mut work = VecDeque::new();
+        work.push_back(&self.start);
+        while let Some(state) = work.pop_front() {
+            budget.check()?;
+            let transitions = match self.nodes.get(state) {
+                Some(transitions) => transitions,
+                None => return Err(NfaError::UnknownState(state.to_owned())),
+            };
+            for letter in &letters {
+                let next = match transitions.get(letter.as_str()) {
+                    Some(next) => next,
+                    None => continue,
+                };
+                if !access_words.contains_key(next) {
+                    let mut word = access_words[state].clone();
+                    word.push(letter.to_string());
+                    access_words.insert(next, word);
+                    order.push(next);
+                    work.push_back(next);
+                }
+            }
+        }
+
+        let final_states = &self.final_states;
+        let (_, distinctions) = distinguish_states(
+            &self.alphabet,
+            &self.nodes,
+            |left, right| final_states.contains(left) != final_states.contains(right),
+            budget,
+        )?;
+        let mut distinguished = Vec::new();
+        for &(left, right) in distinctions.keys().sorted() {
+            budget.check()?;
+            if access_words.contains_key(left) && access_words.contains_key(right) {
+                distinguished.push(DistinguishedPair {
+                    left: left.to_owned(),
+                    right: right.to_owned(),
+                    word: distinguishing_word(&distinctions, (left, right)),
+                });
+            }
+        }
+
+        Ok(NerodeReport {
+            classes: order
+                .into_iter()
+                .map(|state| NerodeClass {
+                    state: state.to_owned(),
+                    access_word: access_words[state].clone(),
+                })
+                .collect(),
+            distinguished,
+        })
+    }
+
+    /// Minimises the DFA, stopping as soon as it goes over the limits. Only explains the
+    /// minimisation if asked to, since that means comparing every pair of states, where
+    /// minimising on its own only has to refine a partition of them.
+    pub(crate) fn reduce(
+        self,
+        limits: &Limits,
+        explain: bool,
+    ) -> Result<(Self, Option<MinimisationExplanation>), NfaError> {
+        Limits::ensure(limits.max_dfa_states, self.nodes.len(), Limit::DfaStates)?;
+        Limits::ensure(limits.max_alphabet_size, self.alphabet.len(), Limit::AlphabetSize)?;
+        self.ensure_states()?;
+        let budget = Budget::start(limits);
+
+        if !explain {
+            let final_states = &self.final_states;
+            let classes =
+                refine_classes(&self.nodes, |state| final_states.contains(state), &budget)?;
+            return Ok((self.merge_states(&classes), None));
+        }
+
+        Limits::ensure(
+            limits.max_explained_states,
+            self.nodes.len(),
+            Limit::ExplainedStates,
+        )?;
+        let (classes, distinguished) = {
+            let final_states = &self.final_states;
+            let (equivalent, distinctions) = distinguish_states(
+                &self.alphabet,
+                &self.nodes,
+                |left, right| final_states.contains(left) != final_states.contains(right),
+                &budget,
+            )?;
+
+            let mut distinguished = Vec::with_capacity(distinctions.len());
+            for &(left, right) in distinctions.keys().sorted() {
+                budget.check()?;
+                distinguished.push(DistinguishedPair {
+                    left: left.to_owned(),
+                    right: right.to_owned(),
+                    word: distinguishing_word(&distinctions, (left, right)),
+                });
+            }
+
+            (merge_classes(self.nodes.keys(), equivalent), distinguished)
+        };
+
+        let mut members: HashMap<String, Vec<String>> = HashMap::new();
+        for (state, class) in &classes {
+            members
+                .entry(class.to_owned())
+                .or_insert_with(Vec::new)
+                .push(state.to_owned());
+        }
+        for states in members.values_mut() {
+            states.sort();
+        }
+
+        let explanation = MinimisationExplanation {
+            classes: members,
+            distinguished,
+        };
+        Ok((self.merge_states(&classes), Some(explanation)))
+    }
+
+    /// Ensures that the start state, the final states and the states transitions go to are all
+    /// states of the DFA, as a DFA that was made directly rather than from an NFA might not be.
+    fn ensure_states(&self) -> Result<(), NfaError> {
+        let unknown_state = iter::once(&self.start)
+            .chain(&self.final_states)
+            .chain(self.nodes.values().flat_map(|transitions| transitions.values()))
+            .find(|&state| !self.nodes.contains_key(state));
+        match unknown_state {
+            Some(state) => Err(NfaError::UnknownState(state.to_owned())),
+            None => Ok(()),
+        }
+    }
+
+    /// Renames every state to the name of its class, merging states with the same class.
+    pub(crate) fn merge_states(self, classes: &HashMap<String, String>) -> Self {
+        Dfa {
+            final_states: self.final_states
+                .into_iter()
+                .map(|state| classes[&state].to_owned())
+                .collect(),
+            start: classes[&self.start].to_owned(),
+            alphabet: self.alphabet,
+            nodes: self.nodes
+                .into_iter()
+                .map(|(state, transitions)| {
+                    (
+                        classes[&state].to_owned(),
+                        transitions
+                            .into_iter()
+                            .map(|(letter, new_state)| (letter, classes[&new_state].to_owned()))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[test]
+pub fn lint_dfa_minimised() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["1", "2"],
+                "b": ["1"]
+            },
+            "2": {
+                "a": ["3"],
+                "b": ["3"]
+            },
+            "3": {}
+        },
+        "final_states": ["3"]
+        }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    let nfa = unsanitary.check().unwrap();
+    let dfa = nfa.make_deterministic();
+    assert!(dfa.lint_states().is_empty())
+}
+
+#[test]
+pub fn lint_dfa_unminimised() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["2"],
+                "b": ["3"]
+            },
+            "2": {},
+            "3": {
+                "a": ["4"],
+                "b": ["1"]
+            },
+            "4": {}
+        },
+        "final_states": ["2", "4"]
+    }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    let nfa = unsanitary.check().unwrap();
+    let dfa = nfa.make_deterministic();
+    assert_eq!(
+        dfa.lint_states()
+            .into_iter()
+            .map(|(x, y)| (x.to_owned(), y.to_owned()))
+            .collect::<HashSet<_>>(),
+        vec![("1".into(), "3".into()), ("2".into(), "4".into())]
+            .into_iter()
+            .collect()
+    )
+}
+
+#[test]
+pub fn optimise_dfa() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["2"],
+                "b": ["3"]
+            },
+            "2": {},
+            "3": {
+                "a": ["4"],
+                "b": ["1"]
+            },
+            "4": {}
+        },
+        "final_states": ["2", "4"]
+    }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    let nfa = unsanitary.check().unwrap();
+    let Dfa {
+        final_states,
+        start,
+        alphabet,
+        nodes,
+    } = nfa.make_deterministic().minimise();
+
+    assert_eq!(final_states, vec!["2 | 4".into()].into_iter().collect());
+    assert_eq!(start, "1 | 3".to_owned());
+    assert_eq!(alphabet, vec!["a".into(), "b".into()].into_iter().collect());
+    assert_eq!(
+        nodes,
+        vec![
+            (
+                "1 | 3".into(),
+                vec![("a".into(), "2 | 4".into()), ("b".into(), "1 | 3".into())]
+                    .into_iter()
+                    .collect(),
+            ),
+            (
+                "2 | 4".into(),
+                vec![("a".into(), "dead state".into()), ("b".into(), "dead state".into())]
+                    .into_iter()
+                    .collect(),
+            ),
+            (
+                "dead state".into(),
+                vec![("a".into(), "dead state".into()), ("b".into(), "dead state".into())]
+                    .into_iter()
+                    .collect(),
+            ),
+        ].into_iter()
+            .collect()
+    );
+}
+
+#[test]
+pub fn optimise_dfa_large_class() {
+    // 1, 2 and 3 are all equivalent, so have to be merged into a single state
+    let input = r#"{
+        "start": "0",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "0": {
+                "a": ["1"],
+                "b": ["2"]
+            },
+            "1": {
+                "a": ["3"],
+                "b": ["4"]
+            },
+            "2": {
+                "a": ["1"],
+                "b": ["4"]
+            },
+            "3": {
+                "a": ["2"],
+                "b": ["4"]
+            },
+            "4": {
+                "a": ["4"],
+                "b": ["4"]
+            }
+        },
+        "final_states": ["4"]
+    }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    let dfa = unsanitary.check().unwrap().make_deterministic().minimise();
+
+    assert_eq!(dfa.nodes.len(), 3);
+    assert_eq!(
+        dfa.nodes["1 | 2 | 3"],
+        vec![("a".into(), "1 | 2 | 3".into()), ("b".into(), "4".into())]
+            .into_iter()
+            .collect()
+    );
+    assert_eq!(dfa.nodes["0"]["b"], "1 | 2 | 3");
+}
+
+#[test]
+pub fn explain_minimised_dfa() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["2"],
+                "b": ["3"]
+            },
+            "2": {},
+            "3": {
+                "a": ["4"],
+                "b": ["1"]
+            },
+            "4": {}
+        },
+        "final_states": ["2", "4"]
+    }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    let (dfa, explanation) = unsanitary
+        .check()
+        .unwrap()
+        .make_deterministic()
+        .minimise_explained();
+
+    assert_eq!(dfa.nodes.len(), 3);
+    assert_eq!(
+        explanation.classes["1 | 3"],
+        vec!["1".to_owned(), "3".to_owned()]
+    );
+    assert_eq!(explanation.classes["dead state"], vec!["dead state".to_owned()]);
+
+    // every pair of states not in the same class is distinguished
+    assert_eq!(explanation.distinguished.len(), 10 - 2);
+    let word = |left: &str, right: &str| {
+        explanation
+            .distinguished
+            .iter()
+            .find(|pair| pair.left == left && pair.right == right)
+            .unwrap()
+            .word
+            .to_owned()
+    };
+    assert_eq!(word("1", "2"), Vec::<String>::new());
+    assert_eq!(word("1", "dead state"), vec!["a".to_owned()]);
+    assert_eq!(word("2", "dead state"), Vec::<String>::new());
+
+    // and every word really does tell its pair apart
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    let original = unsanitary.check().unwrap().make_deterministic();
+    let accepts = |state: &str, word: &[String]| {
+        let end = word.iter()
+            .fold(state, |state, letter| original.nodes[state][letter].as_str());
+        original.final_states.contains(end)
+    };
+    for pair in &explanation.distinguished {
+        assert!(accepts(&pair.left, &pair.word) != accepts(&pair.right, &pair.word));
+    }
+}
+
+#[test]
+pub fn nerode_classes_of_minimised_dfa() {
+    // words ending in "ab"
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": ["1", "2"],
+                "b": ["1"]
+            },
+            "2": {
+                "b": ["3"]
+            },
+            "3": {}
+        },
+        "final_states": ["3"]
+    }"#;
+    let unsanitary: Nfa<_> = serde_json::from_str(input).unwrap();
+    let dfa = unsanitary.check().unwrap().make_deterministic().minimise();
+    let report = dfa.nerode_classes().unwrap();
+
+    let access_words: Vec<String> = report
+        .classes
+        .iter()
+        .map(|class| class.access_word.join(""))
+        .collect();
+    assert_eq!(access_words, vec!["", "a", "ab"]);
+    assert_eq!(report.classes[0].state, dfa.start);
+
+    // every pair of classes is distinguished, by a suffix that really does tell them apart
+    assert_eq!(report.distinguished.len(), 3);
+    let accepts = |state: &str, word: &[String]| {
+        let end = word.iter()
+            .fold(state, |state, letter| dfa.nodes[state][letter].as_str());
+        dfa.final_states.contains(end)
+    };
+    for pair in &report.distinguished {
+        assert!(accepts(&pair.left, &pair.word) != accepts(&pair.right, &pair.word));
+    }
+    let word = |left: &str, right: &str| {
+        let (left, right) = if left < right { (left, right) } else { (right, left) };
+        report
+            .distinguished
+            .iter()
+            .find(|pair| pair.left == left && pair.right == right)
+            .unwrap()
+            .word
+            .join("")
+    };
+    let states: Vec<&str> = report.classes.iter().map(|class| class.state.as_str()).collect();
+    assert_eq!(word(states[0], states[1]), "b");
+    assert_eq!(word(states[1], states[2]), "");
+
+    // unreachable states are not classes
+    let mut dfa = dfa;
+    dfa.nodes.insert("9".into(), dfa.nodes[&dfa.start].clone());
+    let report = dfa.nerode_classes().unwrap();
+    assert_eq!(report.classes.len(), 3);
+    assert_eq!(report.distinguished.len(), 3);
+
+    // a DFA made directly can go to states it does not have
+    dfa.nodes.get_mut("9").unwrap().insert("a".into(), "10".into());
+    dfa.start = "9".into();
+    match dfa.nerode_classes().unwrap_err() {
+        NfaError::UnknownState(state) => assert_eq!(state, "10"),
+        err @ _ => panic!(err),
+    }
+    match Limits::default().minimise_explained(dfa.clone()).unwrap_err() {
+        NfaError::UnknownState(state) => assert_eq!(state, "10"),
+        err @ _ => panic!(err),
+    }
+    dfa.start = "11".into();
+    match Limits::default().minimise(dfa).unwrap_err() {
+        NfaError::UnknownState(state) => assert_eq!(state, "11"),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn dfa_into_nfa() {
+    let dfa = Dfa::new(
+        "1".into(),
+        vec!["2".into()].into_iter().collect(),
+        vec!["a".into(), "b".into()].into_iter().collect(),
+        vec![
+            ("1".into(), vec![("a".into(), "2".into())].into_iter().collect()),
+            ("2".into(), vec![("b".into(), "2".into())].into_iter().collect()),
+        ].into_iter()
+            .collect(),
+    );
+    let nfa = dfa.into_nfa().check().unwrap();
+    assert_eq!(
+        nfa.transition("1", "a").unwrap(),
+        &vec!["2".into()].into_iter().collect()
+    );
+    assert!(nfa.accepts(vec!["a", "b"]).unwrap());
+
+    // converting back adds a dead state for the missing transitions
+    let dfa = nfa.make_deterministic();
+    assert_eq!(dfa.states().count(), 3);
+    assert_eq!(dfa.transition("2", "b"), Some("2"));
+}
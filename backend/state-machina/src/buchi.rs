@@ -0,0 +1,534 @@
+//! Büchi automata, which accept infinite words.
+
+use std::collections::hash_map::Keys;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use automata::{Nfa, NfaError, Sanitary, Unsanitary};
+use symbols;
+
+/// Büchi automata, which accept the infinite words that have a run visiting an accepting state
+/// infinitely often. They have the same layout as an [`Nfa`](struct.Nfa.html), with its final
+/// states as the accepting states, and are checked the same way.
+///
+/// ```
+/// use state_machina::{Buchi, NfaBuilder};
+///
+/// // infinitely many "a"s
+/// let buchi = Buchi::from(
+///     NfaBuilder::new()
+///         .start("1")
+///         .transition("1", "a", "2")
+///         .transition("1", "b", "1")
+///         .transition("2", "a", "2")
+///         .transition("2", "b", "1")
+///         .final_state("2")
+///         .check()
+///         .unwrap(),
+/// );
+/// assert!(buchi.accepts(vec!["b"], vec!["b", "a"]).unwrap());
+/// assert!(!buchi.accepts(vec!["a"], vec!["b"]).unwrap());
+///
+/// let lasso = buchi.lasso().unwrap();
+/// assert_eq!((lasso.prefix, lasso.cycle), (vec!["a".to_string()], vec!["a".to_string()]));
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Buchi<T>(Nfa<T>);
+
+/// An ultimately periodic word, made of a prefix followed by a cycle repeated forever, along with
+/// the accepting state that a run over it visits at the start of every cycle.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Lasso {
+    /// The word leading from the start state to the accepting state.
+    pub prefix: Vec<String>,
+
+    /// The word leading from the accepting state back to itself. It is never empty.
+    pub cycle: Vec<String>,
+
+    /// The accepting state.
+    pub state: String,
+}
+
+impl<T> From<Nfa<T>> for Buchi<T> {
+    /// Reads an NFA as a Büchi automata, with its final states as the accepting states.
+    fn from(nfa: Nfa<T>) -> Self {
+        Buchi(nfa)
+    }
+}
+
+impl<T> Buchi<T> {
+    /// The start state.
+    pub fn start(&self) -> &str {
+        self.0.start()
+    }
+
+    /// The states that an accepted run visits infinitely often.
+    pub fn accepting_states(&self) -> &HashSet<String> {
+        self.0.final_states()
+    }
+
+    /// The alphabet of symbols the automata reads.
+    pub fn alphabet(&self) -> &HashSet<String> {
+        self.0.alphabet()
+    }
+
+    /// The names of all of the states within the automata.
+    pub fn states<'a>(&'a self) -> Keys<'a, String, HashMap<String, HashSet<String>>> {
+        self.0.states()
+    }
+
+    /// The transitions out of a state, as a mapping from symbols to sets of states. Returns
+    /// `None` if there is no such state.
+    pub fn transitions(&self, state: &str) -> Option<&HashMap<String, HashSet<String>>> {
+        self.0.transitions(state)
+    }
+}
+
+impl Buchi<Unsanitary> {
+    /// Creates a Büchi automata from its parts. Nothing is validated until it is checked.
+    pub fn new(
+        start: String,
+        accepting_states: HashSet<String>,
+        alphabet: HashSet<String>,
+        nodes: HashMap<String, HashMap<String, HashSet<String>>>,
+    ) -> Self {
+        Buchi(Nfa::new(start, accepting_states, alphabet, nodes))
+    }
+
+    /// Ensures that the automata is valid, exactly like [`Nfa::check`](struct.Nfa.html#method.check).
+    pub fn check(self) -> Result<Buchi<Sanitary>, NfaError> {
+        self.0.check().map(Buchi)
+    }
+}
+
+impl Buchi<Sanitary> {
+    /// Runs the automata over the infinite word made of a prefix followed by a cycle repeated
+    /// forever, and reports whether the word is accepted. An empty cycle does not make an
+    /// infinite word, so is never accepted.
+    pub fn accepts<'a, I, J>(&self, prefix: I, cycle: J) -> Result<bool, NfaError>
+    where
+        I: IntoIterator<Item = &'a str>,
+        J: IntoIterator<Item = &'a str>,
+    {
+        let nfa = &self.0;
+        let known = |symbol: &str| if symbols::in_alphabet(&nfa.alphabet, symbol) {
+            Ok(())
+        } else {
+            Err(NfaError::UnknownSymbol(symbol.to_owned()))
+        };
+
+        let mut current: HashSet<&String> = Some(&nfa.start).into_iter().collect();
+        for symbol in prefix {
+            known(symbol)?;
+            current = current
+                .into_iter()
+                .flat_map(|state| symbols::follow(&nfa.nodes[state], symbol))
+                .flat_map(|targets| targets)
+                .collect();
+        }
+        let cycle: Vec<&str> = cycle.into_iter().collect();
+        for &symbol in &cycle {
+            known(symbol)?;
+        }
+        if cycle.is_empty() {
+            return Ok(false);
+        }
+
+        // runs over the cycle, as pairs of a state and how far through the cycle it is
+        let names: Vec<&String> = nfa.nodes.keys().collect();
+        let index: HashMap<&String, usize> =
+            names.iter().enumerate().map(|(i, &name)| (name, i)).collect();
+        let node = |state: &String, position: usize| index[state] * cycle.len() + position;
+        let mut edges = vec![Vec::new(); names.len() * cycle.len()];
+        let mut accepting = vec![false; names.len() * cycle.len()];
+        for &state in &names {
+            for (position, &symbol) in cycle.iter().enumerate() {
+                let next = (position + 1) % cycle.len();
+                for targets in symbols::follow(&nfa.nodes[state], symbol) {
+                    for target in targets {
+                        edges[node(state, position)].push(((), node(target, next)));
+                    }
+                }
+                accepting[node(state, position)] = nfa.final_states.contains(state);
+            }
+        }
+        let starts: Vec<usize> = current.into_iter().map(|state| node(state, 0)).collect();
+        Ok(find_lasso(&edges, &accepting, &starts).is_some())
+    }
+
+    /// Whether the automata accepts no words at all.
+    pub fn is_empty(&self) -> bool {
+        self.lasso().is_none()
+    }
+
+    /// Finds a word that the automata accepts, if there is one. The accepting state is the one
+    /// closest to the start that is on a cycle, and the prefix and cycle are as short as they can
+    /// be for it.
+    ///
+    /// The states are split into strongly connected components, and a word is accepted exactly
+    /// when an accepting state can be reached that is on a cycle within its component.
+    pub fn lasso(&self) -> Option<Lasso> {
+        let nfa = &self.0;
+        let mut names: Vec<&String> = nfa.nodes.keys().collect();
+        names.sort();
+        let index: HashMap<&String, usize> =
+            names.iter().enumerate().map(|(i, &name)| (name, i)).collect();
+        // a symbol from every disjoint class, so that the lasso is made of actual symbols
+        let symbols: Vec<String> = symbols::minterms(
+            nfa.alphabet
+                .iter()
+                .chain(nfa.nodes.values().flat_map(|transitions| transitions.keys())),
+        ).into_iter()
+            .filter_map(|(_, set)| set.representative())
+            .collect();
+
+        let mut edges = vec![Vec::new(); names.len()];
+        for (i, &state) in names.iter().enumerate() {
+            for (symbol, representative) in symbols.iter().enumerate() {
+                for targets in symbols::follow(&nfa.nodes[state], representative) {
+                    let mut targets: Vec<usize> = targets.iter().map(|x| index[x]).collect();
+                    targets.sort();
+                    edges[i].extend(targets.into_iter().map(|target| (symbol, target)));
+                }
+            }
+        }
+        let accepting: Vec<bool> = names
+            .iter()
+            .map(|&state| nfa.final_states.contains(state))
+            .collect();
+
+        let word = |path: Vec<usize>| path.into_iter().map(|i| symbols[i].clone()).collect();
+        find_lasso(&edges, &accepting, &[index[&nfa.start]]).map(|(prefix, cycle, state)| Lasso {
+            prefix: word(prefix),
+            cycle: word(cycle),
+            state: names[state].clone(),
+        })
+    }
+
+    /// Builds an automata accepting the words that both automata accept.
+    ///
+    /// Its states are triples of a state from each automata and which of them it is waiting to
+    /// see an accepting state of, named like `left & right / 1`. The triple moves on to waiting
+    /// for the other automata once it sees the accepting state it is waiting for, and the
+    /// accepting states are those where the left one is seen. Only the states reachable from the
+    /// start are built, and the alphabet is made of the symbols both automata have.
+    pub fn intersection(&self, other: &Buchi<Sanitary>) -> Buchi<Sanitary> {
+        let (left, right) = (&self.0, &other.0);
+        let atoms: Vec<(String, String)> = symbols::minterms(
+            left.alphabet
+                .iter()
+                .chain(&right.alphabet)
+                .chain(left.nodes.values().flat_map(|transitions| transitions.keys()))
+                .chain(right.nodes.values().flat_map(|transitions| transitions.keys())),
+        ).into_iter()
+            .map(|(label, set)| (label, set.representative().unwrap()))
+            .filter(|&(_, ref symbol)| {
+                symbols::in_alphabet(&left.alphabet, symbol) &&
+                    symbols::in_alphabet(&right.alphabet, symbol)
+            })
+            .collect();
+
+        let name = |&(left_state, right_state, waiting): &(&String, &String, u8)| {
+            format!("{} & {} / {}", left_state, right_state, waiting)
+        };
+        let start = (&left.start, &right.start, 1);
+        let mut seen = HashSet::new();
+        seen.insert(start);
+        let mut work = VecDeque::new();
+        work.push_back(start);
+        let mut accepting_states = HashSet::new();
+        let mut nodes = HashMap::new();
+        while let Some(state) = work.pop_front() {
+            let (left_state, right_state, waiting) = state;
+            let next_waiting = match waiting {
+                1 if left.final_states.contains(left_state) => 2,
+                2 if right.final_states.contains(right_state) => 1,
+                _ => waiting,
+            };
+            if waiting == 1 && left.final_states.contains(left_state) {
+                accepting_states.insert(name(&state));
+            }
+
+            let mut transitions = HashMap::new();
+            for &(ref label, ref symbol) in &atoms {
+                let mut targets = HashSet::new();
+                for left_targets in symbols::follow(&left.nodes[left_state], symbol) {
+                    for right_targets in symbols::follow(&right.nodes[right_state], symbol) {
+                        for left_target in left_targets {
+                            for right_target in right_targets {
+                                let target = (left_target, right_target, next_waiting);
+                                targets.insert(name(&target));
+                                if seen.insert(target) {
+                                    work.push_back(target);
+                                }
+                            }
+                        }
+                    }
+                }
+                if !targets.is_empty() {
+                    transitions.insert(label.to_owned(), targets);
+                }
+            }
+            nodes.insert(name(&state), transitions);
+        }
+
+        Buchi(Nfa {
+            _sanitized: Sanitary,
+            start: name(&start),
+            final_states: accepting_states,
+            alphabet: atoms.into_iter().map(|(label, _)| label).collect(),
+            nodes,
+        })
+    }
+}
+
+/// Finds an accepting state that can be reached from one of the starts and is on a cycle, using
+/// Tarjan's algorithm for strongly connected components. The state with the shortest path from
+/// the starts is picked, and the labels along that path and the shortest cycle through it are
+/// returned, along with the state.
+fn find_lasso<S: Copy>(
+    edges: &[Vec<(S, usize)>],
+    accepting: &[bool],
+    starts: &[usize],
+) -> Option<(Vec<S>, Vec<S>, usize)> {
+    // the shortest paths from the starts
+    let mut parents: Vec<Option<Option<(S, usize)>>> = vec![None; edges.len()];
+    let mut order = Vec::new();
+    let mut work = VecDeque::new();
+    for &start in starts {
+        if parents[start].is_none() {
+            parents[start] = Some(None);
+            work.push_back(start);
+        }
+    }
+    while let Some(node) = work.pop_front() {
+        order.push(node);
+        for &(label, target) in &edges[node] {
+            if parents[target].is_none() {
+                parents[target] = Some(Some((label, node)));
+                work.push_back(target);
+            }
+        }
+    }
+
+    // the strongly connected components of the reachable nodes, without recursing
+    let mut indices: Vec<Option<usize>> = vec![None; edges.len()];
+    let mut lowlinks = vec![0; edges.len()];
+    let mut on_stack = vec![false; edges.len()];
+    let mut stack = Vec::new();
+    let mut components: Vec<usize> = vec![0; edges.len()];
+    let mut sizes = Vec::new();
+    let mut next_index = 0;
+    for &root in &order {
+        if indices[root].is_some() {
+            continue;
+        }
+        let mut calls = vec![(root, 0)];
+        indices[root] = Some(next_index);
+        lowlinks[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        while let Some(&(node, edge)) = calls.last() {
+            if edge < edges[node].len() {
+                calls.last_mut().unwrap().1 += 1;
+                let target = edges[node][edge].1;
+                match indices[target] {
+                    None => {
+                        indices[target] = Some(next_index);
+                        lowlinks[target] = next_index;
+                        next_index += 1;
+                        stack.push(target);
+                        on_stack[target] = true;
+                        calls.push((target, 0));
+                    }
+                    Some(index) if on_stack[target] => {
+                        lowlinks[node] = lowlinks[node].min(index);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                lowlinks[parent] = lowlinks[parent].min(lowlinks[node]);
+            }
+            if Some(lowlinks[node]) == indices[node] {
+                let mut size = 0;
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    components[member] = sizes.len();
+                    size += 1;
+                    if member == node {
+                        break;
+                    }
+                }
+                sizes.push(size);
+            }
+        }
+    }
+
+    let on_cycle = |node: usize| {
+        sizes[components[node]] > 1 || edges[node].iter().any(|&(_, target)| target == node)
+    };
+    let state = order
+        .iter()
+        .cloned()
+        .find(|&node| accepting[node] && on_cycle(node));
+    let state = match state {
+        Some(state) => state,
+        None => return None,
+    };
+
+    let mut prefix = Vec::new();
+    let mut node = state;
+    while let Some(Some((label, parent))) = parents[node] {
+        prefix.push(label);
+        node = parent;
+    }
+    prefix.reverse();
+
+    // the shortest way back round, staying within the component
+    let mut cycle_parents: Vec<Option<(S, usize)>> = vec![None; edges.len()];
+    let mut work = VecDeque::new();
+    work.push_back(state);
+    'search: while let Some(node) = work.pop_front() {
+        for &(label, target) in &edges[node] {
+            if components[target] != components[state] || cycle_parents[target].is_some() {
+                continue;
+            }
+            cycle_parents[target] = Some((label, node));
+            if target == state {
+                break 'search;
+            }
+            work.push_back(target);
+        }
+    }
+    let mut cycle = Vec::new();
+    let mut node = state;
+    loop {
+        let (label, parent) = cycle_parents[node].unwrap();
+        cycle.push(label);
+        node = parent;
+        if node == state {
+            break;
+        }
+    }
+    cycle.reverse();
+
+    Some((prefix, cycle, state))
+}
+
+#[cfg(test)]
+use serde_json;
+
+#[cfg(test)]
+fn parse(json: &str) -> Buchi<Sanitary> {
+    let buchi: Buchi<Unsanitary> = serde_json::from_str(json).unwrap();
+    buchi.check().unwrap()
+}
+
+#[test]
+fn buchi_lasso() {
+    // an "a" then a "b", infinitely often, after at least two symbols
+    let buchi = parse(
+        r#"{
+            "start": "1",
+            "final_states": ["4"],
+            "alphabet": ["a", "b"],
+            "nodes": {
+                "1": { "a": ["2"], "b": ["2"] },
+                "2": { "a": ["3"], "b": ["2"] },
+                "3": { "a": ["3"], "b": ["4"] },
+                "4": { "a": ["3"], "b": ["2"] }
+            }
+        }"#,
+    );
+    let lasso = buchi.lasso().unwrap();
+    assert_eq!(
+        lasso,
+        Lasso {
+            prefix: vec!["a", "a", "b"].into_iter().map(String::from).collect(),
+            cycle: vec!["a", "b"].into_iter().map(String::from).collect(),
+            state: "4".to_string(),
+        }
+    );
+    assert!(
+        buchi
+            .accepts(
+                lasso.prefix.iter().map(|x| x.as_str()),
+                lasso.cycle.iter().map(|x| x.as_str()),
+            )
+            .unwrap()
+    );
+    assert!(buchi.accepts(vec!["b"], vec!["a", "a", "b", "b"]).unwrap());
+    assert!(!buchi.accepts(vec!["a", "b"], vec!["a"]).unwrap());
+    assert!(!buchi.accepts(vec!["a"], vec![]).unwrap());
+    match buchi.accepts(vec![], vec!["c"]) {
+        Err(NfaError::UnknownSymbol(ref symbol)) if symbol == "c" => {}
+        err @ _ => panic!("{:?}", err),
+    }
+
+    // the accepting state can only be visited once
+    let buchi = parse(
+        r#"{
+            "start": "1",
+            "final_states": ["2"],
+            "alphabet": ["a"],
+            "nodes": {
+                "1": { "a": ["2"] },
+                "2": { "a": ["3"] },
+                "3": { "a": ["3"] }
+            }
+        }"#,
+    );
+    assert!(buchi.is_empty());
+    assert!(!buchi.accepts(vec![], vec!["a"]).unwrap());
+}
+
+#[test]
+fn buchi_intersection() {
+    let infinitely_many = |symbol: &str| {
+        parse(&format!(
+            r#"{{
+                "start": "1",
+                "final_states": ["2"],
+                "alphabet": ["a", "b", "c"],
+                "nodes": {{
+                    "1": {{ "{0}": ["2"], "*": ["1"] }},
+                    "2": {{ "{0}": ["2"], "*": ["1"] }}
+                }}
+            }}"#,
+            symbol
+        ))
+    };
+    let both = infinitely_many("a").intersection(&infinitely_many("b"));
+    assert_eq!(both.start(), "1 & 1 / 1");
+    assert!(both.accepts(vec!["c"], vec!["a", "c", "b"]).unwrap());
+    assert!(!both.accepts(vec!["b"], vec!["a", "c"]).unwrap());
+    let lasso = both.lasso().unwrap();
+    assert_eq!(lasso.prefix, vec!["a".to_string()]);
+    assert_eq!(lasso.cycle, vec!["b".to_string(), "a".to_string()]);
+    assert_eq!(lasso.state, "2 & 1 / 1");
+
+    // eventually only "c"s, over a class
+    let finitely_many = parse(
+        r#"{
+            "start": "1",
+            "final_states": ["2"],
+            "alphabet": ["[a-c]"],
+            "nodes": {
+                "1": { "[a-c]": ["1"], "c": ["2"] },
+                "2": { "c": ["2"] }
+            }
+        }"#,
+    );
+    assert!(!finitely_many.is_empty());
+    assert!(infinitely_many("a").intersection(&finitely_many).is_empty());
+    // the cycle has to go round once waiting for each automata
+    let cs = infinitely_many("c").intersection(&finitely_many);
+    let lasso = cs.lasso().unwrap();
+    assert_eq!(lasso.cycle, vec!["c".to_string(), "c".to_string()]);
+    assert_eq!(lasso.state, "2 & 2 / 1");
+}
@@ -0,0 +1,280 @@
+//! Builders for constructing automata in code.
+
+use std::collections::{HashMap, HashSet};
+
+use automata::{Dfa, Nfa, NfaError, Sanitary, Unsanitary};
+use symbols;
+
+/// Builds an [`Nfa`](struct.Nfa.html) one piece at a time.
+///
+/// Both ends of a transition are added as states, and its symbol is added to the alphabet if it
+/// is a plain symbol that none of the alphabet's classes contain. [Symbol
+/// classes](symbols/index.html) may overlap, so the classes making up the alphabet have to be
+/// added with [`symbol`](#method.symbol). The start and final states are not added automatically,
+/// so that misspelling them is caught when the NFA is checked.
+///
+/// ```
+/// use state_machina::NfaBuilder;
+///
+/// let nfa = NfaBuilder::new()
+///     .start("q0")
+///     .transition("q0", "a", "q0")
+///     .transition("q0", "a", "q1")
+///     .final_state("q1")
+///     .check()
+///     .unwrap();
+/// assert!(nfa.accepts(vec!["a", "a"]).unwrap());
+/// ```
+#[derive(Debug, Default)]
+pub struct NfaBuilder {
+    start: Option<String>,
+    final_states: HashSet<String>,
+    alphabet: HashSet<String>,
+    nodes: HashMap<String, HashMap<String, HashSet<String>>>,
+}
+
+impl NfaBuilder {
+    /// Creates a builder for an empty NFA.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the start state, replacing any previous start state.
+    pub fn start<S: Into<String>>(mut self, state: S) -> Self {
+        self.start = Some(state.into());
+        self
+    }
+
+    /// Adds a state without any transitions.
+    pub fn state<S: Into<String>>(mut self, state: S) -> Self {
+        self.nodes.entry(state.into()).or_insert_with(HashMap::new);
+        self
+    }
+
+    /// Marks a state as final (accepting).
+    pub fn final_state<S: Into<String>>(mut self, state: S) -> Self {
+        self.final_states.insert(state.into());
+        self
+    }
+
+    /// Adds a symbol to the alphabet, for symbols without any transitions.
+    pub fn symbol<S: Into<String>>(mut self, symbol: S) -> Self {
+        self.alphabet.insert(symbol.into());
+        self
+    }
+
+    /// Adds a transition from one state to another on a symbol. Transitions accumulate, so
+    /// calling this repeatedly with the same state and symbol adds more target states.
+    pub fn transition<F, S, T>(mut self, from: F, symbol: S, to: T) -> Self
+    where
+        F: Into<String>,
+        S: Into<String>,
+        T: Into<String>,
+    {
+        let symbol = symbol.into();
+        let to = to.into();
+        if symbols::is_plain(&symbol) {
+            self.alphabet.insert(symbol.to_owned());
+        }
+        self.nodes.entry(to.to_owned()).or_insert_with(HashMap::new);
+        self.nodes
+            .entry(from.into())
+            .or_insert_with(HashMap::new)
+            .entry(symbol)
+            .or_insert_with(HashSet::new)
+            .insert(to);
+        self
+    }
+
+    /// Finishes building, without validating the NFA. If no start state was given it is empty,
+    /// which checking will reject.
+    pub fn build(self) -> Nfa<Unsanitary> {
+        Nfa::new(
+            self.start.unwrap_or_default(),
+            self.final_states,
+            symbols::remove_covered(self.alphabet),
+            self.nodes,
+        )
+    }
+
+    /// Finishes building, and checks the NFA.
+    pub fn check(self) -> Result<Nfa<Sanitary>, NfaError> {
+        self.build().check()
+    }
+}
+
+/// Builds a [`Dfa`](struct.Dfa.html) one piece at a time, following the same rules as
+/// [`NfaBuilder`](struct.NfaBuilder.html).
+#[derive(Debug, Default)]
+pub struct DfaBuilder {
+    start: Option<String>,
+    final_states: HashSet<String>,
+    alphabet: HashSet<String>,
+    nodes: HashMap<String, HashMap<String, String>>,
+}
+
+impl DfaBuilder {
+    /// Creates a builder for an empty DFA.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the start state, replacing any previous start state.
+    pub fn start<S: Into<String>>(mut self, state: S) -> Self {
+        self.start = Some(state.into());
+        self
+    }
+
+    /// Adds a state without any transitions.
+    pub fn state<S: Into<String>>(mut self, state: S) -> Self {
+        self.nodes.entry(state.into()).or_insert_with(HashMap::new);
+        self
+    }
+
+    /// Marks a state as final (accepting).
+    pub fn final_state<S: Into<String>>(mut self, state: S) -> Self {
+        self.final_states.insert(state.into());
+        self
+    }
+
+    /// Adds a symbol to the alphabet, for symbols without any transitions.
+    pub fn symbol<S: Into<String>>(mut self, symbol: S) -> Self {
+        self.alphabet.insert(symbol.into());
+        self
+    }
+
+    /// Sets the transition from one state on a symbol, replacing any previous transition.
+    pub fn transition<F, S, T>(mut self, from: F, symbol: S, to: T) -> Self
+    where
+        F: Into<String>,
+        S: Into<String>,
+        T: Into<String>,
+    {
+        let symbol = symbol.into();
+        let to = to.into();
+        if symbols::is_plain(&symbol) {
+            self.alphabet.insert(symbol.to_owned());
+        }
+        self.nodes.entry(to.to_owned()).or_insert_with(HashMap::new);
+        self.nodes
+            .entry(from.into())
+            .or_insert_with(HashMap::new)
+            .insert(symbol, to);
+        self
+    }
+
+    /// Finishes building, without validating the DFA.
+    pub fn build(self) -> Dfa {
+        Dfa::new(
+            self.start.unwrap_or_default(),
+            self.final_states,
+            self.alphabet,
+            self.nodes,
+        )
+    }
+
+    /// Finishes building, ensuring that the start and final states exist, that no state is
+    /// unnamed, and that no two transitions out of a state share a symbol. Missing transitions
+    /// are allowed, and reject any word that needs them.
+    pub fn check(self) -> Result<Dfa, NfaError> {
+        let DfaBuilder {
+            start,
+            final_states,
+            alphabet,
+            nodes,
+        } = self;
+        let start = start.unwrap_or_default();
+
+        if let Some(invalid_state) = nodes.keys().find(|state| state.is_empty()) {
+            return Err(NfaError::InvalidStateName(invalid_state.to_owned()));
+        }
+        if let Some(unknown_state) = Some(&start)
+            .into_iter()
+            .chain(&final_states)
+            .find(|&state| !nodes.contains_key(state))
+        {
+            return Err(NfaError::UnknownState(unknown_state.to_owned()));
+        }
+        let alphabet = symbols::remove_covered(alphabet);
+        let symbols = symbols::check_alphabet(&alphabet)?;
+        for transitions in nodes.values() {
+            symbols::check_deterministic(transitions.keys(), &symbols)?;
+        }
+
+        Ok(Dfa::new(start, final_states, alphabet, nodes))
+    }
+}
+
+#[test]
+fn build_nfa() {
+    let nfa = NfaBuilder::new()
+        .start("1")
+        .transition("1", "a", "1")
+        .transition("1", "a", "2")
+        .transition("1", "b", "1")
+        .transition("2", "a", "3")
+        .transition("2", "b", "3")
+        .final_state("3")
+        .symbol("c")
+        .check()
+        .unwrap();
+
+    assert_eq!(nfa.start(), "1");
+    assert_eq!(nfa.states().count(), 3);
+    assert_eq!(
+        nfa.alphabet(),
+        &vec!["a".into(), "b".into(), "c".into()].into_iter().collect()
+    );
+    assert_eq!(
+        nfa.transition("1", "a").unwrap(),
+        &vec!["1".into(), "2".into()].into_iter().collect()
+    );
+    assert!(nfa.accepts(vec!["b", "a", "b"]).unwrap());
+    assert!(!nfa.accepts(vec!["a", "b", "b"]).unwrap());
+}
+
+#[test]
+fn build_invalid_nfa() {
+    match NfaBuilder::new()
+        .start("1")
+        .transition("1", "a", "2")
+        .final_state("3")
+        .check()
+        .unwrap_err()
+    {
+        NfaError::UnknownState(err) => assert_eq!(err, "3"),
+        err @ _ => panic!(err),
+    }
+
+    match NfaBuilder::new().state("1").check().unwrap_err() {
+        NfaError::UnknownState(err) => assert_eq!(err, ""),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn build_dfa() {
+    let dfa = DfaBuilder::new()
+        .start("even")
+        .transition("even", "a", "odd")
+        .transition("odd", "a", "even")
+        .transition("odd", "a", "odd")
+        .final_state("odd")
+        .check()
+        .unwrap();
+
+    assert_eq!(dfa.transition("odd", "a"), Some("odd"));
+    assert!(dfa.accepts(vec!["a", "a", "a"]).unwrap());
+    assert!(!dfa.accepts(vec![]).unwrap());
+
+    match DfaBuilder::new()
+        .start("1")
+        .transition("1", "a", "1")
+        .final_state("2")
+        .check()
+        .unwrap_err()
+    {
+        NfaError::UnknownState(err) => assert_eq!(err, "2"),
+        err @ _ => panic!(err),
+    }
+}
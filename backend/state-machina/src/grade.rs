@@ -0,0 +1,341 @@
+//! Grading automata against a reference, for marking exercises.
+
+use std::collections::{HashMap, VecDeque};
+
+use automata::{Dfa, NfaError};
+use limits::Budget;
+use symbols;
+
+/// Settings for grading a DFA against a reference.
+///
+/// ```
+/// use state_machina::{DfaBuilder, Grader};
+///
+/// // words ending in "a"
+/// let reference = DfaBuilder::new()
+///     .start("1")
+///     .transition("1", "a", "2")
+///     .transition("1", "b", "1")
+///     .transition("2", "a", "2")
+///     .transition("2", "b", "1")
+///     .final_state("2")
+///     .check()
+///     .unwrap();
+/// // words containing an "a"
+/// let student = DfaBuilder::new()
+///     .start("1")
+///     .transition("1", "a", "2")
+///     .transition("1", "b", "1")
+///     .transition("2", "a", "2")
+///     .transition("2", "b", "2")
+///     .final_state("2")
+///     .check()
+///     .unwrap();
+///
+/// let grade = Grader::default().grade(&reference, &student);
+/// assert!(!grade.equivalent);
+/// assert!(grade.should_accept.is_empty());
+/// assert_eq!(grade.should_reject[0], vec!["a", "b"]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Grader {
+    /// The longest words compared for the similarity score.
+    pub max_length: usize,
+
+    /// The most counterexamples of each kind to report.
+    pub max_counterexamples: usize,
+}
+
+impl Default for Grader {
+    fn default() -> Self {
+        Grader {
+            max_length: 10,
+            max_counterexamples: 5,
+        }
+    }
+}
+
+/// How a DFA compares to a reference.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Grade {
+    /// Whether the DFA accepts exactly the same words as the reference.
+    pub equivalent: bool,
+
+    /// Words that the reference accepts but the DFA rejects, shortest first.
+    pub should_accept: Vec<Vec<String>>,
+
+    /// Words that the reference rejects but the DFA accepts, shortest first.
+    pub should_reject: Vec<Vec<String>>,
+
+    /// The fraction of words, up to the maximum length, that the DFA and the reference agree on.
+    /// This is `1.0` for equivalent DFAs.
+    pub similarity: f64,
+}
+
+/// A state of the product of two DFAs. A missing state is the implicit dead state that missing
+/// transitions, and symbols outside of a DFA's alphabet, lead to.
+type Pair<'a> = (Option<&'a str>, Option<&'a str>);
+
+impl Grader {
+    /// Grades a DFA against a reference. Symbols in only one of the alphabets are rejected by the
+    /// DFA that does not have them. [Symbol classes](symbols/index.html) count every symbol in
+    /// them, and counterexamples use the first symbol of a class.
+    pub fn grade(&self, reference: &Dfa, student: &Dfa) -> Grade {
+        self.compare(reference, student, &Budget::unlimited())
+            .expect("no limits to exceed")
+    }
+
+    pub(crate) fn compare<'a>(
+        &self,
+        reference: &'a Dfa,
+        student: &'a Dfa,
+        budget: &Budget,
+    ) -> Result<Grade, NfaError> {
+        // every symbol of a class behaves the same in both DFAs, so one stands in for the rest
+        let dfas = [reference, student];
+        let labels = dfas.iter().flat_map(|dfa| {
+            dfa.alphabet
+                .iter()
+                .chain(dfa.nodes.values().flat_map(|transitions| transitions.keys()))
+        });
+        let (symbols, weights): (Vec<String>, Vec<f64>) = symbols::minterms(labels)
+            .into_iter()
+            .map(|(_, set)| (set.representative().unwrap(), set.len() as f64))
+            .unzip();
+        let reference_table = table(reference, &symbols);
+        let student_table = table(student, &symbols);
+
+        let step = |(left, right): Pair<'a>, symbol: usize| -> Pair<'a> {
+            (
+                left.and_then(|state| reference_table[state][symbol]),
+                right.and_then(|state| student_table[state][symbol]),
+            )
+        };
+        let accepts = |(left, right): Pair| {
+            (
+                left.map_or(false, |state| reference.final_states.contains(state)),
+                right.map_or(false, |state| student.final_states.contains(state)),
+            )
+        };
+        let start: Pair = (Some(&reference.start), Some(&student.start));
+
+        // a breadth first search of the product, so that each pair is first reached by its
+        // shortest word, and pairs are reached in order of their words
+        let mut parents: HashMap<Pair, Option<(Pair, usize)>> = HashMap::new();
+        parents.insert(start, None);
+        let mut work = VecDeque::new();
+        work.push_back(start);
+        let mut should_accept = Vec::new();
+        let mut should_reject = Vec::new();
+        let mut equivalent = true;
+        while let Some(pair) = work.pop_front() {
+            budget.check()?;
+            let (expected, actual) = accepts(pair);
+            if expected != actual {
+                equivalent = false;
+                let counterexamples = if expected {
+                    &mut should_accept
+                } else {
+                    &mut should_reject
+                };
+                if counterexamples.len() < self.max_counterexamples {
+                    counterexamples.push(word(&parents, pair, &symbols));
+                }
+            }
+            for symbol in 0..symbols.len() {
+                let next = step(pair, symbol);
+                if !parents.contains_key(&next) {
+                    parents.insert(next, Some((pair, symbol)));
+                    work.push_back(next);
+                }
+            }
+        }
+
+        // the number of words of each length that reach each pair
+        let mut counts: HashMap<Pair, f64> = HashMap::new();
+        counts.insert(start, 1.0);
+        let mut agreed = 0.0;
+        let mut total = 0.0;
+        for length in 0..self.max_length + 1 {
+            budget.check()?;
+            for (&pair, &count) in &counts {
+                let (expected, actual) = accepts(pair);
+                if expected == actual {
+                    agreed += count;
+                }
+                total += count;
+            }
+            if length == self.max_length {
+                break;
+            }
+            let mut next_counts = HashMap::new();
+            for (&pair, &count) in &counts {
+                for (symbol, &weight) in weights.iter().enumerate() {
+                    *next_counts.entry(step(pair, symbol)).or_insert(0.0) += count * weight;
+                }
+            }
+            counts = next_counts;
+        }
+
+        Ok(Grade {
+            equivalent,
+            should_accept,
+            should_reject,
+            similarity: agreed / total,
+        })
+    }
+}
+
+/// The state each state of a DFA goes to on each symbol, with `None` for the dead state.
+fn table<'a>(dfa: &'a Dfa, symbols: &[String]) -> HashMap<&'a str, Vec<Option<&'a str>>> {
+    let known: Vec<bool> = symbols
+        .iter()
+        .map(|symbol| symbols::in_alphabet(&dfa.alphabet, symbol))
+        .collect();
+    dfa.nodes
+        .iter()
+        .map(|(state, transitions)| {
+            let targets = symbols
+                .iter()
+                .zip(&known)
+                .map(|(symbol, &known)| if known {
+                    symbols::follow(transitions, symbol)
+                        .pop()
+                        .map(|state| state.as_str())
+                } else {
+                    None
+                })
+                .collect();
+            (state.as_str(), targets)
+        })
+        .collect()
+}
+
+/// Follows the parents of a pair back to the start, to find the word that reaches it.
+fn word<'a>(
+    parents: &HashMap<Pair<'a>, Option<(Pair<'a>, usize)>>,
+    mut pair: Pair<'a>,
+    symbols: &[String],
+) -> Vec<String> {
+    let mut word = Vec::new();
+    while let Some((parent, symbol)) = parents[&pair] {
+        word.push(symbols[symbol].to_owned());
+        pair = parent;
+    }
+    word.reverse();
+    word
+}
+
+#[cfg(test)]
+use builder::DfaBuilder;
+
+#[test]
+fn grade_equivalent() {
+    // an even number of "a"s, with the reference minimal and the student not
+    let reference = DfaBuilder::new()
+        .start("even")
+        .transition("even", "a", "odd")
+        .transition("odd", "a", "even")
+        .final_state("even")
+        .check()
+        .unwrap();
+    let student = DfaBuilder::new()
+        .start("1")
+        .transition("1", "a", "2")
+        .transition("2", "a", "3")
+        .transition("3", "a", "4")
+        .transition("4", "a", "1")
+        .final_state("1")
+        .final_state("3")
+        .check()
+        .unwrap();
+
+    let grade = Grader::default().grade(&reference, &student);
+    assert_eq!(
+        grade,
+        Grade {
+            equivalent: true,
+            should_accept: vec![],
+            should_reject: vec![],
+            similarity: 1.0,
+        }
+    );
+}
+
+#[test]
+fn grade_counterexamples() {
+    // an even number of "a"s
+    let reference = DfaBuilder::new()
+        .start("even")
+        .transition("even", "a", "odd")
+        .transition("odd", "a", "even")
+        .transition("even", "b", "even")
+        .transition("odd", "b", "odd")
+        .final_state("even")
+        .check()
+        .unwrap();
+    // the student forgot about the empty word and "b"s
+    let student = DfaBuilder::new()
+        .start("1")
+        .transition("1", "a", "2")
+        .transition("2", "a", "3")
+        .transition("3", "a", "2")
+        .final_state("3")
+        .check()
+        .unwrap();
+
+    let grade = Grader {
+        max_length: 2,
+        max_counterexamples: 2,
+    }.grade(&reference, &student);
+    assert!(!grade.equivalent);
+    assert_eq!(
+        grade.should_accept,
+        vec![
+            Vec::<String>::new(),
+            vec!["b".to_string()],
+        ]
+    );
+    assert!(grade.should_reject.is_empty());
+
+    // of the 7 words up to length 2, they agree on "a", "ab", "ba" and "aa"
+    assert_eq!(grade.similarity, 4.0 / 7.0);
+
+    let grade = Grader::default().grade(&student, &reference);
+    assert_eq!(grade.should_reject.len(), 2);
+    assert!(grade.should_accept.is_empty());
+}
+
+#[test]
+fn grade_symbol_classes() {
+    // identifiers, as a letter followed by letters and digits
+    let reference = DfaBuilder::new()
+        .start("1")
+        .symbol("[:alnum:]")
+        .transition("1", "[:alpha:]", "2")
+        .transition("2", "[:alnum:]", "2")
+        .final_state("2")
+        .check()
+        .unwrap();
+    // the student only allows lowercase letters
+    let student = DfaBuilder::new()
+        .start("1")
+        .symbol("[:alnum:]")
+        .transition("1", "[a-z]", "2")
+        .transition("2", "[a-z0-9]", "2")
+        .final_state("2")
+        .check()
+        .unwrap();
+
+    let grade = Grader {
+        max_length: 1,
+        max_counterexamples: 5,
+    }.grade(&reference, &student);
+    assert!(!grade.equivalent);
+    assert_eq!(grade.should_accept[0], vec!["A"]);
+    assert!(grade.should_reject.is_empty());
+
+    // of the 63 words up to length 1, they disagree on the 26 uppercase letters
+    assert_eq!(grade.similarity, 37.0 / 63.0);
+}
@@ -0,0 +1,529 @@
+//! Lexers, which split text into tokens with a single DFA.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::error;
+use std::fmt;
+
+use automata::{equivalent_states, merge_classes, Dfa, Nfa, NfaError, Sanitary};
+use matcher::{Failures, Matcher};
+use regex::parse_regex;
+use symbols;
+
+/// How a token is recognised.
+#[derive(Debug)]
+enum Pattern {
+    Regex(String),
+    Nfa(Nfa<Sanitary>),
+}
+
+#[derive(Debug)]
+struct Rule {
+    name: String,
+    priority: u32,
+    pattern: Pattern,
+}
+
+/// Builds a [`Lexer`](struct.Lexer.html) from named token patterns, each either a regular
+/// expression or an NFA over single characters.
+///
+/// The lexer always takes the longest token it can. When several patterns match the same text,
+/// the one with the highest priority wins, and the one added first breaks any tie.
+///
+/// ```
+/// use state_machina::LexerBuilder;
+///
+/// let lexer = LexerBuilder::new()
+///     .regex("keyword", 1, "let|in")
+///     .regex("name", 0, r"[:alpha:_]\w*")
+///     .regex("number", 0, r"\d+")
+///     .regex("operator", 0, r"[+*=\-]")
+///     .regex("space", 0, r"\s+")
+///     .build()
+///     .unwrap();
+///
+/// let tokens: Vec<&str> = lexer
+///     .tokenize("let x1 = 42")
+///     .unwrap()
+///     .into_iter()
+///     .filter(|token| token.name != "space")
+///     .map(|token| token.name)
+///     .collect();
+/// assert_eq!(tokens, vec!["keyword", "name", "operator", "number"]);
+///
+/// // the longest token wins over the keyword
+/// let tokens = lexer.tokenize("inside").unwrap();
+/// assert_eq!((tokens[0].name, tokens[0].end), ("name", 6));
+/// ```
+#[derive(Debug, Default)]
+pub struct LexerBuilder {
+    rules: Vec<Rule>,
+}
+
+impl LexerBuilder {
+    /// Creates a builder without any tokens.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a token described by a regular expression. The syntax is a small subset of the usual
+    /// one: characters, [symbol classes](symbols/index.html) in square brackets, `\d`, `\s` and
+    /// `\w` for digits, whitespace and word characters, grouping with parentheses, `|` for
+    /// alternatives, and `*`, `+` and `?` for repetition. A backslash escapes any other
+    /// character. There is no `.`, as a class such as `[:alnum:]` says which characters it means.
+    pub fn regex<S, P>(mut self, name: S, priority: u32, pattern: P) -> Self
+    where
+        S: Into<String>,
+        P: Into<String>,
+    {
+        self.rules.push(Rule {
+            name: name.into(),
+            priority,
+            pattern: Pattern::Regex(pattern.into()),
+        });
+        self
+    }
+
+    /// Adds a token recognised by an NFA. A `*` in the NFA only stands for the symbols of its own
+    /// alphabet, not those of the other tokens.
+    pub fn nfa<S: Into<String>>(mut self, name: S, priority: u32, nfa: Nfa<Sanitary>) -> Self {
+        self.rules.push(Rule {
+            name: name.into(),
+            priority,
+            pattern: Pattern::Nfa(nfa),
+        });
+        self
+    }
+
+    /// Combines the tokens into a single NFA, with a state named `start` that starts every token
+    /// and the states of each token's NFA prefixed with its index, such as `0:q1`. The NFA is
+    /// made deterministic, with each DFA state labelled with the best token its subset accepts,
+    /// and minimised without merging states that accept different tokens.
+    pub fn build(self) -> Result<Lexer, LexerError> {
+        let start = "start".to_owned();
+        let mut names = Vec::new();
+        let mut priorities = Vec::new();
+        let mut labels = HashSet::new();
+        let mut nodes = HashMap::new();
+        let mut final_states = HashSet::new();
+        let mut nfa_tokens = HashMap::new();
+        nodes.insert(start.to_owned(), HashMap::new());
+
+        for (token, rule) in self.rules.into_iter().enumerate() {
+            let nfa = match rule.pattern {
+                Pattern::Regex(ref pattern) => parse_regex(pattern).map_err(|(position, why)| {
+                    LexerError::InvalidRegex(rule.name.to_owned(), position, why)
+                })?,
+                Pattern::Nfa(nfa) => nfa,
+            };
+            if nfa.final_states.contains(&nfa.start) {
+                return Err(LexerError::EmptyToken(rule.name));
+            }
+
+            // `*` is replaced before the alphabets are combined, as it only stands for this one
+            let (alphabet, token_nodes) = symbols::split_classes(nfa.alphabet, nfa.nodes);
+            labels.extend(alphabet);
+            let rename = |state: &String| format!("{}:{}", token, state);
+            for (state, transitions) in token_nodes {
+                let transitions: HashMap<String, HashSet<String>> = transitions
+                    .into_iter()
+                    .map(|(label, targets)| (label, targets.iter().map(&rename).collect()))
+                    .collect();
+                if state == nfa.start {
+                    let start_transitions = nodes.get_mut(&start).unwrap();
+                    for (label, targets) in &transitions {
+                        start_transitions
+                            .entry(label.to_owned())
+                            .or_insert_with(HashSet::new)
+                            .extend(targets.iter().cloned());
+                    }
+                }
+                nodes.insert(rename(&state), transitions);
+            }
+            for state in &nfa.final_states {
+                final_states.insert(rename(state));
+                nfa_tokens.insert(rename(state), token);
+            }
+            names.push(rule.name);
+            priorities.push(rule.priority);
+        }
+
+        let alphabet = symbols::minterms(&labels)
+            .into_iter()
+            .map(|(label, _)| label)
+            .collect();
+        let (dfa, steps) = Nfa::new(start, final_states, alphabet, nodes)
+            .check()?
+            .make_deterministic_traced();
+
+        let mut tokens: HashMap<String, usize> = HashMap::new();
+        for step in steps {
+            let best = step.subset
+                .iter()
+                .filter_map(|state| nfa_tokens.get(state))
+                .max_by_key(|&&token| (priorities[token], Reverse(token)));
+            if let Some(&token) = best {
+                tokens.insert(step.state, token);
+            }
+        }
+
+        let classes = {
+            let equivalent = equivalent_states(&dfa.alphabet, &dfa.nodes, |left, right| {
+                tokens.get(left) != tokens.get(right)
+            });
+            merge_classes(dfa.nodes.keys(), equivalent)
+        };
+        let tokens: HashMap<String, usize> = tokens
+            .into_iter()
+            .map(|(state, token)| (classes[&state].to_owned(), token))
+            .collect();
+        let dfa = dfa.merge_states(&classes);
+        let matcher = Matcher::compile(&dfa, |state| tokens.get(state).cloned())?;
+
+        Ok(Lexer {
+            names,
+            dfa,
+            tokens,
+            matcher,
+        })
+    }
+}
+
+/// Why a lexer could not be built.
+#[derive(Debug)]
+pub enum LexerError {
+    /// A token's regular expression is invalid at a byte offset, for a reason.
+    InvalidRegex(String, usize, &'static str),
+    /// A token matches the empty string, so the lexer could get stuck on it.
+    EmptyToken(String),
+    /// The tokens cannot be combined, such as when they use symbols longer than a character.
+    Nfa(NfaError),
+}
+
+impl fmt::Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LexerError::InvalidRegex(ref name, position, why) => write!(
+                f,
+                "invalid regular expression for token {:?} at offset {}: {}",
+                name,
+                position,
+                why
+            ),
+            LexerError::EmptyToken(ref name) => {
+                write!(f, "token {:?} matches the empty string", name)
+            }
+            LexerError::Nfa(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for LexerError {
+    fn description(&self) -> &str {
+        "invalid lexer"
+    }
+}
+
+impl From<NfaError> for LexerError {
+    fn from(err: NfaError) -> Self {
+        LexerError::Nfa(err)
+    }
+}
+
+/// Splits text into the longest tokens it can, from a DFA whose final states are labelled with
+/// tokens. Built with a [`LexerBuilder`](struct.LexerBuilder.html).
+#[derive(Debug, Clone)]
+pub struct Lexer {
+    /// The name of every token.
+    names: Vec<String>,
+
+    /// The minimised DFA for every token.
+    dfa: Dfa,
+
+    /// The token accepted by each final state of the DFA.
+    tokens: HashMap<String, usize>,
+
+    /// The DFA, compiled.
+    matcher: Matcher,
+}
+
+/// A token found in some input, with its byte offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'l> {
+    /// The name of the token.
+    pub name: &'l str,
+
+    /// The offset of the first byte of the token.
+    pub start: usize,
+
+    /// The offset just past the last byte of the token.
+    pub end: usize,
+}
+
+/// Input that no token matches, at a byte offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenError {
+    pub position: usize,
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no token matches at offset {}", self.position)
+    }
+}
+
+impl error::Error for TokenError {
+    fn description(&self) -> &str {
+        "no token matches"
+    }
+}
+
+impl Lexer {
+    /// The DFA that recognises every token.
+    pub fn dfa(&self) -> &Dfa {
+        &self.dfa
+    }
+
+    /// The token a state of the DFA accepts, if it is final.
+    pub fn token(&self, state: &str) -> Option<&str> {
+        self.tokens
+            .get(state)
+            .map(|&token| self.names[token].as_str())
+    }
+
+    /// Iterates over the tokens of some input. The iteration stops after the first error.
+    pub fn tokens<'l, 'i, B>(&'l self, input: &'i B) -> Tokens<'l, 'i>
+    where
+        B: AsRef<[u8]> + ?Sized,
+    {
+        Tokens {
+            lexer: self,
+            input: input.as_ref(),
+            position: 0,
+            failed: false,
+            failures: Failures::new(&self.matcher),
+        }
+    }
+
+    /// Splits all of some input into tokens. Finding the longest token can mean scanning well
+    /// past its end, but nothing scanned without finding a token is scanned again, so this takes
+    /// time linear in the input, at worst a step per byte for each state of the DFA. Remembering
+    /// what was scanned takes a bit for each state of the DFA and each byte from the start of the
+    /// current token to the furthest any scan has reached, so a scan that runs far past the
+    /// tokens costs memory as well as time.
+    pub fn tokenize<B>(&self, input: &B) -> Result<Vec<Token>, TokenError>
+    where
+        B: AsRef<[u8]> + ?Sized,
+    {
+        self.tokens(input).collect()
+    }
+}
+
+/// An iterator over the tokens of some input, created by
+/// [`Lexer::tokens`](struct.Lexer.html#method.tokens).
+#[derive(Debug)]
+pub struct Tokens<'l, 'i> {
+    lexer: &'l Lexer,
+    input: &'i [u8],
+    position: usize,
+    failed: bool,
+    failures: Failures,
+}
+
+impl<'l, 'i> Iterator for Tokens<'l, 'i> {
+    type Item = Result<Token<'l>, TokenError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.position == self.input.len() {
+            return None;
+        }
+        // no token starts before this one again
+        self.failures.forget_before(self.position);
+        match self.lexer
+            .matcher
+            .longest(self.input, self.position, &mut self.failures)
+        {
+            Some((end, token)) => {
+                let start = self.position;
+                self.position = end;
+                Some(Ok(Token {
+                    name: &self.lexer.names[token],
+                    start,
+                    end,
+                }))
+            }
+            None => {
+                self.failed = true;
+                Some(Err(TokenError {
+                    position: self.position,
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use builder::NfaBuilder;
+
+#[cfg(test)]
+use std::time::{Duration, Instant};
+
+#[test]
+fn lexer_priorities() {
+    let lexer = LexerBuilder::new()
+        .regex("name", 0, "[a-z]+")
+        .regex("if", 1, "if")
+        .regex("else", 1, "else")
+        .regex("space", 0, " ")
+        .build()
+        .unwrap();
+    let tokens: Vec<(&str, usize, usize)> = lexer
+        .tokenize("if iffy else elsewhere")
+        .unwrap()
+        .into_iter()
+        .map(|token| (token.name, token.start, token.end))
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            ("if", 0, 2),
+            ("space", 2, 3),
+            ("name", 3, 7),
+            ("space", 7, 8),
+            ("else", 8, 12),
+            ("space", 12, 13),
+            ("name", 13, 22),
+        ]
+    );
+
+    // final states for different tokens are never merged, and names only have a state of their
+    // own for each prefix of a keyword, which may still become the keyword
+    let labels: HashSet<&str> = lexer
+        .dfa()
+        .final_states()
+        .iter()
+        .map(|state| lexer.token(state).unwrap())
+        .collect();
+    assert_eq!(labels.len(), 4);
+    let names = lexer
+        .dfa()
+        .final_states()
+        .iter()
+        .filter(|state| lexer.token(state) == Some("name"))
+        .count();
+    assert_eq!(names, 5);
+
+    // with equal priorities, the first token added wins
+    let lexer = LexerBuilder::new()
+        .regex("first", 0, "ab")
+        .regex("second", 0, "a[b-z]")
+        .build()
+        .unwrap();
+    let tokens = lexer.tokenize("abac").unwrap();
+    assert_eq!(tokens[0].name, "first");
+    assert_eq!(tokens[1].name, "second");
+}
+
+#[test]
+fn lexer_nfa_tokens() {
+    // comments from "#" to the end of the line, where `*` is anything but a newline
+    let comment = NfaBuilder::new()
+        .start("1")
+        .symbol("[:alnum:# ]")
+        .symbol("\n")
+        .transition("1", "#", "2")
+        .transition("2", "*", "2")
+        .transition("2", "\n", "3")
+        .final_state("3")
+        .check()
+        .unwrap();
+    let lexer = LexerBuilder::new()
+        .nfa("comment", 0, comment)
+        .regex("word", 0, r"\w+")
+        .regex("space", 0, r"\s")
+        .build()
+        .unwrap();
+    let tokens: Vec<&str> = lexer
+        .tokenize("x # y z\nw")
+        .unwrap()
+        .into_iter()
+        .map(|token| token.name)
+        .collect();
+    assert_eq!(tokens, vec!["word", "space", "comment", "word"]);
+
+    let mut tokens = lexer.tokens("x ! y");
+    assert_eq!(tokens.next().unwrap().unwrap().name, "word");
+    assert_eq!(tokens.next().unwrap().unwrap().name, "space");
+    assert_eq!(tokens.next().unwrap(), Err(TokenError { position: 2 }));
+    assert!(tokens.next().is_none());
+}
+
+#[test]
+fn lexer_errors() {
+    assert_eq!(
+        LexerBuilder::new()
+            .regex("number", 0, "[0-9]+")
+            .regex("broken", 0, "(a|b")
+            .build()
+            .unwrap_err()
+            .to_string(),
+        "invalid regular expression for token \"broken\" at offset 0: unclosed `(`"
+    );
+    match LexerBuilder::new().regex("maybe", 0, "a?").build().unwrap_err() {
+        LexerError::EmptyToken(name) => assert_eq!(name, "maybe"),
+        err @ _ => panic!("{:?}", err),
+    }
+    let words = NfaBuilder::new()
+        .start("1")
+        .transition("1", "if", "2")
+        .final_state("2")
+        .check()
+        .unwrap();
+    match LexerBuilder::new().nfa("if", 0, words).build().unwrap_err() {
+        LexerError::Nfa(NfaError::InvalidSymbol(symbol)) => assert_eq!(symbol, "if"),
+        err @ _ => panic!("{:?}", err),
+    }
+}
+
+#[test]
+fn lexer_linear_tokenize() {
+    // every "a" is a token of its own, but each scan for "a+b" runs on to the end
+    let lexer = LexerBuilder::new()
+        .regex("a", 0, "a")
+        .regex("ab", 0, "a+b")
+        .build()
+        .unwrap();
+    let input = "a".repeat(200_000);
+    let started = Instant::now();
+    let tokens = lexer.tokenize(&input).unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(tokens.len(), 200_000);
+    assert!(tokens.iter().all(|token| token.name == "a"));
+
+    let names: Vec<&str> = lexer
+        .tokenize("aaab")
+        .unwrap()
+        .into_iter()
+        .map(|token| token.name)
+        .collect();
+    assert_eq!(names, vec!["ab"]);
+
+    // only what was scanned since the start of the current token is remembered, so a long input
+    // of short tokens needs little memory
+    let input = "aab a ".repeat(100_000);
+    let lexer = LexerBuilder::new()
+        .regex("ab", 0, "a+b")
+        .regex("word", 0, "a+")
+        .regex("space", 0, " ")
+        .build()
+        .unwrap();
+    let mut tokens = lexer.tokens(&input);
+    let mut count = 0;
+    while let Some(token) = tokens.next() {
+        assert!(token.is_ok());
+        assert!(tokens.failures.bits.len() <= 4 * tokens.failures.width);
+        count += 1;
+    }
+    assert_eq!(count, 400_000);
+    assert!(tokens.failures.bits.capacity() < 64);
+}
@@ -0,0 +1,76 @@
+//! Finite automata, and the algorithms for working with them.
+//!
+//! Automata start out as an [`Nfa<Unsanitary>`](struct.Nfa.html), either deserialised with serde,
+//! parsed from the [text format](text/index.html) or built with an
+//! [`NfaBuilder`](struct.NfaBuilder.html). Once [checked](struct.Nfa.html#method.check) they
+//! can be simulated, or converted into a [`Dfa`](struct.Dfa.html) and minimised.
+//!
+//! ```
+//! use state_machina::text;
+//!
+//! let nfa = text::parse_nfa(
+//!     "start: 1
+//!      final: 3
+//!      1 -a-> 1, 2
+//!      1 -b-> 1
+//!      2 -a-> 3
+//!      2 -b-> 3",
+//! ).unwrap();
+//! let dfa = nfa.check().unwrap().make_deterministic().minimise();
+//! assert!(dfa.accepts(vec!["b", "a", "a"]).unwrap());
+//! assert!(!dfa.accepts(vec!["a", "a", "b", "b"]).unwrap());
+//! ```
+
+extern crate itertools;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[cfg(test)]
+extern crate serde_json;
+
+mod automata;
+pub use automata::{Dfa, DistinguishedPair, MinimisationExplanation, NerodeClass, NerodeReport,
+                   Nfa, NfaError, Sanitary, SubsetStatus, SubsetStep, SubsetTransition,
+                   Unsanitary};
+
+mod builder;
+pub use builder::{DfaBuilder, NfaBuilder};
+
+mod transducer;
+pub use transducer::{Mealy, MealyTransition, Moore};
+
+mod pda;
+pub use pda::{Acceptance, Pda, PdaConfiguration, PdaRun, PdaTransition, EPSILON};
+
+mod turing;
+pub use turing::{Move, TapeSnapshot, TuringMachine, TuringOutcome, TuringRun, TuringTransition};
+
+mod limits;
+pub use limits::{Limit, Limits};
+
+mod grade;
+pub use grade::{Grade, Grader};
+
+mod random;
+pub use random::{Generator, GeneratorError};
+
+mod matcher;
+pub use matcher::{Match, Matcher, Matches};
+
+mod regex;
+
+mod lexer;
+pub use lexer::{Lexer, LexerBuilder, LexerError, Token, TokenError, Tokens};
+
+mod weighted;
+pub use weighted::{Boolean, Divisible, Probability, Semiring, Tropical, WeightedPath, Wfa};
+
+mod buchi;
+pub use buchi::{Buchi, Lasso};
+
+mod alternating;
+pub use alternating::{Afa, Formula};
+
+pub mod symbols;
+
+pub mod text;
@@ -0,0 +1,368 @@
+//! Limits on the work done converting and minimising automata.
+//!
+//! The subset construction can produce exponentially many states, so anything running it on
+//! untrusted input should do so through a [`Limits`](struct.Limits.html).
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use automata::{Dfa, MinimisationExplanation, NerodeReport, Nfa, NfaError, Sanitary, SubsetStep};
+use grade::{Grade, Grader};
+use pda::{Pda, PdaRun};
+use turing::{TuringMachine, TuringRun};
+
+/// A limit that was exceeded, along with its configured value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    /// The NFA had too many states.
+    NfaStates(usize),
+    /// The automata had too many symbols in its alphabet.
+    AlphabetSize(usize),
+    /// The DFA had, or would have had, too many states.
+    DfaStates(usize),
+    /// The DFA had too many states to explain every pair of them.
+    ExplainedStates(usize),
+    /// A Turing machine's tape grew too long.
+    TapeLength(usize),
+    /// The work took too long.
+    Time(Duration),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Limit::NfaStates(max) => write!(f, "more than {} NFA states", max),
+            Limit::AlphabetSize(max) => write!(f, "more than {} symbols in the alphabet", max),
+            Limit::DfaStates(max) => write!(f, "more than {} DFA states", max),
+            Limit::ExplainedStates(max) => {
+                write!(f, "more than {} DFA states to compare pairwise", max)
+            }
+            Limit::TapeLength(max) => write!(f, "more than {} cells on the tape", max),
+            Limit::Time(max) => write!(
+                f,
+                "more than {}.{:03} seconds",
+                max.as_secs(),
+                max.subsec_nanos() / 1_000_000
+            ),
+        }
+    }
+}
+
+/// Bounds on the size of automata, and on how long work on them may take. Every bound is
+/// optional, and the default is to have none.
+///
+/// ```
+/// use std::time::Duration;
+/// use state_machina::{Limits, NfaBuilder};
+///
+/// let limits = Limits {
+///     max_dfa_states: Some(1000),
+///     time: Some(Duration::from_secs(1)),
+///     ..Limits::default()
+/// };
+/// let nfa = NfaBuilder::new()
+///     .start("1")
+///     .transition("1", "a", "2")
+///     .final_state("2")
+///     .check()
+///     .unwrap();
+/// let dfa = limits.minimise(limits.make_deterministic(nfa).unwrap()).unwrap();
+/// assert!(dfa.accepts(vec!["a"]).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Limits {
+    /// The most states an NFA may have before conversion.
+    pub max_nfa_states: Option<usize>,
+
+    /// The most symbols an alphabet may have.
+    pub max_alphabet_size: Option<usize>,
+
+    /// The most states a DFA may have, whether produced by conversion or given for minimisation.
+    pub max_dfa_states: Option<usize>,
+
+    /// The most states a DFA may have when explaining its minimisation or reporting its
+    /// Myhill–Nerode classes, both of which compare every pair of states.
+    pub max_explained_states: Option<usize>,
+
+    /// The most symbols a pushdown automata's stack may hold while it is simulated. Runs whose
+    /// stack would grow any larger are given up on.
+    pub max_stack_size: Option<usize>,
+
+    /// The most cells a Turing machine's tape may have, including the input, while it is run.
+    pub max_tape_length: Option<usize>,
+
+    /// How long a single conversion, minimisation or simulation may take.
+    pub time: Option<Duration>,
+}
+
+impl Limits {
+    /// Converts an NFA into an equivalent DFA, like
+    /// [`Nfa::make_deterministic`](struct.Nfa.html#method.make_deterministic).
+    pub fn make_deterministic(&self, nfa: Nfa<Sanitary>) -> Result<Dfa, NfaError> {
+        nfa.determinise(self, None)
+    }
+
+    /// Converts an NFA into an equivalent DFA, like
+    /// [`Nfa::make_deterministic_traced`](struct.Nfa.html#method.make_deterministic_traced).
+    pub fn make_deterministic_traced(
+        &self,
+        nfa: Nfa<Sanitary>,
+    ) -> Result<(Dfa, Vec<SubsetStep>), NfaError> {
+        let mut trace = Vec::new();
+        let dfa = nfa.determinise(self, Some(&mut trace))?;
+        Ok((dfa, trace))
+    }
+
+    /// Minimises a DFA, like [`Dfa::minimise`](struct.Dfa.html#method.minimise).
+    pub fn minimise(&self, dfa: Dfa) -> Result<Dfa, NfaError> {
+        dfa.reduce(self, false).map(|(dfa, _)| dfa)
+    }
+
+    /// Minimises a DFA, like [`Dfa::minimise_explained`](struct.Dfa.html#method.minimise_explained).
+    pub fn minimise_explained(&self, dfa: Dfa) -> Result<(Dfa, MinimisationExplanation), NfaError> {
+        dfa.reduce(self, true)
+            .map(|(dfa, explanation)| (dfa, explanation.unwrap()))
+    }
+
+    /// Reports the Myhill–Nerode equivalence classes of a DFA, like
+    /// [`Dfa::nerode_classes`](struct.Dfa.html#method.nerode_classes).
+    pub fn nerode_classes(&self, dfa: &Dfa) -> Result<NerodeReport, NfaError> {
+        Limits::ensure(self.max_dfa_states, dfa.nodes.len(), Limit::DfaStates)?;
+        Limits::ensure(self.max_explained_states, dfa.nodes.len(), Limit::ExplainedStates)?;
+        Limits::ensure(self.max_alphabet_size, dfa.alphabet.len(), Limit::AlphabetSize)?;
+        dfa.nerode(&Budget::start(self))
+    }
+
+    /// Simulates a PDA over a word, like [`Pda::simulate`](struct.Pda.html#method.simulate).
+    pub fn simulate_pda<'a, I>(
+        &self,
+        pda: &Pda<Sanitary>,
+        word: I,
+        max_steps: usize,
+    ) -> Result<PdaRun, NfaError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        pda.search(word, max_steps, self.max_stack_size, &Budget::start(self))
+    }
+
+    /// Runs a Turing machine over a word, like
+    /// [`TuringMachine::run`](struct.TuringMachine.html#method.run).
+    pub fn run_turing<'a, I>(
+        &self,
+        machine: &TuringMachine<Sanitary>,
+        word: I,
+        max_steps: usize,
+    ) -> Result<TuringRun, NfaError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        machine.execute(word, max_steps, self.max_tape_length, &Budget::start(self))
+    }
+
+    /// Grades a DFA against a reference, like [`Grader::grade`](struct.Grader.html#method.grade).
+    pub fn grade(
+        &self,
+        grader: &Grader,
+        reference: &Dfa,
+        student: &Dfa,
+    ) -> Result<Grade, NfaError> {
+        grader.compare(reference, student, &Budget::start(self))
+    }
+
+    /// Ensures that a count is within an optional bound.
+    pub(crate) fn ensure<F>(bound: Option<usize>, count: usize, limit: F) -> Result<(), NfaError>
+    where
+        F: FnOnce(usize) -> Limit,
+    {
+        match bound {
+            Some(max) if count > max => Err(NfaError::LimitExceeded(limit(max))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Keeps track of how much of the time limit is left while working.
+pub(crate) struct Budget {
+    deadline: Option<(Instant, Duration)>,
+}
+
+impl Budget {
+    /// Starts the clock on the time limit, if there is one.
+    pub(crate) fn start(limits: &Limits) -> Self {
+        Budget {
+            deadline: limits.time.map(|time| (Instant::now() + time, time)),
+        }
+    }
+
+    /// A budget that never runs out.
+    pub(crate) fn unlimited() -> Self {
+        Budget { deadline: None }
+    }
+
+    /// Ensures that there is still time left.
+    pub(crate) fn check(&self) -> Result<(), NfaError> {
+        match self.deadline {
+            Some((deadline, time)) if Instant::now() > deadline => {
+                Err(NfaError::LimitExceeded(Limit::Time(time)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+use builder::NfaBuilder;
+
+#[test]
+fn limit_subset_construction() {
+    // the classic "nth symbol from the end is an a" NFA, whose DFA has 2^n states
+    let n = 10;
+    let mut builder = NfaBuilder::new()
+        .start("0")
+        .transition("0", "a", "0")
+        .transition("0", "b", "0")
+        .transition("0", "a", "1")
+        .final_state(n.to_string());
+    for i in 1..n {
+        builder = builder
+            .transition(i.to_string(), "a", (i + 1).to_string())
+            .transition(i.to_string(), "b", (i + 1).to_string());
+    }
+
+    let limits = Limits {
+        max_dfa_states: Some(100),
+        ..Limits::default()
+    };
+    match limits
+        .make_deterministic(builder.check().unwrap())
+        .unwrap_err()
+    {
+        NfaError::LimitExceeded(limit) => assert_eq!(limit, Limit::DfaStates(100)),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn limit_input_size() {
+    let nfa = || {
+        NfaBuilder::new()
+            .start("1")
+            .transition("1", "a", "2")
+            .transition("2", "b", "3")
+            .final_state("3")
+            .check()
+            .unwrap()
+    };
+
+    let limits = Limits {
+        max_nfa_states: Some(2),
+        ..Limits::default()
+    };
+    match limits.make_deterministic(nfa()).unwrap_err() {
+        NfaError::LimitExceeded(limit) => assert_eq!(limit, Limit::NfaStates(2)),
+        err @ _ => panic!(err),
+    }
+
+    let limits = Limits {
+        max_alphabet_size: Some(1),
+        ..Limits::default()
+    };
+    match limits.make_deterministic(nfa()).unwrap_err() {
+        NfaError::LimitExceeded(limit) => assert_eq!(limit, Limit::AlphabetSize(1)),
+        err @ _ => panic!(err),
+    }
+
+    // the DFA has a dead state as well as the original three
+    let limits = Limits {
+        max_dfa_states: Some(3),
+        ..Limits::default()
+    };
+    match limits.minimise(nfa().make_deterministic()).unwrap_err() {
+        NfaError::LimitExceeded(limit) => assert_eq!(limit, Limit::DfaStates(3)),
+        err @ _ => panic!(err),
+    }
+
+    let limits = Limits {
+        max_dfa_states: Some(4),
+        ..Limits::default()
+    };
+    let dfa = limits.make_deterministic(nfa()).unwrap();
+    assert_eq!(limits.minimise(dfa).unwrap().states().count(), 4);
+}
+
+#[test]
+fn limit_time() {
+    let limits = Limits {
+        time: Some(Duration::from_secs(0)),
+        ..Limits::default()
+    };
+    let budget = Budget::start(&limits);
+    ::std::thread::sleep(Duration::from_millis(1));
+    match budget.check().unwrap_err() {
+        NfaError::LimitExceeded(limit) => assert_eq!(limit, Limit::Time(Duration::from_secs(0))),
+        err @ _ => panic!(err),
+    }
+    assert!(Budget::unlimited().check().is_ok());
+}
+
+#[test]
+fn limit_minimisation() {
+    // the DFA for "the 13th symbol from the end is an a" is already minimal, with 2^13 states
+    let n = 13;
+    let mut builder = NfaBuilder::new()
+        .start("0")
+        .transition("0", "a", "0")
+        .transition("0", "b", "0")
+        .transition("0", "a", "1")
+        .final_state(n.to_string());
+    for i in 1..n {
+        builder = builder
+            .transition(i.to_string(), "a", (i + 1).to_string())
+            .transition(i.to_string(), "b", (i + 1).to_string());
+    }
+    let dfa = builder.check().unwrap().make_deterministic();
+
+    let limits = Limits {
+        max_explained_states: Some(1000),
+        time: Some(Duration::from_secs(5)),
+        ..Limits::default()
+    };
+    match limits.minimise_explained(dfa.clone()).unwrap_err() {
+        NfaError::LimitExceeded(limit) => assert_eq!(limit, Limit::ExplainedStates(1000)),
+        err @ _ => panic!(err),
+    }
+    match limits.nerode_classes(&dfa).unwrap_err() {
+        NfaError::LimitExceeded(limit) => assert_eq!(limit, Limit::ExplainedStates(1000)),
+        err @ _ => panic!(err),
+    }
+    assert_eq!(limits.minimise(dfa).unwrap().states().count(), 1 << n);
+
+    // a cycle with a single final state takes a round of refinement per state, so the time
+    // limit has to be checked during the rounds
+    let n = 5000;
+    let nodes = (0..n)
+        .map(|i| {
+            let transitions = vec![("a".to_owned(), ((i + 1) % n).to_string())];
+            (i.to_string(), transitions.into_iter().collect())
+        })
+        .collect();
+    let dfa = Dfa::new(
+        "0".into(),
+        vec!["0".to_owned()].into_iter().collect(),
+        vec!["a".to_owned()].into_iter().collect(),
+        nodes,
+    );
+    let limits = Limits {
+        time: Some(Duration::from_millis(100)),
+        ..Limits::default()
+    };
+    let start = Instant::now();
+    match limits.minimise(dfa).unwrap_err() {
+        NfaError::LimitExceeded(limit) => {
+            assert_eq!(limit, Limit::Time(Duration::from_millis(100)))
+        }
+        err @ _ => panic!(err),
+    }
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
@@ -0,0 +1,269 @@
+extern crate serde_json;
+extern crate state_machina;
+
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process;
+
+use state_machina::{Nfa, NfaError, Sanitary, Unsanitary};
+use state_machina::text::{self, ParseError};
+
+const USAGE: &str = "usage: state-machina <command> [--from json|text] [--to json|text] [file] [symbol...]
+
+commands:
+    convert     convert an NFA into an equivalent DFA
+    minimise    convert an NFA into the minimal equivalent DFA
+    simulate    run an NFA over the given symbols, exiting with 0 on accept and 1 on reject
+    export      rewrite an NFA in another format (text by default)
+
+The automata is read from the given file, or from stdin if the file is missing or `-`. Its
+format is taken from `--from`, the file extension, or guessed from the contents.";
+
+/// Exit code for when the program itself failed, as opposed to a word being rejected.
+const EXIT_ERROR: i32 = 2;
+
+/// The formats automata can be read and written in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    Text,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Result<Self, Error> {
+        match name {
+            "json" => Ok(Format::Json),
+            "text" => Ok(Format::Text),
+            _ => Err(Error::Usage(format!("unknown format {:?}", name))),
+        }
+    }
+}
+
+/// Everything that can go wrong while running a command.
+#[derive(Debug)]
+enum Error {
+    Usage(String),
+    Io(io::Error),
+    Json(serde_json::Error),
+    Parse(ParseError),
+    Nfa(NfaError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Usage(ref message) => write!(f, "{}", message),
+            Error::Io(ref err) => write!(f, "{}", err),
+            Error::Json(ref err) => write!(f, "invalid JSON: {}", err),
+            Error::Parse(ref err) => write!(f, "{}", err),
+            Error::Nfa(ref err) => write!(f, "invalid automata: {}", err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<NfaError> for Error {
+    fn from(err: NfaError) -> Self {
+        Error::Nfa(err)
+    }
+}
+
+/// The parsed command line.
+#[derive(Debug, Default)]
+struct Options {
+    from: Option<Format>,
+    to: Option<Format>,
+    file: Option<String>,
+    word: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, Error> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--from" | "--to" => {
+                    let format = match args.next() {
+                        Some(name) => Format::from_name(name)?,
+                        None => return Err(Error::Usage(format!("{} needs a format", arg))),
+                    };
+                    if arg == "--from" {
+                        options.from = Some(format);
+                    } else {
+                        options.to = Some(format);
+                    }
+                }
+                _ if options.file.is_none() => options.file = Some(arg.to_owned()),
+                _ => options.word.push(arg.to_owned()),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Reads and checks the input automata.
+    fn read_nfa<R: Read>(&self, stdin: &mut R) -> Result<Nfa<Sanitary>, Error> {
+        let mut input = String::new();
+        match self.file.as_ref().map(|file| file.as_str()) {
+            None | Some("-") => stdin.read_to_string(&mut input)?,
+            Some(file) => File::open(file)?.read_to_string(&mut input)?,
+        };
+
+        let from = self.from.unwrap_or_else(|| match self.file {
+            Some(ref file) if file.ends_with(".json") => Format::Json,
+            Some(ref file) if file.ends_with(".txt") => Format::Text,
+            _ if input.trim_start().starts_with('{') => Format::Json,
+            _ => Format::Text,
+        });
+        let nfa: Nfa<Unsanitary> = match from {
+            Format::Json => serde_json::from_str(&input)?,
+            Format::Text => text::parse_nfa(&input)?,
+        };
+
+        Ok(nfa.check()?)
+    }
+}
+
+/// Runs a command, returning the exit code on success.
+fn run<R: Read, W: Write>(args: &[String], stdin: &mut R, stdout: &mut W) -> Result<i32, Error> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Err(Error::Usage("no command given".into())),
+    };
+    let options = Options::parse(rest)?;
+    if command != "simulate" && !options.word.is_empty() {
+        return Err(Error::Usage(format!("unexpected argument {:?}", options.word[0])));
+    }
+
+    match command {
+        "convert" | "minimise" => {
+            let mut dfa = options.read_nfa(stdin)?.make_deterministic();
+            if command == "minimise" {
+                dfa = dfa.minimise();
+            }
+            match options.to.unwrap_or(Format::Json) {
+                Format::Json => writeln!(stdout, "{}", serde_json::to_string_pretty(&dfa)?)?,
+                Format::Text => write!(stdout, "{}", dfa)?,
+            }
+        }
+        "export" => {
+            let nfa = options.read_nfa(stdin)?;
+            match options.to.unwrap_or(Format::Text) {
+                Format::Json => writeln!(stdout, "{}", serde_json::to_string_pretty(&nfa)?)?,
+                Format::Text => write!(stdout, "{}", nfa)?,
+            }
+        }
+        "simulate" => {
+            let nfa = options.read_nfa(stdin)?;
+            let accepted = nfa.accepts(options.word.iter().map(|x| x.as_str()))?;
+            writeln!(stdout, "{}", if accepted { "accept" } else { "reject" })?;
+            return Ok(if accepted { 0 } else { 1 });
+        }
+        _ => return Err(Error::Usage(format!("unknown command {:?}", command))),
+    }
+
+    Ok(0)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    let code = match run(&args, &mut stdin.lock(), &mut stdout.lock()) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("state-machina: {}", err);
+            if let Error::Usage(_) = err {
+                eprintln!("\n{}", USAGE);
+            }
+            EXIT_ERROR
+        }
+    };
+    process::exit(code);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{run, Error};
+
+    use state_machina::{Dfa, NfaError};
+    use serde_json;
+
+    const NFA: &str = "start: 1
+        final: 3
+        1 -a-> 1, 2
+        1 -b-> 1
+        2 -a-> 3
+        2 -b-> 3";
+
+    fn run_with(args: &[&str], input: &str) -> Result<(i32, String), Error> {
+        let args: Vec<String> = args.iter().map(|&x| x.to_owned()).collect();
+        let mut output = Vec::new();
+        let code = run(&args, &mut input.as_bytes(), &mut output)?;
+        Ok((code, String::from_utf8(output).unwrap()))
+    }
+
+    #[test]
+    fn test_minimise() {
+        let (code, output) = run_with(&["minimise"], NFA).unwrap();
+        assert_eq!(code, 0);
+        let dfa: Dfa = serde_json::from_str(&output).unwrap();
+        assert!(dfa.accepts(vec!["b", "a", "b"]).unwrap());
+
+        let (code, output) = run_with(&["minimise", "--to", "text"], NFA).unwrap();
+        assert_eq!(code, 0);
+        assert!(output.starts_with("start: "));
+    }
+
+    #[test]
+    fn test_simulate() {
+        assert_eq!(
+            run_with(&["simulate", "-", "a", "a"], NFA).unwrap(),
+            (0, "accept\n".into())
+        );
+        assert_eq!(
+            run_with(&["simulate", "-", "a"], NFA).unwrap(),
+            (1, "reject\n".into())
+        );
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let (_, json) = run_with(&["export", "--to", "json"], NFA).unwrap();
+        let (_, text) = run_with(&["export"], &json).unwrap();
+        let (_, again) = run_with(&["export", "--from", "text"], &text).unwrap();
+        assert_eq!(text, again);
+    }
+
+    #[test]
+    fn test_errors() {
+        match run_with(&["convert"], "start: 4\n1 -a-> 2").unwrap_err() {
+            Error::Nfa(NfaError::UnknownState(state)) => assert_eq!(state, "4"),
+            err @ _ => panic!("{}", err),
+        }
+        match run_with(&["frobnicate"], NFA).unwrap_err() {
+            Error::Usage(_) => {}
+            err @ _ => panic!("{}", err),
+        }
+    }
+}
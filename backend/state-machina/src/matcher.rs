@@ -0,0 +1,641 @@
+//! Searching text with a DFA, compiled into a table of transitions on bytes.
+
+use std::char;
+use std::collections::{HashMap, VecDeque};
+use std::iter;
+
+use itertools::Itertools;
+
+use automata::{Dfa, NfaError};
+use symbols;
+
+/// The state that every missing transition leads to, and that never leaves itself.
+const DEAD: usize = 0;
+
+/// A DFA compiled for matching text.
+///
+/// Every symbol of the DFA must be a single character, or a [class](symbols/index.html) of them.
+/// Characters are matched in their UTF-8 encoding, so a `&str` can be searched directly, and
+/// ASCII characters take a single byte. The transitions are a dense table with a row per state,
+/// and bytes that behave the same in every state share a column, so that each step costs one
+/// table lookup.
+///
+/// Searching for the leftmost-longest match has to try each starting position in turn, and a
+/// scan from one can run on well past where its match ends. Every pair of a state and a
+/// position that a scan passes through without finding a match is remembered, so no search ever
+/// goes through it twice, and searching takes a step per byte for each state of the table at
+/// worst. Remembering them takes a bit for each state and each position from the start of the
+/// current scan to the furthest any scan has reached, which is only as far as the matches and
+/// near misses are long.
+///
+/// ```
+/// use state_machina::{DfaBuilder, Matcher};
+///
+/// // numbers without leading zeros
+/// let dfa = DfaBuilder::new()
+///     .start("start")
+///     .symbol("[:digit:]")
+///     .transition("start", "0", "zero")
+///     .transition("start", "[1-9]", "number")
+///     .transition("number", "[:digit:]", "number")
+///     .final_state("zero")
+///     .final_state("number")
+///     .check()
+///     .unwrap();
+/// let matcher = Matcher::new(&dfa).unwrap();
+/// assert!(matcher.is_match("1984"));
+/// assert!(!matcher.is_match("0123"));
+///
+/// let text = "route 66, exit 0";
+/// let numbers: Vec<&str> = matcher
+///     .find_iter(text)
+///     .map(|found| &text[found.start..found.end])
+///     .collect();
+/// assert_eq!(numbers, vec!["66", "0"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    /// The column of the table for each byte.
+    classes: Vec<u8>,
+
+    /// The number of columns in the table.
+    stride: usize,
+
+    /// The next state for each state and column, a row at a time.
+    table: Vec<usize>,
+
+    /// What each state accepts, if it is final. For a lexer this is the token.
+    accepting: Vec<Option<usize>>,
+
+    /// The start state.
+    start: usize,
+}
+
+/// Where a match was found, as byte offsets into the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Match {
+    /// The offset of the first byte of the match.
+    pub start: usize,
+
+    /// The offset just past the last byte of the match.
+    pub end: usize,
+}
+
+impl Matcher {
+    /// Compiles a DFA. Symbols longer than a single character cannot be matched, and are an
+    /// `InvalidSymbol` error.
+    pub fn new(dfa: &Dfa) -> Result<Matcher, NfaError> {
+        Matcher::compile(dfa, |state| if dfa.final_states.contains(state) {
+            Some(0)
+        } else {
+            None
+        })
+    }
+
+    /// Compiles a DFA, with what each state accepts.
+    pub(crate) fn compile<F>(dfa: &Dfa, accepts: F) -> Result<Matcher, NfaError>
+    where
+        F: Fn(&str) -> Option<usize>,
+    {
+        let atoms = symbols::minterms(
+            dfa.alphabet
+                .iter()
+                .chain(dfa.nodes.values().flat_map(|transitions| transitions.keys())),
+        );
+        if let Some(&(ref label, _)) = atoms.iter().find(|&&(_, ref set)| !set.words().is_empty())
+        {
+            return Err(NfaError::InvalidSymbol(label.to_owned()));
+        }
+
+        // the dead state comes first, followed by the states of the DFA in sorted order
+        let names: Vec<&String> = dfa.nodes.keys().sorted();
+        let index: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i + 1))
+            .collect();
+        let find = |state: &String| match index.get(state.as_str()) {
+            Some(&i) => Ok(i),
+            None => Err(NfaError::UnknownState(state.to_owned())),
+        };
+        let start = find(&dfa.start)?;
+        let mut accepting: Vec<Option<usize>> = iter::once(None)
+            .chain(names.iter().map(|name| accepts(name)))
+            .collect();
+
+        let mut rows = vec![[DEAD; 256]; accepting.len()];
+        for &name in &names {
+            let transitions = &dfa.nodes[name];
+            for &(_, ref set) in &atoms {
+                let representative = set.representative().unwrap();
+                let target = match symbols::follow(transitions, &representative).pop() {
+                    Some(target) => find(target)?,
+                    None => continue,
+                };
+                for &(low, high) in set.chars() {
+                    for sequence in utf8_sequences(low as u32, high as u32) {
+                        let from = index[name.as_str()];
+                        add_sequence(&mut rows, &mut accepting, from, &sequence, target);
+                    }
+                }
+            }
+        }
+
+        // nothing can be matched after reaching a state that cannot reach a final state, such as
+        // the dead state of a complete DFA, so a scan may as well stop there
+        let mut sources = vec![Vec::new(); rows.len()];
+        for (state, row) in rows.iter().enumerate() {
+            for &next in row.iter() {
+                sources[next].push(state);
+            }
+        }
+        let mut live: Vec<bool> = accepting.iter().map(|accepts| accepts.is_some()).collect();
+        let mut work: Vec<usize> = (0..rows.len()).filter(|&state| live[state]).collect();
+        while let Some(state) = work.pop() {
+            for &source in &sources[state] {
+                if !live[source] {
+                    live[source] = true;
+                    work.push(source);
+                }
+            }
+        }
+        for row in &mut rows {
+            for next in row.iter_mut() {
+                if !live[*next] {
+                    *next = DEAD;
+                }
+            }
+        }
+
+        // bytes with the same transitions out of every state share a column
+        let mut columns: HashMap<Vec<usize>, u8> = HashMap::new();
+        let mut classes = Vec::with_capacity(256);
+        for byte in 0..256 {
+            let column: Vec<usize> = rows.iter().map(|row| row[byte]).collect();
+            let next = columns.len() as u8;
+            classes.push(*columns.entry(column).or_insert(next));
+        }
+        let stride = columns.len();
+        let mut table = vec![DEAD; rows.len() * stride];
+        for (state, row) in rows.iter().enumerate() {
+            for (byte, &class) in classes.iter().enumerate() {
+                table[state * stride + class as usize] = row[byte];
+            }
+        }
+
+        Ok(Matcher {
+            classes,
+            stride,
+            table,
+            accepting,
+            start,
+        })
+    }
+
+    /// Whether the whole of the input is a word the DFA accepts, with a symbol per character,
+    /// like [`Dfa::accepts`](struct.Dfa.html#method.accepts). Use [`find`](#method.find) to
+    /// search for matches within the input.
+    pub fn is_match<B: AsRef<[u8]> + ?Sized>(&self, haystack: &B) -> bool {
+        let mut state = self.start;
+        for &byte in haystack.as_ref() {
+            state = self.next(state, byte);
+            if state == DEAD {
+                return false;
+            }
+        }
+        self.accepting[state].is_some()
+    }
+
+    /// Finds the leftmost match in the input, and the longest of the matches starting there.
+    pub fn find<B: AsRef<[u8]> + ?Sized>(&self, haystack: &B) -> Option<Match> {
+        let haystack = haystack.as_ref();
+        self.find_at(haystack, 0, &mut Failures::new(self))
+    }
+
+    /// Iterates over the leftmost-longest matches that do not overlap, in order. An empty match
+    /// right after another match is skipped.
+    pub fn find_iter<'m, 'h, B>(&'m self, haystack: &'h B) -> Matches<'m, 'h>
+    where
+        B: AsRef<[u8]> + ?Sized,
+    {
+        Matches {
+            matcher: self,
+            haystack: haystack.as_ref(),
+            position: 0,
+            last_end: None,
+            failures: Failures::new(self),
+        }
+    }
+
+    fn find_at(&self, haystack: &[u8], from: usize, failures: &mut Failures) -> Option<Match> {
+        (from..haystack.len() + 1)
+            // matches start on a character, not part way through one
+            .filter(|&start| start == haystack.len() || !is_continuation(haystack[start]))
+            .filter_map(|start| {
+                failures.forget_before(start);
+                self.longest(haystack, start, failures)
+                    .map(|(end, _)| Match { start, end })
+            })
+            .next()
+    }
+
+    /// The end of the longest match that starts at a position, if there is one, along with what
+    /// its final state accepts. Everything the scan passes through after its last final state is
+    /// added to the failures, and the scan stops as soon as it reaches one of them.
+    pub(crate) fn longest(
+        &self,
+        haystack: &[u8],
+        start: usize,
+        failures: &mut Failures,
+    ) -> Option<(usize, usize)> {
+        let (mut state, mut position) = (self.start, start);
+        let mut last = None;
+        // the first position not to remember as a failure
+        let stop = loop {
+            if failures.contains(state, position) {
+                break position;
+            }
+            if let Some(accepted) = self.accepting[state] {
+                last = Some((state, position, accepted));
+            }
+            if position == haystack.len() {
+                break position + 1;
+            }
+            state = self.next(state, haystack[position]);
+            position += 1;
+            if state == DEAD {
+                break position;
+            }
+        };
+
+        // go over the scan again from its last final state, which is not a failure itself
+        let (mut state, mut position, mut skip) = match last {
+            Some((state, position, _)) => (state, position, true),
+            None => (self.start, start, false),
+        };
+        while position < stop {
+            if !skip {
+                failures.insert(state, position);
+            }
+            skip = false;
+            if position + 1 < stop {
+                state = self.next(state, haystack[position]);
+            }
+            position += 1;
+        }
+
+        last.map(|(_, end, accepted)| (end, accepted))
+    }
+
+    #[inline]
+    fn next(&self, state: usize, byte: u8) -> usize {
+        self.table[state * self.stride + self.classes[byte as usize] as usize]
+    }
+}
+
+/// The pairs of a state and a position in some input from which no match can be finished, as a
+/// row of bits for each position. Scans only ever start further on in the input, so the rows
+/// before the start of the current scan are forgotten, and only the rows from there to the
+/// furthest any scan has reached are kept.
+#[derive(Debug)]
+pub(crate) struct Failures {
+    /// The number of words in each row.
+    pub(crate) width: usize,
+
+    /// The position of the first row that is kept.
+    base: usize,
+
+    /// The rows from the base on, one after the other.
+    pub(crate) bits: VecDeque<u64>,
+}
+
+impl Failures {
+    /// Creates an empty set of failures for searching some input.
+    pub(crate) fn new(matcher: &Matcher) -> Self {
+        Failures {
+            width: (matcher.accepting.len() + 63) / 64,
+            base: 0,
+            bits: VecDeque::new(),
+        }
+    }
+
+    /// Forgets the failures before a position, once no scan will start before it again.
+    pub(crate) fn forget_before(&mut self, position: usize) {
+        while self.base < position && !self.bits.is_empty() {
+            self.bits.drain(..self.width);
+            self.base += 1;
+        }
+        self.base = self.base.max(position);
+    }
+
+    #[inline]
+    fn contains(&self, state: usize, position: usize) -> bool {
+        position >= self.base &&
+            self.bits
+                .get((position - self.base) * self.width + state / 64)
+                .map_or(false, |word| word & (1 << (state % 64)) != 0)
+    }
+
+    fn insert(&mut self, state: usize, position: usize) {
+        if position < self.base {
+            return;
+        }
+        let word = (position - self.base) * self.width + state / 64;
+        if self.bits.len() <= word {
+            let len = word - word % self.width + self.width;
+            self.bits.resize(len, 0);
+        }
+        self.bits[word] |= 1 << (state % 64);
+    }
+}
+
+/// An iterator over the matches in some input, created by
+/// [`Matcher::find_iter`](struct.Matcher.html#method.find_iter).
+#[derive(Debug)]
+pub struct Matches<'m, 'h> {
+    matcher: &'m Matcher,
+    haystack: &'h [u8],
+    position: usize,
+    last_end: Option<usize>,
+    failures: Failures,
+}
+
+impl<'m, 'h> Iterator for Matches<'m, 'h> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        while self.position <= self.haystack.len() {
+            let found = match self.matcher
+                .find_at(self.haystack, self.position, &mut self.failures)
+            {
+                Some(found) => found,
+                None => break,
+            };
+            if found.start == found.end {
+                // an empty match would otherwise be found again
+                self.position = found.end + 1;
+                if self.last_end == Some(found.end) {
+                    continue;
+                }
+            } else {
+                self.position = found.end;
+            }
+            self.last_end = Some(found.end);
+            return Some(found);
+        }
+        self.position = self.haystack.len() + 1;
+        None
+    }
+}
+
+/// Whether a byte continues a UTF-8 encoded character, rather than starting one.
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+/// Adds the transitions for a sequence of byte ranges, through states of their own for all but
+/// the last byte.
+fn add_sequence(
+    rows: &mut Vec<[usize; 256]>,
+    accepting: &mut Vec<Option<usize>>,
+    mut state: usize,
+    sequence: &[(u8, u8)],
+    target: usize,
+) {
+    let (&(low, high), prefix) = sequence.split_last().unwrap();
+    for &(low, high) in prefix {
+        // sequences for disjoint characters that start with the same byte agree on all of the
+        // bytes before the ones they differ on, so they can share states
+        let mut next = rows[state][low as usize];
+        if next == DEAD {
+            next = rows.len();
+            rows.push([DEAD; 256]);
+            accepting.push(None);
+        }
+        for slot in &mut rows[state][low as usize..high as usize + 1] {
+            *slot = next;
+        }
+        state = next;
+    }
+    for slot in &mut rows[state][low as usize..high as usize + 1] {
+        *slot = target;
+    }
+}
+
+/// Splits a range of characters into sequences of byte ranges, where each sequence matches the
+/// UTF-8 encodings of a part of the range. Surrogates are skipped, as they are not characters.
+fn utf8_sequences(low: u32, high: u32) -> Vec<Vec<(u8, u8)>> {
+    let mut sequences = Vec::new();
+    split_utf8(low, high, &mut sequences);
+    sequences
+}
+
+fn split_utf8(mut low: u32, high: u32, sequences: &mut Vec<Vec<(u8, u8)>>) {
+    if low >= 0xD800 && low <= 0xDFFF {
+        low = 0xE000;
+    }
+    if low > high {
+        return;
+    }
+
+    // the encodings must all be the same length
+    for &boundary in &[0x7F, 0x7FF, 0xD7FF, 0xFFFF] {
+        if low <= boundary && high > boundary {
+            split_utf8(low, boundary, sequences);
+            split_utf8(boundary + 1, high, sequences);
+            return;
+        }
+    }
+
+    // and every byte after the first must cover all of its values, or share a single prefix
+    for i in 1..4 {
+        let mask = (1 << (6 * i)) - 1;
+        if low & !mask != high & !mask {
+            if low & mask != 0 {
+                split_utf8(low, low | mask, sequences);
+                split_utf8((low | mask) + 1, high, sequences);
+                return;
+            }
+            if high & mask != mask {
+                split_utf8(low, (high & !mask) - 1, sequences);
+                split_utf8(high & !mask, high, sequences);
+                return;
+            }
+        }
+    }
+
+    let (mut low_bytes, mut high_bytes) = ([0; 4], [0; 4]);
+    let low_bytes = char::from_u32(low).unwrap().encode_utf8(&mut low_bytes).as_bytes();
+    let high_bytes = char::from_u32(high).unwrap().encode_utf8(&mut high_bytes).as_bytes();
+    sequences.push(
+        low_bytes
+            .iter()
+            .cloned()
+            .zip(high_bytes.iter().cloned())
+            .collect(),
+    );
+}
+
+#[cfg(test)]
+use builder::DfaBuilder;
+
+#[cfg(test)]
+use std::time::{Duration, Instant};
+
+#[test]
+fn matcher_utf8_sequences() {
+    let ranges = vec![
+        (0, 0x10FFFF),
+        (0x41, 0x5A),
+        (0x3B1, 0x3C9),
+        (0x800, 0xFFFF),
+        (0xE9, 0x1F600),
+    ];
+    for (low, high) in ranges {
+        let sequences = utf8_sequences(low, high);
+        for c in (0..0x110000 / 7).filter_map(|i| char::from_u32(i * 7)) {
+            let mut bytes = [0; 4];
+            let bytes = c.encode_utf8(&mut bytes).as_bytes();
+            let matching = sequences
+                .iter()
+                .filter(|sequence| {
+                    sequence.len() == bytes.len() &&
+                        sequence
+                            .iter()
+                            .zip(bytes)
+                            .all(|(&(low, high), &byte)| low <= byte && byte <= high)
+                })
+                .count();
+            let expected = if low <= c as u32 && c as u32 <= high {
+                1
+            } else {
+                0
+            };
+            assert_eq!(matching, expected, "{:?} in {:x}-{:x}", c, low, high);
+        }
+    }
+}
+
+#[test]
+fn matcher_find() {
+    // an "a", followed by anything but another "a", or the end
+    let dfa = DfaBuilder::new()
+        .start("start")
+        .symbol("[a-z]")
+        .symbol("[:space:]")
+        .transition("start", "a", "a")
+        .transition("a", "a", "dead")
+        .transition("a", "*", "done")
+        .final_state("a")
+        .final_state("done")
+        .check()
+        .unwrap();
+    let matcher = Matcher::new(&dfa).unwrap();
+    assert!(matcher.is_match("ab"));
+    assert!(!matcher.is_match("aa"));
+    assert!(!matcher.is_match("a!"));
+    assert_eq!(matcher.find("bbab c"), Some(Match { start: 2, end: 4 }));
+    assert_eq!(matcher.find("xyz"), None);
+
+    // the longest match is preferred, and matches do not overlap
+    let found: Vec<Match> = matcher.find_iter("aab a").collect();
+    assert_eq!(
+        found,
+        vec![
+            Match { start: 0, end: 1 },
+            Match { start: 1, end: 3 },
+            Match { start: 4, end: 5 },
+        ]
+    );
+
+    // symbols must be single characters
+    let dfa = DfaBuilder::new()
+        .start("1")
+        .transition("1", "if", "2")
+        .check()
+        .unwrap();
+    match Matcher::new(&dfa).unwrap_err() {
+        NfaError::InvalidSymbol(symbol) => assert_eq!(symbol, "if"),
+        err @ _ => panic!("{:?}", err),
+    }
+}
+
+#[test]
+fn matcher_unicode() {
+    // words of Latin or Greek letters
+    let dfa = DfaBuilder::new()
+        .start("start")
+        .symbol("[:alpha:α-ω]")
+        .symbol("[:digit: ]")
+        .transition("start", "[:alpha:α-ω]", "word")
+        .transition("word", "[:alpha:α-ω]", "word")
+        .final_state("word")
+        .check()
+        .unwrap();
+    let matcher = Matcher::new(&dfa).unwrap();
+    assert!(matcher.is_match("λογος"));
+    assert!(!matcher.is_match("λογος1"));
+
+    let text = "αβγ 12 abc";
+    let words: Vec<&str> = matcher
+        .find_iter(text)
+        .map(|found| &text[found.start..found.end])
+        .collect();
+    assert_eq!(words, vec!["αβγ", "abc"]);
+
+    // an empty match is found at every character, but not within one
+    let dfa = DfaBuilder::new()
+        .start("start")
+        .symbol("[:alpha:α-ω]")
+        .state("start")
+        .final_state("start")
+        .check()
+        .unwrap();
+    let found: Vec<usize> = Matcher::new(&dfa)
+        .unwrap()
+        .find_iter("aβ")
+        .map(|found| found.start)
+        .collect();
+    assert_eq!(found, vec![0, 1, 3]);
+}
+
+#[test]
+fn matcher_linear_search() {
+    // every scan for "a*b" runs to the end of a text of "a"s, but each only once
+    let dfa = DfaBuilder::new()
+        .start("start")
+        .transition("start", "a", "start")
+        .transition("start", "b", "done")
+        .final_state("done")
+        .check()
+        .unwrap();
+    let matcher = Matcher::new(&dfa).unwrap();
+    let text = "a".repeat(200_000);
+    let started = Instant::now();
+    assert_eq!(matcher.find_iter(&text).count(), 0);
+    assert_eq!(matcher.find(&text), None);
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    // only the failures ahead of the search are kept, so scanning a long text of short matches
+    // needs little memory
+    let text = "aab b ".repeat(100_000);
+    let mut matches = matcher.find_iter(&text);
+    let mut count = 0;
+    while let Some(_) = matches.next() {
+        assert!(matches.failures.bits.len() <= 4 * matches.failures.width);
+        count += 1;
+    }
+    assert_eq!(count, 200_000);
+    assert!(matches.failures.bits.capacity() < 64);
+
+    // and failures found by one search are still right for the matches after it
+    let text = format!("{}b{}", "a".repeat(1000), "a".repeat(1000));
+    let found: Vec<Match> = matcher.find_iter(&text).collect();
+    assert_eq!(found, vec![Match { start: 0, end: 1001 }]);
+    let text = "aab ab b aa";
+    let found: Vec<&str> = matcher
+        .find_iter(text)
+        .map(|found| &text[found.start..found.end])
+        .collect();
+    assert_eq!(found, vec!["aab", "ab", "b"]);
+}
@@ -0,0 +1,496 @@
+//! Pushdown automata, for languages that need a stack to recognise.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::iter;
+
+use automata::{NfaError, Sanitary, Unsanitary};
+use limits::Budget;
+#[cfg(test)]
+use limits::Limits;
+
+#[cfg(test)]
+use serde_json;
+
+/// The input "symbol" of transitions that do not consume any input.
+pub const EPSILON: &str = "";
+
+/// How a pushdown automata decides whether to accept a word, once all of it has been read.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Acceptance {
+    /// Accept if the automata is in a final state.
+    FinalState,
+    /// Accept if the stack is empty.
+    EmptyStack,
+}
+
+impl Default for Acceptance {
+    fn default() -> Self {
+        Acceptance::FinalState
+    }
+}
+
+/// A single transition of a pushdown automata.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct PdaTransition {
+    /// The state the transition goes to.
+    pub state: String,
+
+    /// The stack symbols that replace the top of the stack, with the new top first. Empty if the
+    /// top of the stack is just popped.
+    pub push: Vec<String>,
+}
+
+/// Nondeterministic pushdown automata.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Pda<T> {
+    /// Marker to ensure that the state machine has been validated before it is used for any
+    /// computations.
+    #[serde(skip_serializing)]
+    _sanitized: T,
+
+    /// Start state.
+    start: String,
+
+    /// The symbol on the stack when the automata starts.
+    start_stack: String,
+
+    /// How the automata decides whether to accept a word.
+    #[serde(default)]
+    accept_by: Acceptance,
+
+    /// The final (accepting) states of the automata. Only used when accepting by final state.
+    #[serde(default)]
+    final_states: HashSet<String>,
+
+    /// The alphabet of symbols the automata reads.
+    alphabet: HashSet<String>,
+
+    /// The symbols that can be on the stack.
+    stack_alphabet: HashSet<String>,
+
+    /// The nodes within the automata. Each node has mappings from input symbols (or `""` to
+    /// read nothing) to the symbol on top of the stack to sets of transitions.
+    nodes: HashMap<String, HashMap<String, HashMap<String, HashSet<PdaTransition>>>>,
+}
+
+/// A snapshot of a pushdown automata part way through reading a word.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PdaConfiguration {
+    /// The current state.
+    pub state: String,
+
+    /// The input that has not been read yet.
+    pub remaining: Vec<String>,
+
+    /// The contents of the stack, with the top first.
+    pub stack: Vec<String>,
+}
+
+/// The result of simulating a pushdown automata over a word.
+#[derive(Debug, Serialize)]
+pub struct PdaRun {
+    /// Whether an accepting configuration was found.
+    pub accepted: bool,
+
+    /// Whether the search stopped before exploring every configuration, having taken too many
+    /// steps or given up on a stack that grew too large. If so, a word that was not accepted
+    /// might still be accepted by a longer search.
+    pub exhausted: bool,
+
+    /// Every accepting configuration found, each with the configurations leading to it from the
+    /// start.
+    pub traces: Vec<Vec<PdaConfiguration>>,
+}
+
+impl<T> Pda<T> {
+    /// The start state.
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// The symbol on the stack when the automata starts.
+    pub fn start_stack(&self) -> &str {
+        &self.start_stack
+    }
+
+    /// How the automata decides whether to accept a word.
+    pub fn accept_by(&self) -> Acceptance {
+        self.accept_by
+    }
+
+    /// The final (accepting) states of the automata.
+    pub fn final_states(&self) -> &HashSet<String> {
+        &self.final_states
+    }
+
+    /// The alphabet of symbols the automata reads.
+    pub fn alphabet(&self) -> &HashSet<String> {
+        &self.alphabet
+    }
+
+    /// The symbols that can be on the stack.
+    pub fn stack_alphabet(&self) -> &HashSet<String> {
+        &self.stack_alphabet
+    }
+
+    /// The transitions from a state on an input symbol (or [`EPSILON`](constant.EPSILON.html))
+    /// with a symbol on top of the stack, if there are any.
+    pub fn transition(
+        &self,
+        state: &str,
+        input: &str,
+        top: &str,
+    ) -> Option<&HashSet<PdaTransition>> {
+        self.nodes
+            .get(state)
+            .and_then(|transitions| transitions.get(input))
+            .and_then(|transitions| transitions.get(top))
+    }
+}
+
+impl Pda<Unsanitary> {
+    /// Ensures that the PDA is valid, and that relevant invariants within the structure hold.
+    pub fn check(self) -> Result<Pda<Sanitary>, NfaError> {
+        let Pda {
+            start,
+            start_stack,
+            accept_by,
+            final_states,
+            alphabet,
+            stack_alphabet,
+            nodes,
+            ..
+        } = self;
+
+        // ensure that the start state is a valid state
+        if !nodes.contains_key(&start) {
+            return Err(NfaError::UnknownState(start));
+        }
+
+        // ensure that all final states are listed as actual states
+        if let Some(unknown_state) = final_states
+            .iter()
+            .find(|&state| !nodes.contains_key(state))
+        {
+            return Err(NfaError::UnknownState(unknown_state.to_owned()));
+        }
+
+        // the empty symbol is reserved for epsilon transitions
+        if alphabet.contains(EPSILON) || stack_alphabet.contains(EPSILON) {
+            return Err(NfaError::InvalidSymbol(EPSILON.to_owned()));
+        }
+
+        if !stack_alphabet.contains(&start_stack) {
+            return Err(NfaError::UnknownSymbol(start_stack));
+        }
+
+        for transitions in nodes.values() {
+            for (input, transitions) in transitions {
+                // ensure that all state transitions are on valid symbols
+                if input != EPSILON && !alphabet.contains(input) {
+                    return Err(NfaError::UnknownSymbol(input.to_owned()));
+                }
+
+                for (top, transitions) in transitions {
+                    // ensure that the stack only ever contains stack symbols
+                    if let Some(unknown_symbol) = iter::once(top)
+                        .chain(transitions.iter().flat_map(|transition| &transition.push))
+                        .find(|&symbol| !stack_alphabet.contains(symbol))
+                    {
+                        return Err(NfaError::UnknownSymbol(unknown_symbol.to_owned()));
+                    }
+
+                    // ensure that all state transitions are to valid states
+                    if let Some(transition) = transitions
+                        .iter()
+                        .find(|&transition| !nodes.contains_key(&transition.state))
+                    {
+                        return Err(NfaError::UnknownState(transition.state.to_owned()));
+                    }
+                }
+            }
+        }
+
+        if let Some(invalid_state) = nodes.keys().find(|state| state.is_empty()) {
+            return Err(NfaError::InvalidStateName(invalid_state.to_owned()));
+        }
+
+        Ok(Pda {
+            _sanitized: Sanitary,
+            start,
+            start_stack,
+            accept_by,
+            final_states,
+            alphabet,
+            stack_alphabet,
+            nodes,
+        })
+    }
+}
+
+impl Pda<Sanitary> {
+    /// Searches for accepting runs of the PDA over a word, breadth first so that the shortest
+    /// runs are found first. As a PDA can loop forever without reading any input, at most
+    /// `max_steps` configurations are explored. Use
+    /// [`Limits::simulate_pda`](struct.Limits.html#method.simulate_pda) to also bound the size of
+    /// the stack and the time taken.
+    pub fn simulate<'a, I>(&self, word: I, max_steps: usize) -> Result<PdaRun, NfaError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.search(word, max_steps, None, &Budget::unlimited())
+    }
+
+    /// Searches for accepting runs, giving up on any branch whose stack would grow past
+    /// `max_stack` symbols, and failing if the budget runs out.
+    pub(crate) fn search<'a, I>(
+        &self,
+        word: I,
+        max_steps: usize,
+        max_stack: Option<usize>,
+        budget: &Budget,
+    ) -> Result<PdaRun, NfaError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        /// A configuration, along with the index of the configuration it came from. The stack has
+        /// its top last.
+        struct Step<'b> {
+            state: &'b str,
+            position: usize,
+            stack: Vec<&'b str>,
+            parent: Option<usize>,
+        }
+
+        let word: Vec<_> = word.into_iter().collect();
+        if let Some(unknown_symbol) = word.iter().find(|&&symbol| !self.alphabet.contains(symbol)) {
+            return Err(NfaError::UnknownSymbol(unknown_symbol.to_string()));
+        }
+
+        let mut explored: Vec<Step> = Vec::new();
+        let mut seen = HashSet::new();
+        let mut work = VecDeque::new();
+        let mut accepting = Vec::new();
+        let mut pruned = false;
+
+        seen.insert((self.start.as_str(), 0, vec![self.start_stack.as_str()]));
+        work.push_back(Step {
+            state: &self.start,
+            position: 0,
+            stack: vec![&self.start_stack],
+            parent: None,
+        });
+
+        while explored.len() < max_steps {
+            budget.check()?;
+            let step = match work.pop_front() {
+                Some(step) => step,
+                None => break,
+            };
+            let index = explored.len();
+
+            let accepts = match self.accept_by {
+                Acceptance::FinalState => self.final_states.contains(step.state),
+                Acceptance::EmptyStack => step.stack.is_empty(),
+            };
+            if accepts && step.position == word.len() {
+                accepting.push(index);
+            }
+
+            if let Some((&top, rest)) = step.stack.split_last() {
+                let inputs = iter::once((EPSILON, step.position)).chain(
+                    word.get(step.position)
+                        .map(|&symbol| (symbol, step.position + 1)),
+                );
+                for (input, position) in inputs {
+                    let transitions = self.nodes[step.state]
+                        .get(input)
+                        .and_then(|transitions| transitions.get(top));
+                    for transition in transitions.into_iter().flat_map(|x| x) {
+                        // a branch whose stack grows too large is given up on, like one that
+                        // would take too many steps
+                        if let Some(max_stack) = max_stack {
+                            if rest.len() + transition.push.len() > max_stack {
+                                pruned = true;
+                                continue;
+                            }
+                        }
+                        let mut stack = rest.to_vec();
+                        stack.extend(transition.push.iter().rev().map(|x| x.as_str()));
+                        if seen.insert((transition.state.as_str(), position, stack.clone())) {
+                            work.push_back(Step {
+                                state: &transition.state,
+                                position,
+                                stack,
+                                parent: Some(index),
+                            });
+                        }
+                    }
+                }
+            }
+
+            explored.push(step);
+        }
+
+        let traces = accepting
+            .into_iter()
+            .map(|index| {
+                let mut trace = Vec::new();
+                let mut current = Some(index);
+                while let Some(index) = current {
+                    let step = &explored[index];
+                    trace.push(PdaConfiguration {
+                        state: step.state.to_owned(),
+                        remaining: word[step.position..].iter().map(|x| x.to_string()).collect(),
+                        stack: step.stack.iter().rev().map(|x| x.to_string()).collect(),
+                    });
+                    current = step.parent;
+                }
+                trace.reverse();
+                trace
+            })
+            .collect::<Vec<_>>();
+
+        Ok(PdaRun {
+            accepted: !traces.is_empty(),
+            exhausted: pruned || !work.is_empty(),
+            traces,
+        })
+    }
+}
+
+#[cfg(test)]
+const BALANCED: &str = r#"{
+    "start": "push",
+    "start_stack": "Z",
+    "final_states": ["done"],
+    "alphabet": ["a", "b"],
+    "stack_alphabet": ["Z", "A"],
+    "nodes": {
+        "push": {
+            "a": {
+                "Z": [{ "state": "push", "push": ["A", "Z"] }],
+                "A": [{ "state": "push", "push": ["A", "A"] }]
+            },
+            "b": {
+                "A": [{ "state": "pop", "push": [] }]
+            },
+            "": {
+                "Z": [{ "state": "done", "push": ["Z"] }]
+            }
+        },
+        "pop": {
+            "b": {
+                "A": [{ "state": "pop", "push": [] }]
+            },
+            "": {
+                "Z": [{ "state": "done", "push": ["Z"] }]
+            }
+        },
+        "done": {}
+    }
+}"#;
+
+#[test]
+fn simulate_pda_final_state() {
+    let unsanitary: Pda<_> = serde_json::from_str(BALANCED).unwrap();
+    let pda = unsanitary.check().unwrap();
+
+    let run = pda.simulate(vec!["a", "a", "b", "b"], 1000).unwrap();
+    assert!(run.accepted);
+    assert!(!run.exhausted);
+    assert_eq!(run.traces.len(), 1);
+    let trace = &run.traces[0];
+    assert_eq!(
+        trace[0],
+        PdaConfiguration {
+            state: "push".into(),
+            remaining: vec!["a".into(), "a".into(), "b".into(), "b".into()],
+            stack: vec!["Z".into()],
+        }
+    );
+    assert_eq!(
+        trace[2],
+        PdaConfiguration {
+            state: "push".into(),
+            remaining: vec!["b".into(), "b".into()],
+            stack: vec!["A".into(), "A".into(), "Z".into()],
+        }
+    );
+    assert_eq!(trace.last().unwrap().state, "done");
+    assert_eq!(trace.len(), 6);
+
+    assert!(pda.simulate(vec![], 1000).unwrap().accepted);
+    let run = pda.simulate(vec!["a", "a", "b"], 1000).unwrap();
+    assert!(!run.accepted);
+    assert!(!run.exhausted);
+
+    match pda.simulate(vec!["c"], 1000).unwrap_err() {
+        NfaError::UnknownSymbol(err) => assert_eq!(err, "c"),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn simulate_pda_empty_stack() {
+    let input = r#"{
+        "start": "1",
+        "start_stack": "Z",
+        "accept_by": "empty_stack",
+        "alphabet": ["a"],
+        "stack_alphabet": ["Z", "A"],
+        "nodes": {
+            "1": {
+                "a": {
+                    "Z": [{ "state": "1", "push": ["A", "Z"] }],
+                    "A": [{ "state": "1", "push": [] }]
+                },
+                "": {
+                    "Z": [{ "state": "1", "push": [] }],
+                    "A": [{ "state": "1", "push": ["A", "A"] }]
+                }
+            }
+        }
+    }"#;
+    let unsanitary: Pda<_> = serde_json::from_str(input).unwrap();
+    let pda = unsanitary.check().unwrap();
+
+    let run = pda.simulate(vec!["a", "a"], 1000).unwrap();
+    assert!(run.accepted);
+    assert!(run.traces[0].last().unwrap().stack.is_empty());
+
+    // the epsilon loop on A grows the stack forever, so the search has to give up
+    let run = pda.simulate(vec!["a"], 50).unwrap();
+    assert!(run.exhausted);
+
+    // or give up on the branches whose stack is too large, still finding the accepting runs
+    let limits = Limits {
+        max_stack_size: Some(10),
+        ..Limits::default()
+    };
+    let run = limits.simulate_pda(&pda, vec!["a"], 1000).unwrap();
+    assert!(!run.accepted);
+    assert!(run.exhausted);
+    let run = limits.simulate_pda(&pda, vec!["a", "a"], 1000).unwrap();
+    assert!(run.accepted);
+    assert!(run.exhausted);
+    assert!(run.traces[0].iter().all(|step| step.stack.len() <= 10));
+}
+
+#[test]
+fn invalid_pda() {
+    let input = BALANCED.replace(r#"["A", "Z"]"#, r#"["B", "Z"]"#);
+    let unsanitary: Pda<_> = serde_json::from_str(&input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::UnknownSymbol(err) => assert_eq!(err, "B"),
+        err @ _ => panic!(err),
+    }
+
+    let input = BALANCED.replace(r#""state": "pop""#, r#""state": "popped""#);
+    let unsanitary: Pda<_> = serde_json::from_str(&input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::UnknownState(err) => assert_eq!(err, "popped"),
+        err @ _ => panic!(err),
+    }
+}
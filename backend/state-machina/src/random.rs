@@ -0,0 +1,374 @@
+//! Generating random automata, for exercises and for stress testing.
+
+use std::collections::{HashMap, HashSet};
+use std::error;
+use std::fmt;
+
+use automata::{Dfa, Nfa, Sanitary};
+
+/// Settings for generating random automata. The same settings, including the seed, always
+/// generate the same automata.
+///
+/// States are named `q0`, `q1`, ..., with `q0` as the start state, and symbols are named `a`,
+/// `b`, ..., `z`, `aa`, `ab`, and so on.
+///
+/// ```
+/// use state_machina::Generator;
+///
+/// let generator = Generator {
+///     states: 8,
+///     alphabet_size: 3,
+///     seed: 42,
+///     ..Generator::default()
+/// };
+/// let nfa = generator.nfa().unwrap();
+/// assert_eq!(nfa.states().count(), 8);
+/// let dfa = generator.dfa().unwrap();
+/// assert_eq!(dfa.alphabet().len(), 3);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Generator {
+    /// The number of states.
+    pub states: usize,
+
+    /// The number of symbols in the alphabet.
+    pub alphabet_size: usize,
+
+    /// For NFAs, the chance of each possible transition (from a state, on a symbol, to a state)
+    /// being present. DFAs always have exactly one transition per state and symbol, so this is
+    /// not used for them.
+    pub density: f64,
+
+    /// The fraction of states that are final, rounded to the nearest state.
+    pub final_ratio: f64,
+
+    /// Whether every state must be reachable from the start state.
+    pub reachable: bool,
+
+    /// The seed for the random number generator.
+    pub seed: u64,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Generator {
+            states: 5,
+            alphabet_size: 2,
+            density: 0.2,
+            final_ratio: 0.3,
+            reachable: true,
+            seed: 0,
+        }
+    }
+}
+
+/// Settings that no automata can be generated from.
+#[derive(Debug, PartialEq)]
+pub enum GeneratorError {
+    /// Automata need at least a start state.
+    NoStates,
+    /// A setting that should be between 0 and 1 is not.
+    InvalidRatio(&'static str, f64),
+    /// There are several states, but no symbols to reach them with.
+    Unreachable,
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GeneratorError::NoStates => write!(f, "an automaton needs at least one state"),
+            GeneratorError::InvalidRatio(setting, value) => {
+                write!(f, "{} must be between 0 and 1, not {}", setting, value)
+            }
+            GeneratorError::Unreachable => write!(
+                f,
+                "states cannot be made reachable from the start state without any symbols"
+            ),
+        }
+    }
+}
+
+impl error::Error for GeneratorError {
+    fn description(&self) -> &str {
+        "invalid generator settings"
+    }
+}
+
+impl Generator {
+    /// Generates a random NFA.
+    pub fn nfa(&self) -> Result<Nfa<Sanitary>, GeneratorError> {
+        self.validate()?;
+        let mut rng = Rng::new(self.seed);
+
+        let mut transitions = vec![vec![HashSet::new(); self.alphabet_size]; self.states];
+        if self.reachable {
+            for (from, symbol, to) in spanning_tree(&mut rng, self.states, self.alphabet_size) {
+                transitions[from][symbol].insert(to);
+            }
+        }
+        for targets in transitions.iter_mut().flat_map(|x| x.iter_mut()) {
+            for to in 0..self.states {
+                if rng.chance(self.density) {
+                    targets.insert(to);
+                }
+            }
+        }
+
+        Ok(Nfa {
+            _sanitized: Sanitary,
+            start: state_name(0),
+            final_states: self.final_states(&mut rng),
+            alphabet: (0..self.alphabet_size).map(symbol_name).collect(),
+            nodes: transitions
+                .into_iter()
+                .enumerate()
+                .map(|(from, transitions)| {
+                    let transitions = transitions
+                        .into_iter()
+                        .enumerate()
+                        .filter(|&(_, ref targets)| !targets.is_empty())
+                        .map(|(symbol, targets)| {
+                            (
+                                symbol_name(symbol),
+                                targets.into_iter().map(state_name).collect(),
+                            )
+                        })
+                        .collect();
+                    (state_name(from), transitions)
+                })
+                .collect(),
+        })
+    }
+
+    /// Generates a random DFA, with a transition on every symbol from every state.
+    pub fn dfa(&self) -> Result<Dfa, GeneratorError> {
+        self.validate()?;
+        let mut rng = Rng::new(self.seed);
+
+        let mut transitions = vec![vec![None; self.alphabet_size]; self.states];
+        if self.reachable {
+            for (from, symbol, to) in spanning_tree(&mut rng, self.states, self.alphabet_size) {
+                transitions[from][symbol] = Some(to);
+            }
+        }
+        for target in transitions.iter_mut().flat_map(|x| x.iter_mut()) {
+            if target.is_none() {
+                *target = Some(rng.below(self.states));
+            }
+        }
+
+        let final_states = self.final_states(&mut rng);
+        let nodes: HashMap<_, HashMap<_, _>> = transitions
+            .into_iter()
+            .enumerate()
+            .map(|(from, transitions)| {
+                let transitions = transitions
+                    .into_iter()
+                    .enumerate()
+                    .map(|(symbol, to)| (symbol_name(symbol), state_name(to.unwrap())))
+                    .collect();
+                (state_name(from), transitions)
+            })
+            .collect();
+        Ok(Dfa::new(
+            state_name(0),
+            final_states,
+            (0..self.alphabet_size).map(symbol_name).collect(),
+            nodes,
+        ))
+    }
+
+    fn validate(&self) -> Result<(), GeneratorError> {
+        if self.states == 0 {
+            return Err(GeneratorError::NoStates);
+        }
+        for &(setting, value) in &[("density", self.density), ("final_ratio", self.final_ratio)] {
+            if !(value >= 0.0 && value <= 1.0) {
+                return Err(GeneratorError::InvalidRatio(setting, value));
+            }
+        }
+        if self.reachable && self.states > 1 && self.alphabet_size == 0 {
+            return Err(GeneratorError::Unreachable);
+        }
+        Ok(())
+    }
+
+    /// Picks which states are final.
+    fn final_states(&self, rng: &mut Rng) -> HashSet<String> {
+        let count = (self.final_ratio * self.states as f64).round() as usize;
+        let mut states: Vec<usize> = (0..self.states).collect();
+        // a partial Fisher-Yates shuffle, which puts a random selection of states at the front
+        for i in 0..count {
+            let j = i + rng.below(self.states - i);
+            states.swap(i, j);
+        }
+        states.into_iter().take(count).map(state_name).collect()
+    }
+}
+
+/// Transitions that make every state reachable from the first, with at most one transition on
+/// each symbol out of a state, so that they can also be used for DFAs.
+///
+/// Each state after the first is reached from a random earlier state, on a random symbol that
+/// state has not used yet. The most recently reached state has not used any symbols, so there is
+/// always one to choose from.
+fn spanning_tree(rng: &mut Rng, states: usize, alphabet_size: usize) -> Vec<(usize, usize, usize)> {
+    let mut unused: Vec<(usize, usize)> = (0..alphabet_size).map(|symbol| (0, symbol)).collect();
+    let mut tree = Vec::new();
+    for to in 1..states {
+        let (from, symbol) = unused.swap_remove(rng.below(unused.len()));
+        tree.push((from, symbol, to));
+        unused.extend((0..alphabet_size).map(|symbol| (to, symbol)));
+    }
+    tree
+}
+
+fn state_name(state: usize) -> String {
+    format!("q{}", state)
+}
+
+/// Names symbols like spreadsheet columns: `a` to `z`, then `aa`, `ab`, and so on.
+fn symbol_name(mut symbol: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'a' + (symbol % 26) as u8);
+        if symbol < 26 {
+            break;
+        }
+        symbol = symbol / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+/// A small, fast random number generator (SplitMix64). It is part of the crate, rather than a
+/// dependency, so that a seed keeps generating the same automata.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A random number below `n`, which must not be 0.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// True with probability `p`.
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+#[test]
+fn random_symbol_names() {
+    let names: Vec<_> = [0, 1, 25, 26, 27, 51, 52, 701, 702]
+        .iter()
+        .map(|&symbol| symbol_name(symbol))
+        .collect();
+    assert_eq!(names, vec!["a", "b", "z", "aa", "ab", "az", "ba", "zz", "aaa"]);
+}
+
+#[test]
+fn random_nfa() {
+    let generator = Generator {
+        states: 20,
+        alphabet_size: 3,
+        density: 0.0,
+        final_ratio: 0.25,
+        reachable: true,
+        seed: 7,
+    };
+    let nfa = generator.nfa().unwrap();
+    assert_eq!(nfa.states().count(), 20);
+    assert_eq!(nfa.alphabet().len(), 3);
+    assert_eq!(nfa.final_states().len(), 5);
+    assert_eq!(nfa.start(), "q0");
+
+    // the spanning tree alone reaches every state
+    let mut reached = HashSet::new();
+    let mut work = vec!["q0".to_string()];
+    while let Some(state) = work.pop() {
+        if reached.insert(state.clone()) {
+            for targets in nfa.transitions(&state).unwrap().values() {
+                work.extend(targets.iter().cloned());
+            }
+        }
+    }
+    assert_eq!(reached.len(), 20);
+
+    // the same seed generates the same NFA, and another seed generates a different one
+    let again = generator.nfa().unwrap();
+    assert_eq!(again.nodes, nfa.nodes);
+    assert_eq!(again.final_states, nfa.final_states);
+    let other = Generator { seed: 8, ..generator }.nfa().unwrap();
+    assert!(other.nodes != nfa.nodes);
+
+    // every transition is present at full density
+    let nfa = Generator {
+        density: 1.0,
+        ..generator
+    }.nfa()
+        .unwrap();
+    assert!(nfa.states().all(|state| {
+        nfa.transitions(state)
+            .unwrap()
+            .values()
+            .all(|targets| targets.len() == 20)
+    }));
+}
+
+#[test]
+fn random_dfa() {
+    let generator = Generator {
+        states: 30,
+        alphabet_size: 2,
+        final_ratio: 0.5,
+        seed: 1234,
+        ..Generator::default()
+    };
+    let dfa = generator.dfa().unwrap();
+    assert_eq!(dfa.nodes.len(), 30);
+    assert_eq!(dfa.final_states.len(), 15);
+    assert!(dfa.nodes.values().all(|transitions| transitions.len() == 2));
+    assert_eq!(dfa.nodes, generator.dfa().unwrap().nodes);
+
+    let minimised = dfa.minimise();
+    assert!(minimised.nodes.len() <= 30);
+
+    assert_eq!(
+        Generator {
+            states: 0,
+            ..generator
+        }.dfa()
+            .unwrap_err(),
+        GeneratorError::NoStates
+    );
+    assert_eq!(
+        Generator {
+            density: 1.5,
+            ..generator
+        }.nfa()
+            .unwrap_err()
+            .to_string(),
+        "density must be between 0 and 1, not 1.5"
+    );
+    assert_eq!(
+        Generator {
+            alphabet_size: 0,
+            ..generator
+        }.dfa()
+            .unwrap_err(),
+        GeneratorError::Unreachable
+    );
+}
@@ -0,0 +1,283 @@
+//! Parsing regular expressions into NFAs, for describing the tokens of a lexer. The syntax is
+//! described with [`LexerBuilder::regex`](../struct.LexerBuilder.html#method.regex).
+
+use std::collections::BTreeSet;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use automata::{Nfa, Sanitary};
+use builder::NfaBuilder;
+use symbols::{self, Label};
+
+/// Where a regular expression is invalid, as a byte offset, and why.
+pub(crate) type RegexError = (usize, &'static str);
+
+/// A parsed regular expression. Each symbol is numbered by its position in the expression.
+#[derive(Debug)]
+enum Regex {
+    Empty,
+    Symbol(usize),
+    Concat(Vec<Regex>),
+    Alternate(Vec<Regex>),
+    Star(Box<Regex>),
+    Plus(Box<Regex>),
+    Optional(Box<Regex>),
+}
+
+struct Parser<'a> {
+    pattern: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    /// The label of every symbol, by position.
+    labels: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn position(&mut self) -> usize {
+        match self.chars.peek() {
+            Some(&(position, _)) => position,
+            None => self.pattern.len(),
+        }
+    }
+
+    fn alternate(&mut self) -> Result<Regex, RegexError> {
+        let mut alternatives = vec![self.concat()?];
+        while let Some(&(_, '|')) = self.chars.peek() {
+            self.chars.next();
+            alternatives.push(self.concat()?);
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Regex::Alternate(alternatives)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Regex, RegexError> {
+        let mut parts = Vec::new();
+        loop {
+            match self.chars.peek() {
+                None | Some(&(_, '|')) | Some(&(_, ')')) => break,
+                _ => parts.push(self.repeat()?),
+            }
+        }
+        Ok(match parts.len() {
+            0 => Regex::Empty,
+            1 => parts.pop().unwrap(),
+            _ => Regex::Concat(parts),
+        })
+    }
+
+    fn repeat(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = self.atom()?;
+        loop {
+            regex = match self.chars.peek() {
+                Some(&(_, '*')) => Regex::Star(Box::new(regex)),
+                Some(&(_, '+')) => Regex::Plus(Box::new(regex)),
+                Some(&(_, '?')) => Regex::Optional(Box::new(regex)),
+                _ => return Ok(regex),
+            };
+            self.chars.next();
+        }
+    }
+
+    fn atom(&mut self) -> Result<Regex, RegexError> {
+        let (position, c) = self.chars.next().unwrap();
+        let label = match c {
+            '(' => {
+                let regex = self.alternate()?;
+                return match self.chars.next() {
+                    Some((_, ')')) => Ok(regex),
+                    _ => Err((position, "unclosed `(`")),
+                };
+            }
+            '*' | '+' | '?' => return Err((position, "nothing to repeat")),
+            '.' => return Err((position, "`.` is not supported, use a class instead")),
+            '[' => {
+                let mut escaped = false;
+                let end = loop {
+                    match self.chars.next() {
+                        Some((_, '\\')) if !escaped => escaped = true,
+                        Some((end, ']')) if !escaped => break end,
+                        Some(_) => escaped = false,
+                        None => return Err((position, "unclosed `[`")),
+                    }
+                };
+                let class = &self.pattern[position..end + 1];
+                match Label::parse(class) {
+                    Some(Label::Class(_)) => class.to_owned(),
+                    _ => return Err((position, "invalid class")),
+                }
+            }
+            '\\' => match self.chars.next() {
+                Some((_, 'd')) => "[:digit:]".to_owned(),
+                Some((_, 's')) => "[:space:]".to_owned(),
+                Some((_, 'w')) => "[:alnum:_]".to_owned(),
+                Some((_, c)) => character(c),
+                None => return Err((position, "trailing backslash")),
+            },
+            c => character(c),
+        };
+        self.labels.push(label);
+        Ok(Regex::Symbol(self.labels.len()))
+    }
+}
+
+/// The label for a single character.
+fn character(c: char) -> String {
+    if c.to_string() == symbols::OTHER {
+        format!("[{}]", c)
+    } else {
+        c.to_string()
+    }
+}
+
+/// The sets of the Glushkov construction for part of an expression: whether it matches the
+/// empty word, and which symbols can come first and last.
+struct Positions {
+    nullable: bool,
+    first: BTreeSet<usize>,
+    last: BTreeSet<usize>,
+}
+
+/// Works out the positions of an expression, adding which symbols can follow which to `follow`.
+fn positions(regex: &Regex, follow: &mut Vec<BTreeSet<usize>>) -> Positions {
+    match *regex {
+        Regex::Empty => Positions {
+            nullable: true,
+            first: BTreeSet::new(),
+            last: BTreeSet::new(),
+        },
+        Regex::Symbol(position) => Positions {
+            nullable: false,
+            first: Some(position).into_iter().collect(),
+            last: Some(position).into_iter().collect(),
+        },
+        Regex::Concat(ref parts) => {
+            let mut whole = positions(&Regex::Empty, follow);
+            for part in parts {
+                let part = positions(part, follow);
+                for &position in &whole.last {
+                    follow[position].extend(part.first.iter().cloned());
+                }
+                if whole.nullable {
+                    whole.first.extend(part.first.iter().cloned());
+                }
+                if part.nullable {
+                    whole.last.extend(part.last);
+                } else {
+                    whole.last = part.last;
+                }
+                whole.nullable = whole.nullable && part.nullable;
+            }
+            whole
+        }
+        Regex::Alternate(ref alternatives) => {
+            let mut whole = Positions {
+                nullable: false,
+                first: BTreeSet::new(),
+                last: BTreeSet::new(),
+            };
+            for alternative in alternatives {
+                let alternative = positions(alternative, follow);
+                whole.nullable = whole.nullable || alternative.nullable;
+                whole.first.extend(alternative.first);
+                whole.last.extend(alternative.last);
+            }
+            whole
+        }
+        Regex::Star(ref inner) | Regex::Plus(ref inner) => {
+            let mut inner_positions = positions(inner, follow);
+            for &position in &inner_positions.last {
+                follow[position].extend(inner_positions.first.iter().cloned());
+            }
+            if let Regex::Star(_) = *regex {
+                inner_positions.nullable = true;
+            }
+            inner_positions
+        }
+        Regex::Optional(ref inner) => {
+            let mut inner_positions = positions(inner, follow);
+            inner_positions.nullable = true;
+            inner_positions
+        }
+    }
+}
+
+/// Parses a regular expression into an NFA with a state for the start, named `0`, and one for
+/// every symbol in the expression, named after its position.
+pub(crate) fn parse_regex(pattern: &str) -> Result<Nfa<Sanitary>, RegexError> {
+    let mut parser = Parser {
+        pattern,
+        chars: pattern.char_indices().peekable(),
+        labels: Vec::new(),
+    };
+    let regex = parser.alternate()?;
+    if parser.chars.peek().is_some() {
+        let position = parser.position();
+        return Err((position, "unmatched `)`"));
+    }
+    let labels = parser.labels;
+
+    let mut follow = vec![BTreeSet::new(); labels.len() + 1];
+    let Positions {
+        nullable,
+        first,
+        last,
+    } = positions(&regex, &mut follow);
+    follow[0] = first;
+
+    let mut builder = NfaBuilder::new().start("0").state("0");
+    for (label, _) in symbols::minterms(&labels) {
+        builder = builder.symbol(label);
+    }
+    for (from, targets) in follow.iter().enumerate() {
+        for &to in targets {
+            builder = builder.transition(from.to_string(), labels[to - 1].as_str(), to.to_string());
+        }
+    }
+    for position in last {
+        builder = builder.final_state(position.to_string());
+    }
+    if nullable {
+        builder = builder.final_state("0");
+    }
+    Ok(builder
+        .check()
+        .expect("the symbols of an expression make up its alphabet"))
+}
+
+#[test]
+fn parse_regex_words() {
+    let nfa = parse_regex("(a|b)*abb").unwrap();
+    let accepts = |word: &str| {
+        nfa.accepts(word.split("").filter(|x| !x.is_empty()))
+            .unwrap()
+    };
+    assert!(accepts("abb"));
+    assert!(accepts("babaabb"));
+    assert!(!accepts("ab"));
+    assert!(!accepts(""));
+
+    let nfa = parse_regex(r"-?\d+(\.\d+)?|[*+]").unwrap();
+    let accepts = |word: &str| {
+        nfa.accepts(word.split("").filter(|x| !x.is_empty()))
+            .unwrap()
+    };
+    assert!(accepts("-12.5"));
+    assert!(accepts("7"));
+    assert!(accepts("*"));
+    assert!(!accepts("1."));
+    assert!(!accepts("-"));
+
+    assert!(parse_regex("a*|b?").unwrap().accepts(vec![]).unwrap());
+}
+
+#[test]
+fn parse_regex_errors() {
+    assert_eq!(parse_regex("(ab").unwrap_err(), (0, "unclosed `(`"));
+    assert_eq!(parse_regex("ab)c").unwrap_err(), (2, "unmatched `)`"));
+    assert_eq!(parse_regex("a|*").unwrap_err(), (2, "nothing to repeat"));
+    assert_eq!(parse_regex("x[z-a]").unwrap_err(), (1, "invalid class"));
+    assert_eq!(parse_regex("[a-z").unwrap_err(), (0, "unclosed `[`"));
+    assert_eq!(parse_regex("ab\\").unwrap_err(), (2, "trailing backslash"));
+}
@@ -0,0 +1,672 @@
+//! Symbol classes, which let a single transition stand for many symbols.
+//!
+//! Anywhere a symbol is written, whether in an alphabet or on a transition, it can be one of:
+//!
+//! - A plain symbol, such as `a` or `if`.
+//! - A class of single character symbols in square brackets, made of characters, ranges such as
+//!   `a-z`, and the named classes `:digit:`, `:lower:`, `:upper:`, `:alpha:`, `:alnum:` and
+//!   `:space:`, which only cover ASCII. For example `[a-zA-Z_]` or `[:digit:.]`. A backslash
+//!   escapes the next character, so `[+\-]` is a plus or a minus. A `:` starts a named class,
+//!   so a literal colon must be escaped too, as in `[a\:]`.
+//! - `*`, on a transition only, which stands for every symbol of the alphabet that no other
+//!   transition out of the same state is labelled with.
+//!
+//! The symbols in an alphabet must not overlap. When an NFA is made deterministic, its labels
+//! are split into disjoint classes (minterms), so that the DFA has one transition per class
+//! rather than one per symbol. Plain symbols always stay classes of their own.
+
+use std::char;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use automata::NfaError;
+
+/// The label for every symbol not on another transition out of the same state.
+pub(crate) const OTHER: &str = "*";
+
+/// The named classes, and the ranges they cover.
+const NAMED_CLASSES: &[(&str, &[(char, char)])] = &[
+    ("digit", &[('0', '9')]),
+    ("lower", &[('a', 'z')]),
+    ("upper", &[('A', 'Z')]),
+    ("alpha", &[('A', 'Z'), ('a', 'z')]),
+    ("alnum", &[('0', '9'), ('A', 'Z'), ('a', 'z')]),
+    ("space", &[('\t', '\r'), (' ', ' ')]),
+];
+
+/// A set of symbols.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct SymbolSet {
+    /// Single character symbols, as sorted, disjoint and non-adjacent ranges.
+    chars: Vec<(char, char)>,
+
+    /// Symbols that are not a single character.
+    words: BTreeSet<String>,
+}
+
+/// What a label on a transition, or in an alphabet, stands for.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Label {
+    /// A plain symbol.
+    Symbol(String),
+    /// A bracketed class of symbols.
+    Class(SymbolSet),
+    /// Every symbol not on another transition out of the same state.
+    Other,
+}
+
+/// The character after another, skipping over the surrogates, which are not characters.
+fn succ(c: char) -> Option<char> {
+    match c {
+        '\u{D7FF}' => Some('\u{E000}'),
+        '\u{10FFFF}' => None,
+        _ => Some(char::from_u32(c as u32 + 1).unwrap()),
+    }
+}
+
+impl SymbolSet {
+    /// The set containing a single symbol.
+    fn symbol(symbol: &str) -> Self {
+        let mut chars = symbol.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => SymbolSet {
+                chars: vec![(c, c)],
+                words: BTreeSet::new(),
+            },
+            _ => SymbolSet {
+                chars: Vec::new(),
+                words: Some(symbol.to_owned()).into_iter().collect(),
+            },
+        }
+    }
+
+    /// The set of characters in some ranges, which may overlap.
+    fn ranges(mut ranges: Vec<(char, char)>) -> Self {
+        ranges.sort();
+        let mut chars: Vec<(char, char)> = Vec::new();
+        for (low, high) in ranges {
+            if let Some(last) = chars.last_mut() {
+                // overlapping or adjacent ranges are merged
+                if succ(last.1).map_or(true, |next| low <= next) {
+                    if high > last.1 {
+                        last.1 = high;
+                    }
+                    continue;
+                }
+            }
+            chars.push((low, high));
+        }
+        SymbolSet {
+            chars,
+            words: BTreeSet::new(),
+        }
+    }
+
+    /// The single character symbols, as sorted and disjoint ranges.
+    pub(crate) fn chars(&self) -> &[(char, char)] {
+        &self.chars
+    }
+
+    /// The symbols that are not a single character.
+    pub(crate) fn words(&self) -> &BTreeSet<String> {
+        &self.words
+    }
+
+    /// Whether the set contains a symbol.
+    pub(crate) fn contains(&self, symbol: &str) -> bool {
+        let mut chars = symbol.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => self.chars
+                .iter()
+                .any(|&(low, high)| low <= c && c <= high),
+            _ => self.words.contains(symbol),
+        }
+    }
+
+    /// Whether every symbol in this set is in another.
+    pub(crate) fn is_subset(&self, other: &SymbolSet) -> bool {
+        self.words.is_subset(&other.words) &&
+            self.chars.iter().all(|&(low, high)| {
+                other
+                    .chars
+                    .iter()
+                    .any(|&(other_low, other_high)| other_low <= low && high <= other_high)
+            })
+    }
+
+    /// Whether any symbol is in both sets.
+    pub(crate) fn intersects(&self, other: &SymbolSet) -> bool {
+        !self.words.is_disjoint(&other.words) ||
+            self.chars.iter().any(|&(low, high)| {
+                other
+                    .chars
+                    .iter()
+                    .any(|&(other_low, other_high)| low <= other_high && other_low <= high)
+            })
+    }
+
+    /// Every symbol that is in either set.
+    pub(crate) fn union(&self, other: &SymbolSet) -> SymbolSet {
+        let mut union =
+            SymbolSet::ranges(self.chars.iter().chain(&other.chars).cloned().collect());
+        union.words = self.words.union(&other.words).cloned().collect();
+        union
+    }
+
+    /// The number of symbols in the set.
+    pub(crate) fn len(&self) -> usize {
+        let chars: usize = self.chars
+            .iter()
+            .map(|&(low, high)| {
+                let mut count = high as usize - low as usize + 1;
+                if low <= '\u{D7FF}' && high >= '\u{E000}' {
+                    count -= 0xE000 - 0xD800;
+                }
+                count
+            })
+            .sum();
+        chars + self.words.len()
+    }
+
+    /// The first symbol of the set, if it has any.
+    pub(crate) fn representative(&self) -> Option<String> {
+        self.chars
+            .first()
+            .map(|&(low, _)| low.to_string())
+            .or_else(|| self.words.iter().next().cloned())
+    }
+
+    /// Writes the set as a label. The set must not mix single characters and longer symbols.
+    fn label(&self) -> String {
+        if self.chars.is_empty() && self.words.len() == 1 {
+            return self.words.iter().next().unwrap().to_owned();
+        }
+        debug_assert!(self.words.is_empty());
+        if self.chars.len() == 1 && self.chars[0].0 == self.chars[0].1 && self.chars[0].0 != '*' {
+            return self.chars[0].0.to_string();
+        }
+
+        let mut label = String::from("[");
+        let escape = |label: &mut String, c: char| {
+            if "\\]-:".contains(c) {
+                label.push('\\');
+            }
+            label.push(c);
+        };
+        for &(low, high) in &self.chars {
+            escape(&mut label, low);
+            if high != low {
+                if succ(low) != Some(high) {
+                    label.push('-');
+                }
+                escape(&mut label, high);
+            }
+        }
+        label.push(']');
+        label
+    }
+}
+
+impl Label {
+    /// Parses a label, or returns `None` if it is a malformed class.
+    pub(crate) fn parse(label: &str) -> Option<Label> {
+        if label == OTHER {
+            return Some(Label::Other);
+        }
+        if label.len() < 2 || !label.starts_with('[') || !label.ends_with(']') {
+            return Some(Label::Symbol(label.to_owned()));
+        }
+
+        let mut ranges = Vec::new();
+        let mut chars = label[1..label.len() - 1].chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c == ':' {
+                chars.next();
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some(':') => break,
+                        Some(c) => name.push(c),
+                        None => return None,
+                    }
+                }
+                match NAMED_CLASSES.iter().find(|&&(x, _)| x == name) {
+                    Some(&(_, class)) => ranges.extend_from_slice(class),
+                    None => return None,
+                }
+                continue;
+            }
+            let low = match escaped(&mut chars) {
+                Some(low) => low,
+                None => return None,
+            };
+            if chars.peek() == Some(&'-') {
+                chars.next();
+                match escaped(&mut chars) {
+                    Some(high) if low <= high => ranges.push((low, high)),
+                    _ => return None,
+                }
+            } else {
+                ranges.push((low, low));
+            }
+        }
+        if ranges.is_empty() {
+            return None;
+        }
+        Some(Label::Class(SymbolSet::ranges(ranges)))
+    }
+
+    /// The symbols the label stands for, unless it is `*`.
+    pub(crate) fn set(&self) -> Option<SymbolSet> {
+        match *self {
+            Label::Symbol(ref symbol) => Some(SymbolSet::symbol(symbol)),
+            Label::Class(ref set) => Some(set.clone()),
+            Label::Other => None,
+        }
+    }
+}
+
+/// Reads a character of a class, which may be escaped with a backslash.
+fn escaped(chars: &mut Peekable<Chars>) -> Option<char> {
+    match chars.next() {
+        Some('\\') => chars.next(),
+        c => c,
+    }
+}
+
+/// Ensures that every symbol in an alphabet is valid, and that no two overlap. Returns every
+/// symbol in the alphabet.
+pub(crate) fn check_alphabet(alphabet: &HashSet<String>) -> Result<SymbolSet, NfaError> {
+    let mut sets: Vec<(&String, SymbolSet)> = Vec::new();
+    let mut labels: Vec<&String> = alphabet.iter().collect();
+    labels.sort();
+    for label in labels {
+        let set = match Label::parse(label).as_ref().and_then(Label::set) {
+            Some(set) => set,
+            None => return Err(NfaError::InvalidSymbol(label.to_owned())),
+        };
+        if let Some(&(other, _)) = sets.iter().find(|&&(_, ref other)| other.intersects(&set)) {
+            return Err(NfaError::OverlappingSymbols(
+                other.to_owned(),
+                label.to_owned(),
+            ));
+        }
+        sets.push((label, set));
+    }
+    Ok(sets.into_iter()
+        .fold(SymbolSet::default(), |all, (_, set)| all.union(&set)))
+}
+
+/// Ensures that a label on a transition is valid, and only stands for symbols in the alphabet.
+pub(crate) fn check_label(label: &str, alphabet: &SymbolSet) -> Result<Label, NfaError> {
+    let parsed = Label::parse(label).ok_or_else(|| NfaError::InvalidSymbol(label.to_owned()))?;
+    match parsed.set() {
+        Some(ref set) if !set.is_subset(alphabet) => {
+            Err(NfaError::UnknownSymbol(label.to_owned()))
+        }
+        _ => Ok(parsed),
+    }
+}
+
+/// Ensures that the labels on the transitions out of a state of a DFA are valid, and that no
+/// two of them have a symbol in common.
+pub(crate) fn check_deterministic<'a, I>(labels: I, alphabet: &SymbolSet) -> Result<(), NfaError>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let mut labels: Vec<&String> = labels.into_iter().collect();
+    labels.sort();
+    let mut sets: Vec<(&String, SymbolSet)> = Vec::new();
+    for label in labels {
+        if let Some(set) = check_label(label, alphabet)?.set() {
+            if let Some(&(other, _)) = sets.iter().find(|&&(_, ref other)| other.intersects(&set))
+            {
+                return Err(NfaError::OverlappingSymbols(
+                    other.to_owned(),
+                    label.to_owned(),
+                ));
+            }
+            sets.push((label, set));
+        }
+    }
+    Ok(())
+}
+
+/// Whether a label is a plain symbol, rather than a class or `*`.
+pub(crate) fn is_plain(label: &str) -> bool {
+    match Label::parse(label) {
+        Some(Label::Symbol(_)) => true,
+        _ => false,
+    }
+}
+
+/// Removes the plain symbols that are already in one of the alphabet's classes, such as the
+/// symbols of transitions that builders add to the alphabet.
+pub(crate) fn remove_covered(alphabet: HashSet<String>) -> HashSet<String> {
+    let classes: Vec<SymbolSet> = alphabet
+        .iter()
+        .filter(|symbol| !is_plain(symbol))
+        .filter_map(|symbol| Label::parse(symbol).and_then(|label| label.set()))
+        .collect();
+    alphabet
+        .into_iter()
+        .filter(|symbol| !is_plain(symbol) || !classes.iter().any(|set| set.contains(symbol)))
+        .collect()
+}
+
+/// Whether any of the labels is a class, or `*`.
+pub(crate) fn has_classes<'a, I>(labels: I) -> bool
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    labels.into_iter().any(|label| !is_plain(label))
+}
+
+/// Whether an alphabet has a symbol.
+pub(crate) fn in_alphabet(alphabet: &HashSet<String>, symbol: &str) -> bool {
+    alphabet.contains(symbol) ||
+        alphabet.iter().any(|label| match Label::parse(label) {
+            Some(Label::Class(ref set)) => set.contains(symbol),
+            _ => false,
+        })
+}
+
+/// The transitions out of a single state that a symbol follows: those with a label containing
+/// the symbol, or failing that, the one labelled `*`.
+pub(crate) fn follow<'a, T>(transitions: &'a HashMap<String, T>, symbol: &str) -> Vec<&'a T> {
+    let mut targets = Vec::new();
+    let mut other = None;
+    for (label, target) in transitions {
+        match Label::parse(label) {
+            Some(Label::Other) => other = Some(target),
+            Some(Label::Symbol(ref plain)) if plain == symbol => targets.push(target),
+            Some(Label::Class(ref set)) if set.contains(symbol) => targets.push(target),
+            _ => {}
+        }
+    }
+    if targets.is_empty() {
+        targets.extend(other);
+    }
+    targets
+}
+
+/// Splits the symbols of some labels into the fewest disjoint classes such that every label is
+/// a union of classes, except that plain symbols are always classes of their own. Returns every
+/// class as a label, along with its symbols, in sorted order of labels. `*` is ignored, as it
+/// covers whatever the other labels do not.
+pub(crate) fn minterms<'a, I>(labels: I) -> Vec<(String, SymbolSet)>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let mut plain = BTreeSet::new();
+    let mut classes = Vec::new();
+    for label in labels {
+        match Label::parse(label) {
+            Some(Label::Symbol(symbol)) => {
+                plain.insert(symbol);
+            }
+            Some(Label::Class(set)) => classes.push(set),
+            Some(Label::Other) | None => {}
+        }
+    }
+
+    // every character in a class, apart from the plain symbols, is grouped with the others that
+    // are in exactly the same classes
+    let mut boundaries = BTreeSet::new();
+    for set in &classes {
+        for &(low, high) in &set.chars {
+            boundaries.insert(Some(low));
+            boundaries.insert(succ(high));
+        }
+    }
+    for symbol in &plain {
+        let mut chars = symbol.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            boundaries.insert(Some(c));
+            boundaries.insert(succ(c));
+        }
+    }
+    let boundaries: Vec<Option<char>> = boundaries.into_iter().collect();
+    let mut groups: BTreeMap<Vec<usize>, Vec<(char, char)>> = BTreeMap::new();
+    for window in boundaries.windows(2) {
+        let low = match window[0] {
+            Some(low) => low,
+            None => continue,
+        };
+        let high = match window[1] {
+            Some(next) => char::from_u32(next as u32 - 1)
+                .unwrap_or('\u{D7FF}'),
+            None => '\u{10FFFF}',
+        };
+        if low == high && plain.contains(&low.to_string()) {
+            continue;
+        }
+        let members: Vec<usize> = classes
+            .iter()
+            .enumerate()
+            .filter(|&(_, set)| set.contains(&low.to_string()))
+            .map(|(i, _)| i)
+            .collect();
+        if !members.is_empty() {
+            groups.entry(members).or_insert_with(Vec::new).push((low, high));
+        }
+    }
+
+    let mut minterms: Vec<(String, SymbolSet)> = plain
+        .into_iter()
+        .map(|symbol| {
+            let set = SymbolSet::symbol(&symbol);
+            (symbol, set)
+        })
+        .chain(groups.into_iter().map(|(_, ranges)| {
+            let set = SymbolSet::ranges(ranges);
+            (set.label(), set)
+        }))
+        .collect();
+    minterms.sort_by(|left, right| left.0.cmp(&right.0));
+    minterms
+}
+
+/// Rewrites the transitions of an NFA to be over the minterms of its labels, returning the
+/// minterms as the new alphabet. NFAs without any classes are returned as they are.
+pub(crate) fn split_classes(
+    alphabet: HashSet<String>,
+    nodes: HashMap<String, HashMap<String, HashSet<String>>>,
+) -> (HashSet<String>, HashMap<String, HashMap<String, HashSet<String>>>) {
+    let classes = has_classes(&alphabet) ||
+        nodes
+            .values()
+            .any(|transitions| has_classes(transitions.keys()));
+    if !classes {
+        return (alphabet, nodes);
+    }
+
+    let minterms = minterms(
+        alphabet
+            .iter()
+            .chain(nodes.values().flat_map(|transitions| transitions.keys())),
+    );
+    let nodes = nodes
+        .into_iter()
+        .map(|(state, transitions)| {
+            let labels: Vec<(Option<SymbolSet>, &HashSet<String>)> = transitions
+                .iter()
+                .filter_map(|(label, targets)| {
+                    Label::parse(label).map(|label| (label.set(), targets))
+                })
+                .collect();
+            let split = minterms
+                .iter()
+                .filter_map(|&(ref minterm, ref symbols)| {
+                    let mut targets: HashSet<String> = HashSet::new();
+                    let mut other = None;
+                    let mut covered = false;
+                    for &(ref set, label_targets) in &labels {
+                        match *set {
+                            Some(ref set) if symbols.is_subset(set) => {
+                                covered = true;
+                                targets.extend(label_targets.iter().cloned());
+                            }
+                            Some(_) => {}
+                            None => other = Some(label_targets),
+                        }
+                    }
+                    if !covered {
+                        targets.extend(other.into_iter().flat_map(|x| x.iter().cloned()));
+                    }
+                    if targets.is_empty() {
+                        None
+                    } else {
+                        Some((minterm.to_owned(), targets))
+                    }
+                })
+                .collect();
+            (state, split)
+        })
+        .collect();
+
+    let alphabet = minterms.into_iter().map(|(label, _)| label).collect();
+    (alphabet, nodes)
+}
+
+#[test]
+fn parse_symbol_classes() {
+    let class = |label: &str| match Label::parse(label) {
+        Some(Label::Class(set)) => set.chars,
+        other @ _ => panic!("{:?}", other),
+    };
+    assert_eq!(class("[a-z]"), vec![('a', 'z')]);
+    assert_eq!(class("[a-cb-fx]"), vec![('a', 'f'), ('x', 'x')]);
+    assert_eq!(class("[:digit:_]"), vec![('0', '9'), ('_', '_')]);
+    assert_eq!(class("[+\\-]"), vec![('+', '+'), ('-', '-')]);
+    assert_eq!(class("[*]"), vec![('*', '*')]);
+
+    assert_eq!(Label::parse("*"), Some(Label::Other));
+    assert_eq!(Label::parse("a-z"), Some(Label::Symbol("a-z".to_owned())));
+    assert_eq!(Label::parse("["), Some(Label::Symbol("[".to_owned())));
+    assert_eq!(Label::parse("[]"), None);
+    assert_eq!(Label::parse("[z-a]"), None);
+    assert_eq!(Label::parse("[:vowel:]"), None);
+    assert_eq!(Label::parse("[a-]"), None);
+    assert_eq!(Label::parse("[:digit]"), None);
+    assert_eq!(Label::parse("[a:]"), None);
+    assert_eq!(class("[a\\:]"), vec![(':', ':'), ('a', 'a')]);
+
+    let set = Label::parse("[a-z0-9]").unwrap().set().unwrap();
+    assert!(set.contains("q"));
+    assert!(!set.contains("Q"));
+    assert!(!set.contains("ab"));
+    assert_eq!(set.len(), 36);
+    assert_eq!(set.label(), "[0-9a-z]");
+    assert_eq!(set.representative(), Some("0".to_owned()));
+}
+
+#[test]
+fn split_minterms() {
+    let labels: Vec<String> = vec!["[a-z]", "[0-9a-f]", "x", "if", "*"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let atoms: Vec<String> = minterms(&labels)
+        .into_iter()
+        .map(|(label, _)| label)
+        .collect();
+    assert_eq!(atoms, vec!["[0-9]", "[a-f]", "[g-wyz]", "if", "x"]);
+
+    let labels: Vec<String> = vec!["a", "b"].into_iter().map(String::from).collect();
+    let atoms: Vec<String> = minterms(&labels)
+        .into_iter()
+        .map(|(label, _)| label)
+        .collect();
+    assert_eq!(atoms, vec!["a", "b"]);
+}
+
+#[cfg(test)]
+use builder::{DfaBuilder, NfaBuilder};
+
+#[test]
+fn symbol_class_automata() {
+    // identifiers and numbers
+    let nfa = NfaBuilder::new()
+        .start("start")
+        .symbol("[:alnum:]")
+        .symbol("_")
+        .transition("start", "[:alpha:_]", "word")
+        .transition("word", "[:alnum:_]", "word")
+        .transition("start", "[:digit:]", "number")
+        .transition("number", "[:digit:]", "number")
+        .final_state("word")
+        .final_state("number")
+        .check()
+        .unwrap();
+    assert!(nfa.accepts(vec!["x", "1", "_"]).unwrap());
+    assert!(nfa.accepts(vec!["4", "2"]).unwrap());
+    assert!(!nfa.accepts(vec!["4", "x"]).unwrap());
+    assert_eq!(
+        nfa.accepts(vec!["-"]).unwrap_err().to_string(),
+        "unknown symbol \"-\""
+    );
+
+    // the letters are always used together, so they share a single transition
+    let dfa = nfa.make_deterministic().minimise();
+    let alphabet: BTreeSet<&str> = dfa.alphabet().iter().map(|x| x.as_str()).collect();
+    assert_eq!(alphabet, vec!["[0-9]", "[A-Za-z]", "_"].into_iter().collect());
+    assert_eq!(dfa.states().count(), 4);
+    assert!(dfa.accepts(vec!["x", "1", "_"]).unwrap());
+    assert!(!dfa.accepts(vec!["4", "x"]).unwrap());
+
+    // words containing an "a", using the fallback for every other letter
+    let nfa = NfaBuilder::new()
+        .start("1")
+        .symbol("[a-z]")
+        .transition("1", "a", "2")
+        .transition("1", "*", "1")
+        .transition("2", "*", "2")
+        .final_state("2")
+        .check()
+        .unwrap();
+    let dfa = nfa.make_deterministic();
+    let alphabet: BTreeSet<&str> = dfa.alphabet().iter().map(|x| x.as_str()).collect();
+    assert_eq!(alphabet, vec!["[b-z]", "a"].into_iter().collect());
+    assert!(dfa.accepts(vec!["b", "a", "c"]).unwrap());
+    assert!(!dfa.accepts(vec!["b", "z"]).unwrap());
+}
+
+#[test]
+fn invalid_symbol_classes() {
+    let error = |builder: NfaBuilder| builder.start("1").state("1").check().unwrap_err();
+    match error(NfaBuilder::new().symbol("[a-z]").symbol("[x-z0-9]")) {
+        NfaError::OverlappingSymbols(left, right) => {
+            assert_eq!((left.as_str(), right.as_str()), ("[a-z]", "[x-z0-9]"))
+        }
+        err @ _ => panic!("{:?}", err),
+    }
+    match error(NfaBuilder::new().symbol("[a-z]").transition("1", "[a-z0-9]", "1")) {
+        NfaError::UnknownSymbol(symbol) => assert_eq!(symbol, "[a-z0-9]"),
+        err @ _ => panic!("{:?}", err),
+    }
+    match error(NfaBuilder::new().symbol("[a-z]").transition("1", "[z-a]", "1")) {
+        NfaError::InvalidSymbol(symbol) => assert_eq!(symbol, "[z-a]"),
+        err @ _ => panic!("{:?}", err),
+    }
+    match error(NfaBuilder::new().symbol("*")) {
+        NfaError::InvalidSymbol(symbol) => assert_eq!(symbol, "*"),
+        err @ _ => panic!("{:?}", err),
+    }
+
+    // transitions out of a state of a DFA must not overlap
+    match DfaBuilder::new()
+        .start("1")
+        .symbol("[a-z]")
+        .transition("1", "[a-m]", "1")
+        .transition("1", "[k-z]", "2")
+        .check()
+        .unwrap_err()
+    {
+        NfaError::OverlappingSymbols(left, right) => {
+            assert_eq!((left.as_str(), right.as_str()), ("[a-m]", "[k-z]"))
+        }
+        err @ _ => panic!("{:?}", err),
+    }
+}
@@ -0,0 +1,555 @@
+//! A compact, line based text format for automata.
+//!
+//! ```text
+//! # comments run to the end of the line
+//! start: q0
+//! final: q2 q3
+//! alphabet: a b
+//! states: q0 q1 q2 q3
+//! q0 -a-> q1, q2
+//! q1 -b-> q3
+//! ```
+//!
+//! Every state mentioned on either side of a transition is a state of the automata, so the
+//! `states` line is only needed for states without any transitions. If there is no `alphabet`
+//! line, the alphabet is every symbol used in a transition. Names containing whitespace or any
+//! of `: , # " - \` can be written as double quoted strings, e.g. `"1 + 2" -a-> "dead state"`.
+//!
+//! [Symbol classes](../symbols/index.html) with ranges need quoting, e.g. `q0 -"[a-z]"-> q1`, and
+//! have to be listed on the `alphabet` line, as only plain symbols are added to it
+//! automatically.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use automata::{Dfa, Nfa, Unsanitary};
+use symbols;
+
+#[cfg(test)]
+use automata::NfaError;
+
+/// An error encountered while parsing the text format, along with where it happened. Lines and
+/// columns are both counted from 1.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+/// The different ways parsing the text format can fail.
+#[derive(Debug, PartialEq)]
+pub enum ParseErrorKind {
+    UnexpectedChar(char),
+    UnterminatedString,
+    Expected(&'static str),
+    UnknownDirective(String),
+    DuplicateStart,
+    MissingStart,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match self.kind {
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            ParseErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            ParseErrorKind::Expected(what) => write!(f, "expected {}", what),
+            ParseErrorKind::UnknownDirective(ref name) => {
+                write!(f, "unknown directive {:?}", name)
+            }
+            ParseErrorKind::DuplicateStart => write!(f, "start state given more than once"),
+            ParseErrorKind::MissingStart => write!(f, "no start state given"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Name(String),
+    Colon,
+    Comma,
+    Dash,
+    Arrow,
+}
+
+/// Splits a single line into tokens, each paired with the column it starts at.
+fn tokenise(line_no: usize, line: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let error = |column, kind| ParseError {
+        line: line_no,
+        column,
+        kind,
+    };
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().enumerate().map(|(i, c)| (i + 1, c)).peekable();
+
+    while let Some((column, c)) = chars.next() {
+        match c {
+            '#' => break,
+            ':' => tokens.push((column, Token::Colon)),
+            ',' => tokens.push((column, Token::Comma)),
+            '-' => if let Some(&(_, '>')) = chars.peek() {
+                chars.next();
+                tokens.push((column, Token::Arrow));
+            } else {
+                tokens.push((column, Token::Dash));
+            },
+            '"' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) => name.push(c),
+                            None => return Err(error(column, ParseErrorKind::UnterminatedString)),
+                        },
+                        Some((_, c)) => name.push(c),
+                        None => return Err(error(column, ParseErrorKind::UnterminatedString)),
+                    }
+                }
+                tokens.push((column, Token::Name(name)));
+            }
+            '\\' => return Err(error(column, ParseErrorKind::UnexpectedChar(c))),
+            c if c.is_whitespace() => {}
+            c => {
+                let mut name = c.to_string();
+                while let Some(&(_, c)) = chars.peek() {
+                    if is_special(c) {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                tokens.push((column, Token::Name(name)));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Characters that cannot appear in an unquoted name.
+fn is_special(c: char) -> bool {
+    c.is_whitespace() || ":,#\"-\\".contains(c)
+}
+
+/// Parses the text format into an NFA. The result still needs to be checked before use.
+pub fn parse_nfa(input: &str) -> Result<Nfa<Unsanitary>, ParseError> {
+    let mut start = None;
+    let mut final_states = HashSet::new();
+    let mut alphabet = None;
+    let mut nodes: HashMap<String, HashMap<String, HashSet<String>>> = HashMap::new();
+    let mut used = HashSet::new();
+    let mut last_line = 0;
+
+    for (line_no, line) in input.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+        last_line = line_no;
+        let mut tokens = tokenise(line_no, line)?.into_iter();
+        let end_column = line.chars().count() + 1;
+        let error = |column, kind| ParseError {
+            line: line_no,
+            column,
+            kind,
+        };
+
+        let (column, first) = match tokens.next() {
+            Some((column, Token::Name(name))) => (column, name),
+            Some((column, _)) => {
+                return Err(error(column, ParseErrorKind::Expected("a directive or state")))
+            }
+            None => continue,
+        };
+
+        match tokens.next() {
+            Some((_, Token::Colon)) => {
+                let mut names = Vec::new();
+                for (column, token) in tokens {
+                    match token {
+                        Token::Name(name) => names.push((column, name)),
+                        _ => return Err(error(column, ParseErrorKind::Expected("a name"))),
+                    }
+                }
+                match first.as_str() {
+                    "start" => {
+                        if start.is_some() {
+                            return Err(error(column, ParseErrorKind::DuplicateStart));
+                        }
+                        let mut names = names.into_iter();
+                        match (names.next(), names.next()) {
+                            (Some((_, name)), None) => start = Some(name),
+                            (None, _) => {
+                                return Err(error(end_column, ParseErrorKind::Expected("a state")))
+                            }
+                            (_, Some((column, _))) => {
+                                return Err(error(column, ParseErrorKind::Expected("end of line")))
+                            }
+                        }
+                    }
+                    "final" => final_states.extend(names.into_iter().map(|(_, name)| name)),
+                    "alphabet" => alphabet
+                        .get_or_insert_with(HashSet::new)
+                        .extend(names.into_iter().map(|(_, name)| name)),
+                    "states" => for (_, name) in names {
+                        nodes.entry(name).or_insert_with(HashMap::new);
+                    },
+                    _ => return Err(error(column, ParseErrorKind::UnknownDirective(first))),
+                }
+            }
+            Some((_, Token::Dash)) => {
+                let symbol = match tokens.next() {
+                    Some((_, Token::Name(symbol))) => symbol,
+                    Some((column, _)) => {
+                        return Err(error(column, ParseErrorKind::Expected("a symbol")))
+                    }
+                    None => return Err(error(end_column, ParseErrorKind::Expected("a symbol"))),
+                };
+                match tokens.next() {
+                    Some((_, Token::Arrow)) => {}
+                    Some((column, _)) => return Err(error(column, ParseErrorKind::Expected("`->`"))),
+                    None => return Err(error(end_column, ParseErrorKind::Expected("`->`"))),
+                }
+
+                let mut targets = HashSet::new();
+                loop {
+                    match tokens.next() {
+                        Some((_, Token::Name(name))) => {
+                            targets.insert(name);
+                        }
+                        Some((column, _)) => {
+                            return Err(error(column, ParseErrorKind::Expected("a state")))
+                        }
+                        None => return Err(error(end_column, ParseErrorKind::Expected("a state"))),
+                    }
+                    match tokens.next() {
+                        Some((_, Token::Comma)) => {}
+                        Some((column, _)) => {
+                            return Err(error(column, ParseErrorKind::Expected("`,`")))
+                        }
+                        None => break,
+                    }
+                }
+
+                for target in &targets {
+                    nodes.entry(target.to_owned()).or_insert_with(HashMap::new);
+                }
+                if symbols::is_plain(&symbol) {
+                    used.insert(symbol.to_owned());
+                }
+                nodes
+                    .entry(first)
+                    .or_insert_with(HashMap::new)
+                    .entry(symbol)
+                    .or_insert_with(HashSet::new)
+                    .extend(targets);
+            }
+            Some((column, _)) => {
+                return Err(error(column, ParseErrorKind::Expected("`:` or `-`")))
+            }
+            None => return Err(error(end_column, ParseErrorKind::Expected("`:` or `-`"))),
+        }
+    }
+
+    let start = match start {
+        Some(start) => start,
+        None => {
+            return Err(ParseError {
+                line: last_line + 1,
+                column: 1,
+                kind: ParseErrorKind::MissingStart,
+            })
+        }
+    };
+
+    Ok(Nfa::new(
+        start,
+        final_states,
+        alphabet.unwrap_or_else(|| symbols::remove_covered(used)),
+        nodes,
+    ))
+}
+
+/// Writes a name, quoting it if it would not otherwise survive being parsed back in.
+fn write_name(f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+    if !name.is_empty() && !name.chars().any(is_special) {
+        return write!(f, "{}", name);
+    }
+    write!(f, "\"")?;
+    for c in name.chars() {
+        if c == '"' || c == '\\' {
+            write!(f, "\\")?;
+        }
+        write!(f, "{}", c)?;
+    }
+    write!(f, "\"")
+}
+
+/// Writes a directive line, with its arguments in sorted order.
+fn write_directive<'a, I>(f: &mut fmt::Formatter, directive: &str, names: I) -> fmt::Result
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    write!(f, "{}:", directive)?;
+    let mut names: Vec<_> = names.into_iter().collect();
+    names.sort();
+    for name in names {
+        write!(f, " ")?;
+        write_name(f, name)?;
+    }
+    writeln!(f)
+}
+
+/// Writes everything but the transitions, which are where NFAs and DFAs differ.
+fn write_header<'a, I>(
+    f: &mut fmt::Formatter,
+    start: &str,
+    final_states: &HashSet<String>,
+    alphabet: &HashSet<String>,
+    states: I,
+) -> fmt::Result
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    write!(f, "start: ")?;
+    write_name(f, start)?;
+    writeln!(f)?;
+    write_directive(f, "final", final_states)?;
+    write_directive(f, "alphabet", alphabet)?;
+    write_directive(f, "states", states)
+}
+
+fn write_transition<'a, I>(f: &mut fmt::Formatter, from: &str, symbol: &str, to: I) -> fmt::Result
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    write_name(f, from)?;
+    write!(f, " -")?;
+    write_name(f, symbol)?;
+    write!(f, "->")?;
+    for (i, state) in to.into_iter().enumerate() {
+        write!(f, "{}", if i == 0 { " " } else { ", " })?;
+        write_name(f, state)?;
+    }
+    writeln!(f)
+}
+
+impl<T> fmt::Display for Nfa<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_header(
+            f,
+            &self.start,
+            &self.final_states,
+            &self.alphabet,
+            self.nodes.keys(),
+        )?;
+
+        let mut states: Vec<_> = self.nodes.iter().collect();
+        states.sort_by_key(|&(state, _)| state);
+        for (state, transitions) in states {
+            let mut transitions: Vec<_> = transitions
+                .iter()
+                .filter(|&(_, to)| !to.is_empty())
+                .collect();
+            transitions.sort_by_key(|&(symbol, _)| symbol);
+            for (symbol, to) in transitions {
+                let mut to: Vec<_> = to.iter().collect();
+                to.sort();
+                write_transition(f, state, symbol, to)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Dfa {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_header(
+            f,
+            &self.start,
+            &self.final_states,
+            &self.alphabet,
+            self.nodes.keys(),
+        )?;
+
+        let mut states: Vec<_> = self.nodes.iter().collect();
+        states.sort_by_key(|&(state, _)| state);
+        for (state, transitions) in states {
+            let mut transitions: Vec<_> = transitions.iter().collect();
+            transitions.sort_by_key(|&(symbol, _)| symbol);
+            for (symbol, to) in transitions {
+                write_transition(f, state, symbol, Some(to))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn parse_text_nfa() {
+    let input = "
+        # the same automata as the JSON tests
+        start: 1
+        final: 3
+        1 -a-> 1, 2
+        1 -b-> 1
+        2 -a-> 3
+        2 -b-> 3
+        3 -a-> 1
+        3 -b-> 2
+    ";
+    let nfa = parse_nfa(input).unwrap();
+    assert_eq!(nfa.start, "1");
+    assert_eq!(nfa.final_states, vec!["3".into()].into_iter().collect());
+    assert_eq!(nfa.alphabet, vec!["a".into(), "b".into()].into_iter().collect());
+    assert_eq!(
+        nfa.nodes["1"]["a"],
+        vec!["1".into(), "2".into()].into_iter().collect()
+    );
+    assert_eq!(nfa.nodes.len(), 3);
+    nfa.check().unwrap();
+}
+
+#[test]
+fn parse_text_quoted_names() {
+    let input = r#"
+        start: "dead state"
+        states: "a \"quoted\" state"
+        "dead state" -"-"-> "dead state"
+    "#;
+    let nfa = parse_nfa(input).unwrap();
+    assert_eq!(nfa.start, "dead state");
+    assert!(nfa.nodes.contains_key("a \"quoted\" state"));
+    assert_eq!(nfa.alphabet, vec!["-".into()].into_iter().collect());
+}
+
+#[test]
+fn parse_text_symbol_classes() {
+    let input = r#"
+        start: 1
+        final: 2
+        alphabet: "[a-z]" "[:digit:]"
+        1 -"[a-z]"-> 2
+        2 -a-> 2
+        2 -*-> 1
+    "#;
+    let nfa = parse_nfa(input).unwrap().check().unwrap();
+    assert_eq!(
+        nfa.alphabet,
+        vec!["[a-z]".into(), "[:digit:]".into()].into_iter().collect()
+    );
+    assert!(nfa.accepts(vec!["x", "7", "y", "a"]).unwrap());
+    assert!(!nfa.accepts(vec!["x", "7"]).unwrap());
+}
+
+#[test]
+fn parse_text_unknown_state() {
+    let input = "
+        start: 1
+        final: 4
+        1 -a-> 2
+    ";
+    match parse_nfa(input).unwrap().check().unwrap_err() {
+        NfaError::UnknownState(err) => assert_eq!(err, "4"),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn parse_text_errors() {
+    assert_eq!(
+        parse_nfa("start: 1\n1 -a> 2").unwrap_err(),
+        ParseError {
+            line: 2,
+            column: 7,
+            kind: ParseErrorKind::Expected("`->`"),
+        }
+    );
+    assert_eq!(
+        parse_nfa("start: 1\n1 -a-> 2,").unwrap_err(),
+        ParseError {
+            line: 2,
+            column: 10,
+            kind: ParseErrorKind::Expected("a state"),
+        }
+    );
+    assert_eq!(
+        parse_nfa("start: 1\nstart: 2").unwrap_err(),
+        ParseError {
+            line: 2,
+            column: 1,
+            kind: ParseErrorKind::DuplicateStart,
+        }
+    );
+    assert_eq!(
+        parse_nfa("  begin: 1").unwrap_err(),
+        ParseError {
+            line: 1,
+            column: 3,
+            kind: ParseErrorKind::UnknownDirective("begin".into()),
+        }
+    );
+    assert_eq!(
+        parse_nfa("1 -a-> \"2").unwrap_err(),
+        ParseError {
+            line: 1,
+            column: 8,
+            kind: ParseErrorKind::UnterminatedString,
+        }
+    );
+    assert_eq!(
+        parse_nfa("1 -a-> 2\n").unwrap_err(),
+        ParseError {
+            line: 2,
+            column: 1,
+            kind: ParseErrorKind::MissingStart,
+        }
+    );
+}
+
+#[test]
+fn print_text_round_trip() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b", "c"],
+        "nodes": {
+            "1": {
+                "a": ["1", "2"],
+                "b": ["1"]
+            },
+            "2": {
+                "a": ["3"],
+                "b": ["3"]
+            },
+            "3": {}
+        },
+        "final_states": ["3"]
+    }"#;
+    let nfa = ::serde_json::from_str::<Nfa<_>>(input)
+        .unwrap()
+        .check()
+        .unwrap();
+    let text = nfa.to_string();
+    assert_eq!(
+        text,
+        "start: 1\n\
+         final: 3\n\
+         alphabet: a b c\n\
+         states: 1 2 3\n\
+         1 -a-> 1, 2\n\
+         1 -b-> 1\n\
+         2 -a-> 3\n\
+         2 -b-> 3\n"
+    );
+    let parsed = parse_nfa(&text).unwrap();
+    assert_eq!(parsed.start, nfa.start);
+    assert_eq!(parsed.final_states, nfa.final_states);
+    assert_eq!(parsed.alphabet, nfa.alphabet);
+
+    let dfa = nfa.make_deterministic().minimise();
+    let parsed = parse_nfa(&dfa.to_string()).unwrap();
+    assert_eq!(parsed.start, dfa.start);
+    assert_eq!(parsed.final_states, dfa.final_states);
+    assert_eq!(parsed.alphabet, dfa.alphabet);
+    assert_eq!(parsed.nodes.len(), dfa.nodes.len());
+}
@@ -0,0 +1,683 @@
+//! Finite state transducers, which produce output rather than just accepting or rejecting.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::hash_map::Keys;
+
+use automata::{equivalent_states, merge_classes, NfaError, Sanitary, Unsanitary};
+
+#[cfg(test)]
+use serde_json;
+
+/// Moore machine, which produces an output in every state it visits.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Moore<T> {
+    /// Marker to ensure that the state machine has been validated before it is used for any
+    /// computations.
+    #[serde(skip_serializing)]
+    _sanitized: T,
+
+    /// Start state.
+    start: String,
+
+    /// The alphabet of symbols the machine reads.
+    alphabet: HashSet<String>,
+
+    /// The output produced by each state.
+    outputs: HashMap<String, String>,
+
+    /// The nodes within the machine. Each node has mappings from alphabet symbols to transition
+    /// states, and must have one for every symbol.
+    nodes: HashMap<String, HashMap<String, String>>,
+}
+
+/// A single transition of a Mealy machine.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MealyTransition {
+    /// The state the transition goes to.
+    pub state: String,
+
+    /// The output produced by taking the transition.
+    pub output: String,
+}
+
+/// Mealy machine, which produces an output on every transition it takes.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Mealy<T> {
+    /// Marker to ensure that the state machine has been validated before it is used for any
+    /// computations.
+    #[serde(skip_serializing)]
+    _sanitized: T,
+
+    /// Start state.
+    start: String,
+
+    /// The alphabet of symbols the machine reads.
+    alphabet: HashSet<String>,
+
+    /// The nodes within the machine. Each node has mappings from alphabet symbols to transitions,
+    /// and must have one for every symbol.
+    nodes: HashMap<String, HashMap<String, MealyTransition>>,
+}
+
+/// Ensures that the transition graph of a transducer is valid and complete.
+fn check_nodes<V, F>(
+    start: &String,
+    alphabet: &HashSet<String>,
+    nodes: &HashMap<String, HashMap<String, V>>,
+    target: F,
+) -> Result<(), NfaError>
+where
+    F: Fn(&V) -> &String,
+{
+    // ensure that the start state is a valid state
+    if !nodes.contains_key(start) {
+        return Err(NfaError::UnknownState(start.to_owned()));
+    }
+
+    // ensure that all state transitions are on valid symbols
+    if let Some((unknown_symbol, _)) = nodes
+        .iter()
+        .filter_map(|(_, maps)| {
+            maps.iter().find(|&(symbol, _)| !alphabet.contains(symbol))
+        })
+        .next()
+    {
+        return Err(NfaError::UnknownSymbol(unknown_symbol.to_owned()));
+    }
+
+    // ensure that all state transitions are to valid states
+    if let Some(unknown_state) = nodes
+        .iter()
+        .filter_map(|(_, maps)| {
+            maps.iter()
+                .map(|(_, transition)| target(transition))
+                .find(|&state| !nodes.contains_key(state))
+        })
+        .next()
+    {
+        return Err(NfaError::UnknownState(unknown_state.to_owned()));
+    }
+
+    // ensure that every state can go somewhere on every symbol
+    for (state, maps) in nodes {
+        if let Some(symbol) = alphabet.iter().find(|&symbol| !maps.contains_key(symbol)) {
+            return Err(NfaError::MissingTransition(
+                state.to_owned(),
+                symbol.to_owned(),
+            ));
+        }
+    }
+
+    if let Some(invalid_state) = nodes.keys().find(|state| state.is_empty()) {
+        return Err(NfaError::InvalidStateName(invalid_state.to_owned()));
+    }
+
+    Ok(())
+}
+
+impl<T> Moore<T> {
+    /// The start state.
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// The alphabet of symbols the machine reads.
+    pub fn alphabet(&self) -> &HashSet<String> {
+        &self.alphabet
+    }
+
+    /// The names of all of the states within the machine.
+    pub fn states<'a>(&'a self) -> Keys<'a, String, HashMap<String, String>> {
+        self.nodes.keys()
+    }
+
+    /// The output of a state, if it has one.
+    pub fn output(&self, state: &str) -> Option<&str> {
+        self.outputs.get(state).map(|output| output.as_str())
+    }
+
+    /// The state reached from a state on a single symbol, if there is one.
+    pub fn transition(&self, state: &str, symbol: &str) -> Option<&str> {
+        self.nodes
+            .get(state)
+            .and_then(|transitions| transitions.get(symbol))
+            .map(|state| state.as_str())
+    }
+}
+
+impl Moore<Unsanitary> {
+    /// Creates a Moore machine from its parts. Nothing is validated until the machine is checked.
+    pub fn new(
+        start: String,
+        alphabet: HashSet<String>,
+        outputs: HashMap<String, String>,
+        nodes: HashMap<String, HashMap<String, String>>,
+    ) -> Self {
+        Moore {
+            _sanitized: Unsanitary,
+            start,
+            alphabet,
+            outputs,
+            nodes,
+        }
+    }
+
+    /// Ensures that the machine is valid, that every state has an output, and that every state
+    /// has a transition on every symbol.
+    pub fn check(self) -> Result<Moore<Sanitary>, NfaError> {
+        let Moore {
+            start,
+            alphabet,
+            outputs,
+            nodes,
+            ..
+        } = self;
+
+        check_nodes(&start, &alphabet, &nodes, |state| state)?;
+
+        // ensure that outputs are only given for actual states
+        if let Some(unknown_state) = outputs.keys().find(|&state| !nodes.contains_key(state)) {
+            return Err(NfaError::UnknownState(unknown_state.to_owned()));
+        }
+
+        // ensure that every state has an output
+        if let Some(state) = nodes.keys().find(|&state| !outputs.contains_key(state)) {
+            return Err(NfaError::MissingOutput(state.to_owned()));
+        }
+
+        Ok(Moore {
+            _sanitized: Sanitary,
+            start,
+            alphabet,
+            outputs,
+            nodes,
+        })
+    }
+}
+
+impl Moore<Sanitary> {
+    /// Runs the machine over a word, returning the output of every state visited. This includes
+    /// the start state, so there is always one more output than there are symbols.
+    pub fn run<'a, 'b, I>(&'a self, word: I) -> Result<Vec<&'a str>, NfaError>
+    where
+        I: IntoIterator<Item = &'b str>,
+    {
+        let mut current = &self.start;
+        let mut output = vec![self.outputs[current].as_str()];
+        for letter in word {
+            current = match self.nodes[current].get(letter) {
+                Some(state) => state,
+                None => return Err(NfaError::UnknownSymbol(letter.to_owned())),
+            };
+            output.push(&self.outputs[current]);
+        }
+
+        Ok(output)
+    }
+
+    /// Minimises the machine, merging states that produce the same output for every word.
+    /// Merged states are named after the states they replace, joined with ` | `.
+    pub fn minimise(self) -> Self {
+        let classes = {
+            let outputs = &self.outputs;
+            let equivalent = equivalent_states(&self.alphabet, &self.nodes, |left, right| {
+                outputs[left] != outputs[right]
+            });
+            merge_classes(self.nodes.keys(), equivalent)
+        };
+
+        Moore {
+            _sanitized: Sanitary,
+            start: classes[&self.start].to_owned(),
+            alphabet: self.alphabet,
+            outputs: self.outputs
+                .into_iter()
+                .map(|(state, output)| (classes[&state].to_owned(), output))
+                .collect(),
+            nodes: self.nodes
+                .into_iter()
+                .map(|(state, transitions)| {
+                    (
+                        classes[&state].to_owned(),
+                        transitions
+                            .into_iter()
+                            .map(|(letter, new_state)| (letter, classes[&new_state].to_owned()))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Converts the machine into a Mealy machine, where each transition outputs what the state
+    /// it goes to would have. The output of the start state is lost, as it is produced before
+    /// any transitions are taken.
+    pub fn into_mealy(self) -> Mealy<Sanitary> {
+        let Moore {
+            start,
+            alphabet,
+            outputs,
+            nodes,
+            ..
+        } = self;
+
+        let nodes = nodes
+            .into_iter()
+            .map(|(state, transitions)| {
+                (
+                    state,
+                    transitions
+                        .into_iter()
+                        .map(|(letter, new_state)| {
+                            let output = outputs[&new_state].to_owned();
+                            (
+                                letter,
+                                MealyTransition {
+                                    state: new_state,
+                                    output,
+                                },
+                            )
+                        })
+                        .collect(),
+                )
+            })
+            .collect();
+
+        Mealy {
+            _sanitized: Sanitary,
+            start,
+            alphabet,
+            nodes,
+        }
+    }
+}
+
+impl<T> Mealy<T> {
+    /// The start state.
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// The alphabet of symbols the machine reads.
+    pub fn alphabet(&self) -> &HashSet<String> {
+        &self.alphabet
+    }
+
+    /// The names of all of the states within the machine.
+    pub fn states<'a>(&'a self) -> Keys<'a, String, HashMap<String, MealyTransition>> {
+        self.nodes.keys()
+    }
+
+    /// The transition taken from a state on a single symbol, if there is one.
+    pub fn transition(&self, state: &str, symbol: &str) -> Option<&MealyTransition> {
+        self.nodes
+            .get(state)
+            .and_then(|transitions| transitions.get(symbol))
+    }
+}
+
+impl Mealy<Unsanitary> {
+    /// Creates a Mealy machine from its parts. Nothing is validated until the machine is checked.
+    pub fn new(
+        start: String,
+        alphabet: HashSet<String>,
+        nodes: HashMap<String, HashMap<String, MealyTransition>>,
+    ) -> Self {
+        Mealy {
+            _sanitized: Unsanitary,
+            start,
+            alphabet,
+            nodes,
+        }
+    }
+
+    /// Ensures that the machine is valid, and that every state has a transition on every symbol.
+    pub fn check(self) -> Result<Mealy<Sanitary>, NfaError> {
+        let Mealy {
+            start,
+            alphabet,
+            nodes,
+            ..
+        } = self;
+
+        check_nodes(&start, &alphabet, &nodes, |transition| &transition.state)?;
+
+        Ok(Mealy {
+            _sanitized: Sanitary,
+            start,
+            alphabet,
+            nodes,
+        })
+    }
+}
+
+impl Mealy<Sanitary> {
+    /// Runs the machine over a word, returning the output of every transition taken.
+    pub fn run<'a, 'b, I>(&'a self, word: I) -> Result<Vec<&'a str>, NfaError>
+    where
+        I: IntoIterator<Item = &'b str>,
+    {
+        let mut current = &self.start;
+        let mut output = Vec::new();
+        for letter in word {
+            let transition = match self.nodes[current].get(letter) {
+                Some(transition) => transition,
+                None => return Err(NfaError::UnknownSymbol(letter.to_owned())),
+            };
+            output.push(transition.output.as_str());
+            current = &transition.state;
+        }
+
+        Ok(output)
+    }
+
+    /// Minimises the machine, merging states that produce the same output for every word.
+    /// Merged states are named after the states they replace, joined with ` | `.
+    pub fn minimise(self) -> Self {
+        let classes = {
+            let nodes = &self.nodes;
+            let alphabet = &self.alphabet;
+            let targets: HashMap<_, HashMap<_, _>> = nodes
+                .iter()
+                .map(|(state, transitions)| {
+                    (
+                        state.to_owned(),
+                        transitions
+                            .iter()
+                            .map(|(letter, transition)| {
+                                (letter.to_owned(), transition.state.to_owned())
+                            })
+                            .collect(),
+                    )
+                })
+                .collect();
+            let equivalent = equivalent_states(alphabet, &targets, |left, right| {
+                alphabet
+                    .iter()
+                    .any(|letter| nodes[left][letter].output != nodes[right][letter].output)
+            });
+            merge_classes(targets.keys(), equivalent)
+        };
+
+        Mealy {
+            _sanitized: Sanitary,
+            start: classes[&self.start].to_owned(),
+            alphabet: self.alphabet,
+            nodes: self.nodes
+                .into_iter()
+                .map(|(state, transitions)| {
+                    (
+                        classes[&state].to_owned(),
+                        transitions
+                            .into_iter()
+                            .map(|(letter, transition)| {
+                                (
+                                    letter,
+                                    MealyTransition {
+                                        state: classes[&transition.state].to_owned(),
+                                        output: transition.output,
+                                    },
+                                )
+                            })
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Converts the machine into a Moore machine. Each Moore state is a Mealy state paired with
+    /// the output of the transition used to reach it, named `state / output`, with a number
+    /// added if another pair already has that name. Only reachable pairs are kept. As the start
+    /// state is not reached by any transition, its output has to be given.
+    pub fn into_moore<S: Into<String>>(self, start_output: S) -> Moore<Sanitary> {
+        let mut names = MooreNames::default();
+        let mut work = VecDeque::new();
+        let mut outputs = HashMap::new();
+        let mut nodes = HashMap::new();
+
+        let start_output = start_output.into();
+        let start = names.name(&self.start, &start_output);
+        work.push_back((self.start.as_str(), start_output));
+
+        while let Some((state, output)) = work.pop_front() {
+            let moore_state = names.name(state, &output);
+            if nodes.contains_key(&moore_state) {
+                continue;
+            }
+
+            // in order, so that the same pairs always get the same names
+            let mut letters: Vec<_> = self.nodes[state].iter().collect();
+            letters.sort_by(|x, y| x.0.cmp(y.0));
+            let mut transitions = HashMap::new();
+            for (letter, transition) in letters {
+                work.push_back((transition.state.as_str(), transition.output.to_owned()));
+                transitions.insert(
+                    letter.to_owned(),
+                    names.name(&transition.state, &transition.output),
+                );
+            }
+
+            outputs.insert(moore_state.to_owned(), output);
+            nodes.insert(moore_state, transitions);
+        }
+
+        Moore {
+            _sanitized: Sanitary,
+            start,
+            alphabet: self.alphabet,
+            outputs,
+            nodes,
+        }
+    }
+}
+
+/// The names of the Moore states made from pairs of a Mealy state and an output. A name like
+/// `state / output` can come from more than one pair, such as `a / x` with `y` and `a` with
+/// `x / y`, so later pairs with a taken name have a number added.
+#[derive(Default)]
+struct MooreNames<'a> {
+    names: HashMap<(&'a str, String), String>,
+    taken: HashSet<String>,
+}
+
+impl<'a> MooreNames<'a> {
+    /// The name of the Moore state for a pair, naming it if it is new.
+    fn name(&mut self, state: &'a str, output: &str) -> String {
+        if let Some(name) = self.names.get(&(state, output.to_owned())) {
+            return name.to_owned();
+        }
+        let plain = format!("{} / {}", state, output);
+        let mut name = plain.clone();
+        let mut count = 1;
+        while self.taken.contains(&name) {
+            count += 1;
+            name = format!("{} ({})", plain, count);
+        }
+        self.taken.insert(name.to_owned());
+        self.names.insert((state, output.to_owned()), name.to_owned());
+        name
+    }
+}
+
+#[cfg(test)]
+fn parity_moore() -> Moore<Sanitary> {
+    let input = r#"{
+        "start": "even",
+        "alphabet": ["0", "1"],
+        "outputs": {
+            "even": "E",
+            "odd": "O",
+            "even again": "E"
+        },
+        "nodes": {
+            "even": {
+                "0": "even again",
+                "1": "odd"
+            },
+            "odd": {
+                "0": "odd",
+                "1": "even"
+            },
+            "even again": {
+                "0": "even",
+                "1": "odd"
+            }
+        }
+    }"#;
+    let unsanitary: Moore<_> = serde_json::from_str(input).unwrap();
+    unsanitary.check().unwrap()
+}
+
+#[test]
+fn invalid_moore() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "outputs": { "1": "x" },
+        "nodes": {
+            "1": { "a": "1" }
+        }
+    }"#;
+    let unsanitary: Moore<_> = serde_json::from_str(input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::MissingTransition(state, symbol) => {
+            assert_eq!(state, "1");
+            assert_eq!(symbol, "b");
+        }
+        err @ _ => panic!(err),
+    }
+
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a"],
+        "outputs": {},
+        "nodes": {
+            "1": { "a": "1" }
+        }
+    }"#;
+    let unsanitary: Moore<_> = serde_json::from_str(input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::MissingOutput(state) => assert_eq!(state, "1"),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn invalid_mealy() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a"],
+        "nodes": {
+            "1": { "a": { "state": "2", "output": "x" } }
+        }
+    }"#;
+    let unsanitary: Mealy<_> = serde_json::from_str(input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::UnknownState(state) => assert_eq!(state, "2"),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn run_and_minimise_moore() {
+    let moore = parity_moore();
+    assert_eq!(
+        moore.run(vec!["1", "0", "1", "1"]).unwrap(),
+        vec!["E", "O", "O", "E", "O"]
+    );
+
+    let minimised = moore.minimise();
+    assert_eq!(minimised.states().count(), 2);
+    assert_eq!(minimised.start(), "even | even again");
+    assert_eq!(minimised.transition("even | even again", "0"), Some("even | even again"));
+    assert_eq!(
+        minimised.run(vec!["1", "0", "1", "1"]).unwrap(),
+        vec!["E", "O", "O", "E", "O"]
+    );
+}
+
+#[test]
+fn run_and_minimise_mealy() {
+    let input = r#"{
+        "start": "1",
+        "alphabet": ["a", "b"],
+        "nodes": {
+            "1": {
+                "a": { "state": "2", "output": "x" },
+                "b": { "state": "1", "output": "y" }
+            },
+            "2": {
+                "a": { "state": "3", "output": "x" },
+                "b": { "state": "1", "output": "y" }
+            },
+            "3": {
+                "a": { "state": "3", "output": "z" },
+                "b": { "state": "1", "output": "y" }
+            }
+        }
+    }"#;
+    let unsanitary: Mealy<_> = serde_json::from_str(input).unwrap();
+    let mealy = unsanitary.check().unwrap();
+    assert_eq!(
+        mealy.run(vec!["a", "a", "a", "b", "a"]).unwrap(),
+        vec!["x", "x", "z", "y", "x"]
+    );
+    match mealy.run(vec!["c"]).unwrap_err() {
+        NfaError::UnknownSymbol(symbol) => assert_eq!(symbol, "c"),
+        err @ _ => panic!(err),
+    }
+
+    // 1 and 2 only differ in what the second `a` outputs, so nothing can be merged
+    let minimised = mealy.minimise();
+    assert_eq!(minimised.states().count(), 3);
+
+    let minimised = parity_moore().into_mealy().minimise();
+    assert_eq!(minimised.states().count(), 2);
+}
+
+#[test]
+fn convert_moore_and_mealy() {
+    let moore = parity_moore();
+    let word = vec!["0", "1", "1", "0", "1"];
+    let expected = moore.run(word.iter().cloned()).unwrap()[1..]
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+
+    let mealy = moore.into_mealy();
+    assert_eq!(mealy.run(word.iter().cloned()).unwrap(), expected);
+
+    let moore = mealy.into_moore("E");
+    assert_eq!(moore.start(), "even / E");
+    let output = moore.run(word.iter().cloned()).unwrap();
+    assert_eq!(output[0], "E");
+    assert_eq!(&output[1..], expected.as_slice());
+
+    // the pairs `a / x` and `y`, and `a` and `x / y`, would both be named `a / x / y`
+    let input = r#"{
+        "start": "a",
+        "alphabet": ["0", "1"],
+        "nodes": {
+            "a": {
+                "0": { "state": "a / x", "output": "y" },
+                "1": { "state": "a", "output": "x / y" }
+            },
+            "a / x": {
+                "0": { "state": "a", "output": "x / y" },
+                "1": { "state": "a / x", "output": "y" }
+            }
+        }
+    }"#;
+    let unsanitary: Mealy<_> = serde_json::from_str(input).unwrap();
+    let mealy = unsanitary.check().unwrap();
+    let moore = mealy.into_moore("s");
+    assert_eq!(moore.states().count(), 3);
+    let mut names: Vec<_> = moore.states().cloned().collect();
+    names.sort();
+    assert_eq!(names, vec!["a / s", "a / x / y", "a / x / y (2)"]);
+    assert_eq!(
+        moore.run(vec!["0", "0", "1", "1"]).unwrap(),
+        vec!["s", "y", "x / y", "x / y", "x / y"]
+    );
+}
@@ -0,0 +1,426 @@
+//! Deterministic single tape Turing machines.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::iter;
+
+use automata::{NfaError, Sanitary, Unsanitary};
+use limits::{Budget, Limit, Limits};
+
+#[cfg(test)]
+use serde_json;
+
+/// Which way the head moves after a transition.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum Move {
+    /// One cell to the left.
+    #[serde(rename = "L")]
+    Left,
+    /// One cell to the right.
+    #[serde(rename = "R")]
+    Right,
+    /// Stay on the same cell.
+    #[serde(rename = "S")]
+    Stay,
+}
+
+/// A single transition of a Turing machine.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TuringTransition {
+    /// The state the transition goes to.
+    pub state: String,
+
+    /// The symbol written over the one under the head.
+    pub write: String,
+
+    /// Where the head goes once the symbol is written.
+    #[serde(rename = "move")]
+    pub direction: Move,
+}
+
+/// Deterministic Turing machine. The machine halts as soon as it enters a final state, or when
+/// it has no transition for the current state and symbol.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TuringMachine<T> {
+    /// Marker to ensure that the state machine has been validated before it is used for any
+    /// computations.
+    #[serde(skip_serializing)]
+    _sanitized: T,
+
+    /// Start state.
+    start: String,
+
+    /// The final (accepting) states of the machine.
+    final_states: HashSet<String>,
+
+    /// The alphabet of symbols input words are made from.
+    alphabet: HashSet<String>,
+
+    /// The symbols that can be on the tape, including all of the input symbols and the blank.
+    tape_alphabet: HashSet<String>,
+
+    /// The symbol every cell of the tape starts with, apart from those holding the input.
+    blank: String,
+
+    /// The nodes within the machine. Each node has mappings from tape symbols to transitions.
+    nodes: HashMap<String, HashMap<String, TuringTransition>>,
+}
+
+/// How a run of a Turing machine ended.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TuringOutcome {
+    /// The machine reached a final state.
+    Accept,
+    /// The machine halted outside of a final state.
+    Reject,
+    /// The machine was still running when the step limit was reached.
+    Timeout,
+}
+
+/// The state of a Turing machine between steps.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TapeSnapshot {
+    /// The current state.
+    pub state: String,
+
+    /// Every cell of the tape that has been visited or holds input, from left to right.
+    pub tape: Vec<String>,
+
+    /// The index into `tape` of the cell under the head.
+    pub head: usize,
+
+    /// The index into `tape` of the cell the input started on. This moves right as the head
+    /// visits cells left of the input.
+    pub origin: usize,
+}
+
+/// The result of running a Turing machine over a word.
+#[derive(Debug, Serialize)]
+pub struct TuringRun {
+    /// How the run ended.
+    pub outcome: TuringOutcome,
+
+    /// The machine before the first step, and after every step.
+    pub steps: Vec<TapeSnapshot>,
+}
+
+impl<T> TuringMachine<T> {
+    /// The start state.
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// The final (accepting) states of the machine.
+    pub fn final_states(&self) -> &HashSet<String> {
+        &self.final_states
+    }
+
+    /// The alphabet of symbols input words are made from.
+    pub fn alphabet(&self) -> &HashSet<String> {
+        &self.alphabet
+    }
+
+    /// The symbols that can be on the tape.
+    pub fn tape_alphabet(&self) -> &HashSet<String> {
+        &self.tape_alphabet
+    }
+
+    /// The symbol every cell of the tape starts with, apart from those holding the input.
+    pub fn blank(&self) -> &str {
+        &self.blank
+    }
+
+    /// The transition taken from a state when reading a symbol, if there is one.
+    pub fn transition(&self, state: &str, symbol: &str) -> Option<&TuringTransition> {
+        self.nodes
+            .get(state)
+            .and_then(|transitions| transitions.get(symbol))
+    }
+}
+
+impl TuringMachine<Unsanitary> {
+    /// Ensures that the machine is valid, and that relevant invariants within the structure hold.
+    pub fn check(self) -> Result<TuringMachine<Sanitary>, NfaError> {
+        let TuringMachine {
+            start,
+            final_states,
+            alphabet,
+            tape_alphabet,
+            blank,
+            nodes,
+            ..
+        } = self;
+
+        // ensure that the start state is a valid state
+        if !nodes.contains_key(&start) {
+            return Err(NfaError::UnknownState(start));
+        }
+
+        // ensure that all final states are listed as actual states
+        if let Some(unknown_state) = final_states
+            .iter()
+            .find(|&state| !nodes.contains_key(state))
+        {
+            return Err(NfaError::UnknownState(unknown_state.to_owned()));
+        }
+
+        // the blank can be written, but never be part of the input
+        if alphabet.contains(&blank) {
+            return Err(NfaError::InvalidSymbol(blank));
+        }
+
+        // ensure that everything that can end up on the tape is a tape symbol
+        if let Some(unknown_symbol) = iter::once(&blank)
+            .chain(&alphabet)
+            .find(|&symbol| !tape_alphabet.contains(symbol))
+        {
+            return Err(NfaError::UnknownSymbol(unknown_symbol.to_owned()));
+        }
+
+        for transitions in nodes.values() {
+            for (symbol, transition) in transitions {
+                // ensure that all state transitions are on, and write, valid symbols
+                if let Some(unknown_symbol) = iter::once(symbol)
+                    .chain(iter::once(&transition.write))
+                    .find(|&symbol| !tape_alphabet.contains(symbol))
+                {
+                    return Err(NfaError::UnknownSymbol(unknown_symbol.to_owned()));
+                }
+
+                // ensure that all state transitions are to valid states
+                if !nodes.contains_key(&transition.state) {
+                    return Err(NfaError::UnknownState(transition.state.to_owned()));
+                }
+            }
+        }
+
+        if let Some(invalid_state) = nodes.keys().find(|state| state.is_empty()) {
+            return Err(NfaError::InvalidStateName(invalid_state.to_owned()));
+        }
+
+        Ok(TuringMachine {
+            _sanitized: Sanitary,
+            start,
+            final_states,
+            alphabet,
+            tape_alphabet,
+            blank,
+            nodes,
+        })
+    }
+}
+
+impl TuringMachine<Sanitary> {
+    /// Runs the machine over a word for at most `max_steps` steps, recording the tape after
+    /// every step. Use [`Limits::run_turing`](struct.Limits.html#method.run_turing) to also bound
+    /// the length of the tape and the time taken.
+    pub fn run<'a, I>(&self, word: I, max_steps: usize) -> Result<TuringRun, NfaError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.execute(word, max_steps, None, &Budget::unlimited())
+    }
+
+    /// Runs the machine, failing if the tape would grow past `max_tape` cells or the budget runs
+    /// out. As every step records the whole tape, this bounds the memory used along with
+    /// `max_steps`.
+    pub(crate) fn execute<'a, I>(
+        &self,
+        word: I,
+        max_steps: usize,
+        max_tape: Option<usize>,
+        budget: &Budget,
+    ) -> Result<TuringRun, NfaError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut tape: VecDeque<&str> = VecDeque::new();
+        for symbol in word {
+            match self.alphabet.get(symbol) {
+                Some(symbol) => tape.push_back(symbol),
+                None => return Err(NfaError::UnknownSymbol(symbol.to_owned())),
+            }
+            Limits::ensure(max_tape, tape.len(), Limit::TapeLength)?;
+        }
+        if tape.is_empty() {
+            tape.push_back(&self.blank);
+        }
+
+        let mut state = &self.start;
+        let mut head = 0;
+        let mut origin = 0;
+        let snapshot = |state: &String, tape: &VecDeque<&str>, head, origin| TapeSnapshot {
+            state: state.to_owned(),
+            tape: tape.iter().map(|x| x.to_string()).collect(),
+            head,
+            origin,
+        };
+        let mut steps = vec![snapshot(state, &tape, head, origin)];
+
+        let outcome = loop {
+            if self.final_states.contains(state) {
+                break TuringOutcome::Accept;
+            }
+            let transition = match self.nodes[state].get(tape[head]) {
+                Some(transition) => transition,
+                None => break TuringOutcome::Reject,
+            };
+            if steps.len() > max_steps {
+                break TuringOutcome::Timeout;
+            }
+            budget.check()?;
+
+            tape[head] = &transition.write;
+            state = &transition.state;
+            match transition.direction {
+                Move::Left if head == 0 => {
+                    tape.push_front(&self.blank);
+                    origin += 1;
+                }
+                Move::Left => head -= 1,
+                Move::Right => {
+                    head += 1;
+                    if head == tape.len() {
+                        tape.push_back(&self.blank);
+                    }
+                }
+                Move::Stay => {}
+            }
+            Limits::ensure(max_tape, tape.len(), Limit::TapeLength)?;
+
+            steps.push(snapshot(state, &tape, head, origin));
+        };
+
+        Ok(TuringRun { outcome, steps })
+    }
+}
+
+#[cfg(test)]
+fn binary_increment() -> TuringMachine<Sanitary> {
+    let input = r#"{
+        "start": "right",
+        "final_states": ["done"],
+        "alphabet": ["0", "1"],
+        "tape_alphabet": ["0", "1", "_"],
+        "blank": "_",
+        "nodes": {
+            "right": {
+                "0": { "state": "right", "write": "0", "move": "R" },
+                "1": { "state": "right", "write": "1", "move": "R" },
+                "_": { "state": "carry", "write": "_", "move": "L" }
+            },
+            "carry": {
+                "1": { "state": "carry", "write": "0", "move": "L" },
+                "0": { "state": "done", "write": "1", "move": "S" },
+                "_": { "state": "done", "write": "1", "move": "S" }
+            },
+            "done": {}
+        }
+    }"#;
+    let unsanitary: TuringMachine<_> = serde_json::from_str(input).unwrap();
+    unsanitary.check().unwrap()
+}
+
+#[test]
+fn run_turing_machine() {
+    let machine = binary_increment();
+
+    let run = machine.run(vec!["1", "0", "1", "1"], 100).unwrap();
+    assert_eq!(run.outcome, TuringOutcome::Accept);
+    let last = run.steps.last().unwrap();
+    assert_eq!(last.state, "done");
+    assert_eq!(last.tape, vec!["1", "1", "0", "0", "_"]);
+    assert_eq!(last.head, 1);
+    assert_eq!(last.origin, 0);
+    assert_eq!(run.steps.len(), 9);
+
+    // carrying past the left end of the input grows the tape leftwards
+    let run = machine.run(vec!["1", "1"], 100).unwrap();
+    assert_eq!(run.outcome, TuringOutcome::Accept);
+    let last = run.steps.last().unwrap();
+    assert_eq!(last.tape, vec!["1", "0", "0", "_"]);
+    assert_eq!(last.head, 0);
+    assert_eq!(last.origin, 1);
+
+    let run = machine.run(vec!["1", "1"], 3).unwrap();
+    assert_eq!(run.outcome, TuringOutcome::Timeout);
+    assert_eq!(run.steps.len(), 4);
+
+    // the tape is bounded both as input and as it grows
+    let limits = Limits {
+        max_tape_length: Some(4),
+        ..Limits::default()
+    };
+    let run = limits.run_turing(&machine, vec!["1", "1"], 100).unwrap();
+    assert_eq!(run.outcome, TuringOutcome::Accept);
+    match limits
+        .run_turing(&machine, vec!["1", "0", "1", "0", "1"], 100)
+        .unwrap_err()
+    {
+        NfaError::LimitExceeded(limit) => assert_eq!(limit, Limit::TapeLength(4)),
+        err @ _ => panic!(err),
+    }
+    match limits
+        .run_turing(&machine, vec!["1", "0", "1", "1"], 100)
+        .unwrap_err()
+    {
+        NfaError::LimitExceeded(limit) => assert_eq!(limit, Limit::TapeLength(4)),
+        err @ _ => panic!(err),
+    }
+
+    match machine.run(vec!["2"], 100).unwrap_err() {
+        NfaError::UnknownSymbol(err) => assert_eq!(err, "2"),
+        err @ _ => panic!(err),
+    }
+}
+
+#[test]
+fn reject_turing_machine() {
+    let input = r#"{
+        "start": "1",
+        "final_states": [],
+        "alphabet": ["a"],
+        "tape_alphabet": ["a", "_"],
+        "blank": "_",
+        "nodes": {
+            "1": { "a": { "state": "1", "write": "a", "move": "R" } }
+        }
+    }"#;
+    let unsanitary: TuringMachine<_> = serde_json::from_str(input).unwrap();
+    let run = unsanitary.check().unwrap().run(vec!["a", "a"], 100).unwrap();
+    assert_eq!(run.outcome, TuringOutcome::Reject);
+    assert_eq!(run.steps.len(), 3);
+}
+
+#[test]
+fn invalid_turing_machine() {
+    let input = r#"{
+        "start": "1",
+        "final_states": [],
+        "alphabet": ["a", "_"],
+        "tape_alphabet": ["a", "_"],
+        "blank": "_",
+        "nodes": { "1": {} }
+    }"#;
+    let unsanitary: TuringMachine<_> = serde_json::from_str(input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::InvalidSymbol(err) => assert_eq!(err, "_"),
+        err @ _ => panic!(err),
+    }
+
+    let input = r#"{
+        "start": "1",
+        "final_states": [],
+        "alphabet": ["a"],
+        "tape_alphabet": ["a", "_"],
+        "blank": "_",
+        "nodes": {
+            "1": { "a": { "state": "1", "write": "b", "move": "R" } }
+        }
+    }"#;
+    let unsanitary: TuringMachine<_> = serde_json::from_str(input).unwrap();
+    match unsanitary.check().unwrap_err() {
+        NfaError::UnknownSymbol(err) => assert_eq!(err, "b"),
+        err @ _ => panic!(err),
+    }
+}
@@ -0,0 +1,667 @@
+//! Weighted finite automata, which give every word a weight, such as a cost or a probability,
+//! rather than just accepting or rejecting it.
+//!
+//! Weights come from a [`Semiring`](trait.Semiring.html): the weight of a path is the product
+//! of the weights along it, and the weight of a word is the sum of the weights of its paths.
+//! With the [`Boolean`](struct.Boolean.html) semiring this is an ordinary NFA, with the
+//! [`Tropical`](struct.Tropical.html) semiring a word weighs as much as its cheapest path, and
+//! with the [`Probability`](struct.Probability.html) semiring the weights of its paths add up.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::collections::hash_map::Keys;
+use std::f64;
+use std::fmt;
+use std::iter;
+
+use automata::{Nfa, NfaError, Sanitary, Unsanitary};
+use limits::{Budget, Limit, Limits};
+use symbols;
+
+/// A way of combining weights. `plus` combines the weights of alternative paths and `times`
+/// those of consecutive steps, so `zero` is the weight of no path at all and `one` the weight of
+/// an empty step.
+pub trait Semiring: Copy + PartialEq + fmt::Debug + fmt::Display {
+    /// The identity of `plus`, which `times` turns everything into.
+    fn zero() -> Self;
+
+    /// The identity of `times`.
+    fn one() -> Self;
+
+    /// Combines the weights of alternative paths.
+    fn plus(self, other: Self) -> Self;
+
+    /// Combines the weights of consecutive steps of a path.
+    fn times(self, other: Self) -> Self;
+
+    /// Whether a path of this weight is better than one of another weight.
+    fn better(self, other: Self) -> bool;
+}
+
+/// A semiring where a weight can be divided by any weight but zero, so that
+/// `a.times(b).divide(a) == b`. Determinisation needs this, to push weights forwards.
+pub trait Divisible: Semiring {
+    /// Divides the weight by another on the left.
+    fn divide(self, divisor: Self) -> Self;
+}
+
+/// Whether a word is accepted, which makes a weighted automaton an ordinary NFA.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Boolean(pub bool);
+
+/// Costs, where a word costs as much as its cheapest path: the min-plus semiring. Paths without
+/// any cost have weight `0`, and missing paths an infinite one.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Tropical(pub f64);
+
+/// Probabilities, where the probabilities of the paths of a word add up.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Probability(pub f64);
+
+impl Semiring for Boolean {
+    fn zero() -> Self {
+        Boolean(false)
+    }
+
+    fn one() -> Self {
+        Boolean(true)
+    }
+
+    fn plus(self, other: Self) -> Self {
+        Boolean(self.0 || other.0)
+    }
+
+    fn times(self, other: Self) -> Self {
+        Boolean(self.0 && other.0)
+    }
+
+    fn better(self, other: Self) -> bool {
+        self.0 && !other.0
+    }
+}
+
+impl Divisible for Boolean {
+    fn divide(self, _divisor: Self) -> Self {
+        self
+    }
+}
+
+impl Semiring for Tropical {
+    fn zero() -> Self {
+        Tropical(f64::INFINITY)
+    }
+
+    fn one() -> Self {
+        Tropical(0.0)
+    }
+
+    fn plus(self, other: Self) -> Self {
+        Tropical(self.0.min(other.0))
+    }
+
+    fn times(self, other: Self) -> Self {
+        Tropical(self.0 + other.0)
+    }
+
+    fn better(self, other: Self) -> bool {
+        self.0 < other.0
+    }
+}
+
+impl Divisible for Tropical {
+    fn divide(self, divisor: Self) -> Self {
+        Tropical(self.0 - divisor.0)
+    }
+}
+
+impl Semiring for Probability {
+    fn zero() -> Self {
+        Probability(0.0)
+    }
+
+    fn one() -> Self {
+        Probability(1.0)
+    }
+
+    fn plus(self, other: Self) -> Self {
+        Probability(self.0 + other.0)
+    }
+
+    fn times(self, other: Self) -> Self {
+        Probability(self.0 * other.0)
+    }
+
+    /// The most likely path is the best, as in the Viterbi algorithm.
+    fn better(self, other: Self) -> bool {
+        self.0 > other.0
+    }
+}
+
+impl Divisible for Probability {
+    fn divide(self, divisor: Self) -> Self {
+        Probability(self.0 / divisor.0)
+    }
+}
+
+impl fmt::Display for Boolean {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Tropical {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Probability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Weighted finite automata, with the same states and alphabet as an
+/// [`Nfa`](struct.Nfa.html), but with a weight on every transition and final state.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Wfa<T, W> {
+    /// Marker to ensure that the automata has been validated before it is used for any
+    /// computations.
+    #[serde(skip_serializing)]
+    _sanitized: T,
+
+    /// Start state.
+    start: String,
+
+    /// The weight of ending a word in each final state. Other states have weight zero.
+    final_weights: HashMap<String, W>,
+
+    /// The alphabet of symbols the automata reads.
+    alphabet: HashSet<String>,
+
+    /// The nodes within the automata. Each node has mappings from alphabet symbols to the
+    /// states they lead to, with the weight of each transition.
+    nodes: HashMap<String, HashMap<String, HashMap<String, W>>>,
+}
+
+/// A path through a weighted automata, from the start state to a final state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WeightedPath<W> {
+    /// The states along the path, starting with the start state.
+    pub states: Vec<String>,
+
+    /// The symbols of the transitions along the path.
+    pub word: Vec<String>,
+
+    /// The weight of the path, including the weight of its final state.
+    pub weight: W,
+}
+
+impl<T, W> Wfa<T, W> {
+    /// The start state.
+    pub fn start(&self) -> &str {
+        &self.start
+    }
+
+    /// The weight of ending a word in each final state.
+    pub fn final_weights(&self) -> &HashMap<String, W> {
+        &self.final_weights
+    }
+
+    /// The alphabet of symbols the automata reads.
+    pub fn alphabet(&self) -> &HashSet<String> {
+        &self.alphabet
+    }
+
+    /// The names of all of the states within the automata.
+    pub fn states<'a>(&'a self) -> Keys<'a, String, HashMap<String, HashMap<String, W>>> {
+        self.nodes.keys()
+    }
+
+    /// The transitions out of a state, as a mapping from symbols to the weight of each state they
+    /// lead to. Returns `None` if there is no such state.
+    pub fn transitions(&self, state: &str) -> Option<&HashMap<String, HashMap<String, W>>> {
+        self.nodes.get(state)
+    }
+}
+
+impl<W> Wfa<Unsanitary, W> {
+    /// Creates a weighted automata from its parts. Nothing is validated until it is checked.
+    pub fn new(
+        start: String,
+        final_weights: HashMap<String, W>,
+        alphabet: HashSet<String>,
+        nodes: HashMap<String, HashMap<String, HashMap<String, W>>>,
+    ) -> Self {
+        Wfa {
+            _sanitized: Unsanitary,
+            start,
+            final_weights,
+            alphabet,
+            nodes,
+        }
+    }
+
+    /// Ensures that the automata is valid, with the same rules as
+    /// [`Nfa::check`](struct.Nfa.html#method.check).
+    pub fn check(self) -> Result<Wfa<Sanitary, W>, NfaError> {
+        let Wfa {
+            start,
+            final_weights,
+            alphabet,
+            nodes,
+            ..
+        } = self;
+
+        if let Some(invalid_state) = nodes.keys().find(|state| state.is_empty()) {
+            return Err(NfaError::InvalidStateName(invalid_state.to_owned()));
+        }
+        if let Some(unknown_state) = Some(&start)
+            .into_iter()
+            .chain(final_weights.keys())
+            .chain(
+                nodes
+                    .values()
+                    .flat_map(|transitions| transitions.values())
+                    .flat_map(|targets| targets.keys()),
+            )
+            .find(|&state| !nodes.contains_key(state))
+        {
+            return Err(NfaError::UnknownState(unknown_state.to_owned()));
+        }
+
+        let symbols = symbols::check_alphabet(&alphabet)?;
+        for transitions in nodes.values() {
+            for symbol in transitions.keys() {
+                if !alphabet.contains(symbol) {
+                    symbols::check_label(symbol, &symbols)?;
+                }
+            }
+        }
+
+        Ok(Wfa {
+            _sanitized: Sanitary,
+            start,
+            final_weights,
+            alphabet,
+            nodes,
+        })
+    }
+}
+
+impl<W: Semiring> Wfa<Sanitary, W> {
+    /// The weight of a word: the sum over its paths of the product of the weights along them.
+    /// Words that the automata has no path for weigh zero.
+    pub fn weight<'a, I>(&self, word: I) -> Result<W, NfaError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut current: HashMap<&String, W> = iter::once((&self.start, W::one())).collect();
+        for letter in word {
+            if !symbols::in_alphabet(&self.alphabet, letter) {
+                return Err(NfaError::UnknownSymbol(letter.to_owned()));
+            }
+            let mut next: HashMap<&String, W> = HashMap::new();
+            for (state, weight) in current {
+                for targets in symbols::follow(&self.nodes[state], letter) {
+                    for (target, &step) in targets {
+                        let total = next.entry(target).or_insert_with(W::zero);
+                        *total = total.plus(weight.times(step));
+                    }
+                }
+            }
+            current = next;
+        }
+
+        Ok(current
+            .into_iter()
+            .filter_map(|(state, weight)| {
+                self.final_weights
+                    .get(state)
+                    .map(|&end| weight.times(end))
+            })
+            .fold(W::zero(), W::plus))
+    }
+
+    /// Finds the best path from the start state to a final state, such as the cheapest one or
+    /// the most likely one. This is Dijkstra's algorithm, so it needs weights that never make
+    /// a path better by taking another step, such as costs that are not negative, or
+    /// probabilities no more than 1. Returns `None` if no final state can be reached.
+    pub fn best_path(&self) -> Option<WeightedPath<W>> {
+        let mut best: HashMap<&str, (W, Option<(&str, &str)>)> = HashMap::new();
+        best.insert(&self.start, (W::one(), None));
+        let mut settled = HashSet::new();
+        loop {
+            let next = best.iter()
+                .filter(|&(state, _)| !settled.contains(state))
+                .fold(None, |best: Option<(&str, W)>, (&state, &(weight, _))| match best {
+                    Some((_, best_weight)) if !weight.better(best_weight) => best,
+                    _ => Some((state, weight)),
+                });
+            let (state, weight) = match next {
+                Some(next) => next,
+                None => break,
+            };
+            settled.insert(state);
+            for (symbol, targets) in &self.nodes[state] {
+                for (target, &step) in targets {
+                    let total = weight.times(step);
+                    if total == W::zero() || settled.contains(target.as_str()) {
+                        continue;
+                    }
+                    let improves = match best.get(target.as_str()) {
+                        Some(&(current, _)) => total.better(current),
+                        None => true,
+                    };
+                    if improves {
+                        best.insert(target, (total, Some((state, symbol))));
+                    }
+                }
+            }
+        }
+
+        let end = self.final_weights
+            .iter()
+            .filter_map(|(state, &end)| {
+                best.get(state.as_str())
+                    .map(|&(weight, _)| (state.as_str(), weight.times(end)))
+            })
+            .filter(|&(_, weight)| weight != W::zero())
+            .fold(None, |best: Option<(&str, W)>, (state, weight)| match best {
+                Some((_, best_weight)) if !weight.better(best_weight) => best,
+                _ => Some((state, weight)),
+            });
+        let (end, weight) = match end {
+            Some(end) => end,
+            None => return None,
+        };
+
+        let mut states = vec![end.to_owned()];
+        let mut word = Vec::new();
+        let mut state = end;
+        while let Some((previous, symbol)) = best[state].1 {
+            states.push(previous.to_owned());
+            word.push(symbol.to_owned());
+            state = previous;
+        }
+        states.reverse();
+        word.reverse();
+        Some(WeightedPath {
+            states,
+            word,
+            weight,
+        })
+    }
+}
+
+impl<W: Divisible> Wfa<Sanitary, W> {
+    /// Converts the automata into an equivalent deterministic one, with a single transition per
+    /// state and symbol, by the weighted subset construction. Each state of the result is a set
+    /// of states, each with the weight still owed on reaching it, which is left out when it is
+    /// one. The states are joined with ` + `, like those of
+    /// [`Nfa::make_deterministic`](struct.Nfa.html#method.make_deterministic), so with the
+    /// boolean semiring the result has the same states, apart from the dead state.
+    ///
+    /// Unlike for NFAs, there are weighted automata with no deterministic equivalent, for which
+    /// this would never finish, so it should be given a limit on the states or the time.
+    pub fn make_deterministic(&self, limits: &Limits) -> Result<Wfa<Sanitary, W>, NfaError> {
+        Limits::ensure(limits.max_nfa_states, self.nodes.len(), Limit::NfaStates)?;
+        let budget = Budget::start(limits);
+
+        let atoms: Vec<(String, String)> = symbols::minterms(
+            self.alphabet
+                .iter()
+                .chain(self.nodes.values().flat_map(|transitions| transitions.keys())),
+        ).into_iter()
+            .map(|(label, set)| (label, set.representative().unwrap()))
+            .collect();
+        Limits::ensure(limits.max_alphabet_size, atoms.len(), Limit::AlphabetSize)?;
+
+        let start: BTreeMap<&str, W> = iter::once((self.start.as_str(), W::one())).collect();
+        let mut names = HashSet::new();
+        names.insert(subset_name(&start));
+        let mut work = VecDeque::new();
+        work.push_back(start);
+        let mut final_weights = HashMap::new();
+        let mut nodes = HashMap::new();
+
+        while let Some(subset) = work.pop_front() {
+            budget.check()?;
+            let name = subset_name(&subset);
+
+            let end = subset
+                .iter()
+                .filter_map(|(&state, &owed)| {
+                    self.final_weights
+                        .get(state)
+                        .map(|&end| owed.times(end))
+                })
+                .fold(W::zero(), W::plus);
+            if end != W::zero() {
+                final_weights.insert(name.to_owned(), end);
+            }
+
+            let mut transitions = HashMap::new();
+            for &(ref label, ref symbol) in &atoms {
+                let mut reached: BTreeMap<&str, W> = BTreeMap::new();
+                for (&state, &owed) in &subset {
+                    for targets in symbols::follow(&self.nodes[state], symbol) {
+                        for (target, &step) in targets {
+                            let total = reached.entry(target.as_str()).or_insert_with(W::zero);
+                            *total = total.plus(owed.times(step));
+                        }
+                    }
+                }
+
+                // the transition takes as much weight as all of the paths agree on, and each
+                // state owes the rest
+                let weight = reached.values().fold(W::zero(), |total, &weight| total.plus(weight));
+                if weight == W::zero() {
+                    continue;
+                }
+                let next: BTreeMap<&str, W> = reached
+                    .into_iter()
+                    .filter(|&(_, reached)| reached != W::zero())
+                    .map(|(state, reached)| (state, reached.divide(weight)))
+                    .collect();
+                let next_name = subset_name(&next);
+                if names.insert(next_name.to_owned()) {
+                    Limits::ensure(limits.max_dfa_states, names.len(), Limit::DfaStates)?;
+                    work.push_back(next);
+                }
+                transitions.insert(
+                    label.to_owned(),
+                    iter::once((next_name, weight)).collect(),
+                );
+            }
+            nodes.insert(name, transitions);
+        }
+
+        Ok(Wfa {
+            _sanitized: Sanitary,
+            start: self.start.to_owned(),
+            final_weights,
+            alphabet: atoms.into_iter().map(|(label, _)| label).collect(),
+            nodes,
+        })
+    }
+}
+
+impl From<Nfa<Sanitary>> for Wfa<Sanitary, Boolean> {
+    /// Gives every transition and final state of an NFA the weight `true`.
+    fn from(nfa: Nfa<Sanitary>) -> Self {
+        Wfa {
+            _sanitized: Sanitary,
+            start: nfa.start,
+            final_weights: nfa.final_states
+                .into_iter()
+                .map(|state| (state, Boolean(true)))
+                .collect(),
+            alphabet: nfa.alphabet,
+            nodes: nfa.nodes
+                .into_iter()
+                .map(|(state, transitions)| {
+                    let transitions = transitions
+                        .into_iter()
+                        .map(|(symbol, targets)| {
+                            let targets = targets
+                                .into_iter()
+                                .map(|target| (target, Boolean(true)))
+                                .collect();
+                            (symbol, targets)
+                        })
+                        .collect();
+                    (state, transitions)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Names a state of a determinised automata after the states in it, and what they are owed.
+fn subset_name<W: Semiring>(subset: &BTreeMap<&str, W>) -> String {
+    subset
+        .iter()
+        .map(|(&state, &owed)| if owed == W::one() {
+            state.to_owned()
+        } else {
+            format!("{} ({})", state, owed)
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+#[cfg(test)]
+use serde_json;
+
+#[test]
+fn wfa_tropical() {
+    let wfa: Wfa<Unsanitary, Tropical> = serde_json::from_str(
+        r#"{
+            "start": "1",
+            "alphabet": ["a", "b"],
+            "nodes": {
+                "1": { "a": { "2": 1, "3": 4 }, "b": { "3": 6 } },
+                "2": { "b": { "3": 2 } },
+                "3": {}
+            },
+            "final_weights": { "3": 0.5 }
+        }"#,
+    ).unwrap();
+    let wfa = wfa.check().unwrap();
+    assert_eq!(wfa.weight(vec!["a"]).unwrap(), Tropical(4.5));
+    assert_eq!(wfa.weight(vec!["a", "b"]).unwrap(), Tropical(3.5));
+    assert_eq!(wfa.weight(vec!["b", "b"]).unwrap(), Tropical::zero());
+    assert!(wfa.weight(vec!["c"]).is_err());
+
+    assert_eq!(
+        wfa.best_path(),
+        Some(WeightedPath {
+            states: vec!["1".to_string(), "2".to_string(), "3".to_string()],
+            word: vec!["a".to_string(), "b".to_string()],
+            weight: Tropical(3.5),
+        })
+    );
+
+    // determinising pushes the weight the paths agree on to the front
+    let dfa = wfa.make_deterministic(&Limits::default()).unwrap();
+    assert_eq!(dfa.transitions("1").unwrap()["a"]["2 + 3 (3)"], Tropical(1.0));
+    for word in vec![vec!["a"], vec!["a", "b"], vec!["b"], vec!["b", "b"]] {
+        assert_eq!(
+            dfa.weight(word.clone()).unwrap(),
+            wfa.weight(word).unwrap()
+        );
+    }
+}
+
+#[test]
+fn wfa_probability() {
+    let wfa: Wfa<Unsanitary, Probability> = serde_json::from_str(
+        r#"{
+            "start": "1",
+            "alphabet": ["a", "b", "c"],
+            "nodes": {
+                "1": { "a": { "2": 0.5, "3": 0.5 } },
+                "2": { "b": { "4": 0.4 }, "c": { "4": 0.6 } },
+                "3": { "b": { "4": 0.2 }, "c": { "4": 0.8 } },
+                "4": {}
+            },
+            "final_weights": { "1": 0.1, "4": 1 }
+        }"#,
+    ).unwrap();
+    let wfa = wfa.check().unwrap();
+    let close = |left: Probability, right: f64| (left.0 - right).abs() < 1e-9;
+    assert!(close(wfa.weight(vec![]).unwrap(), 0.1));
+    assert!(close(wfa.weight(vec!["a", "b"]).unwrap(), 0.3));
+    assert!(close(wfa.weight(vec!["a", "c"]).unwrap(), 0.7));
+    assert_eq!(wfa.weight(vec!["b"]).unwrap(), Probability(0.0));
+
+    let path = wfa.best_path().unwrap();
+    assert_eq!(path.word, vec!["a", "c"]);
+    assert_eq!(path.states, vec!["1", "3", "4"]);
+    assert!(close(path.weight, 0.4));
+
+    let dfa = wfa.make_deterministic(&Limits::default()).unwrap();
+    assert_eq!(dfa.states().count(), 3);
+    for word in vec![vec![], vec!["a", "b"], vec!["a", "c"], vec!["a"]] {
+        assert!(close(
+            dfa.weight(word.clone()).unwrap(),
+            wfa.weight(word).unwrap().0
+        ));
+    }
+}
+
+#[test]
+fn wfa_boolean_make_deterministic() {
+    let nfa: Nfa<Unsanitary> = serde_json::from_str(
+        r#"{
+            "start": "1",
+            "alphabet": ["a", "b"],
+            "nodes": {
+                "1": { "a": ["1", "2"], "b": ["1"] },
+                "2": { "a": ["3"], "b": ["3"] },
+                "3": {}
+            },
+            "final_states": ["3"]
+        }"#,
+    ).unwrap();
+    let nfa = nfa.check().unwrap();
+    let dfa = nfa.clone().make_deterministic();
+    let wfa = Wfa::from(nfa)
+        .make_deterministic(&Limits::default())
+        .unwrap();
+
+    let states: HashSet<&String> = wfa.states().collect();
+    let expected: HashSet<&String> = dfa.states()
+        .filter(|state| state.as_str() != "dead state")
+        .collect();
+    assert_eq!(states, expected);
+    let final_states: HashSet<&String> = wfa.final_weights().keys().collect();
+    assert_eq!(final_states, dfa.final_states().iter().collect());
+}
+
+#[test]
+fn wfa_not_determinisable() {
+    // the cheapest path depends on the symbol after all of the "a"s, and the difference between
+    // the paths grows with every "a"
+    let wfa: Wfa<Unsanitary, Tropical> = serde_json::from_str(
+        r#"{
+            "start": "1",
+            "alphabet": ["a", "b", "c"],
+            "nodes": {
+                "1": { "a": { "2": 1, "3": 2 } },
+                "2": { "a": { "2": 1 }, "b": { "4": 0 } },
+                "3": { "a": { "3": 3 }, "c": { "4": 0 } },
+                "4": {}
+            },
+            "final_weights": { "4": 0 }
+        }"#,
+    ).unwrap();
+    let limits = Limits {
+        max_dfa_states: Some(20),
+        ..Limits::default()
+    };
+    match wfa.check().unwrap().make_deterministic(&limits).unwrap_err() {
+        NfaError::LimitExceeded(Limit::DfaStates(20)) => {}
+        err @ _ => panic!("{:?}", err),
+    }
+}
@@ -113,7 +113,7 @@ update msg model =
     case msg of
         ConvertToDFA ->
             { model | loading = True }
-                ! [ Http.post "/submit" (Http.jsonBody (encodeFA model.currentFA)) faDecoder
+                ! [ Http.post "/api/v1/submit" (Http.jsonBody (encodeFA model.currentFA)) faDecoder
                         |> Http.send ConvertToDFAResult
                   ]
 